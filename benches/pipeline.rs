@@ -0,0 +1,90 @@
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+use criterion::{criterion_group, criterion_main, Criterion};
+use rust_decimal_macros::dec;
+use mach_rs::{Asset, Command, Engine, EngineOutput, Exchange, Instrument, Order, OrderSide, PipelineBuilder};
+use mach_rs::actor::OUTPUT_CAPACITY;
+use mach_rs::observer::{BalanceEvent, EventListener, OrderEvent};
+
+const ORDERS: u64 = 10_000;
+
+// 两个用户交替挂同价买卖单，每两条指令成交一次
+fn commands() -> Vec<Command> {
+    let (btc, usdt) = (Asset::from("BTC"), Asset::from("USDT"));
+    let mut commands = vec![
+        Command::Deposit { user_id: 1, asset: btc, amount: dec!(1_000_000) },
+        Command::Deposit { user_id: 2, asset: usdt, amount: dec!(1_000_000_000) },
+    ];
+    for id in 0..ORDERS {
+        let (user_id, side) = if id % 2 == 0 { (1, OrderSide::Ask) } else { (2, OrderSide::Bid) };
+        commands.push(Command::Place(Order { id, user_id, price: dec!(100), quantity: dec!(1), side }));
+    }
+    commands
+}
+
+fn exchange() -> Exchange {
+    Exchange::new(Instrument::new(Asset::from("BTC"), Asset::from("USDT")), 8, 2)
+}
+
+// 与 Engine 的输出通道做同样的事: 克隆事件发送给另一个线程
+#[derive(Clone)]
+struct Forward(SyncSender<EngineOutput>);
+
+impl EventListener for Forward {
+    fn on_balance(&mut self, event: &BalanceEvent) {
+        let _ = self.0.send(EngineOutput::Balance(event.clone()));
+    }
+
+    fn on_order(&mut self, event: &OrderEvent) {
+        let _ = self.0.send(EngineOutput::Order(event.clone()));
+    }
+}
+
+// 两种实现都由另一个线程读完全部输出
+fn drain(outputs: Receiver<EngineOutput>) -> JoinHandle<usize> {
+    thread::spawn(move || outputs.iter().count())
+}
+
+fn benchmark_pipeline(c: &mut Criterion) {
+    let commands = commands();
+    let mut group = c.benchmark_group("place_10000_orders");
+
+    group.bench_function("channel_actor", |b| {
+        b.iter(|| {
+            let (engine, outputs) = Engine::spawn(exchange(), 1024);
+            let reader = drain(outputs);
+            let handle = engine.handle();
+            for command in &commands {
+                handle.submit(command.clone()).unwrap();
+            }
+            drop(handle);
+            drop(engine.shutdown());
+            reader.join().unwrap()
+        })
+    });
+
+    group.bench_function("ring_buffer", |b| {
+        b.iter(|| {
+            let (sender, outputs) = mpsc::sync_channel(OUTPUT_CAPACITY);
+            let reader = drain(outputs);
+            let mut exchange = exchange();
+            exchange.add_listener(Forward(sender.clone()));
+            let mut pipeline = PipelineBuilder::new(exchange, 1024)
+                .consumer(move |p| {
+                    let processed = EngineOutput::Processed { seq: p.seq, command: p.command.clone(), outcome: p.outcome.clone() };
+                    let _ = sender.send(processed);
+                })
+                .spawn();
+            for command in &commands {
+                pipeline.publish(command.clone()).unwrap();
+            }
+            drop(pipeline.shutdown());
+            reader.join().unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_pipeline);
+criterion_main!(benches);
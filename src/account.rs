@@ -1,104 +1,689 @@
-use std::collections::HashMap;
-use crate::types::{Asset, UserID};
+use std::collections::{BTreeSet, HashMap};
+use crate::ledger::{EntryReason, EntryRef, Ledger, LedgerAccount, LedgerError};
+use crate::observer::{BalanceEvent, BalanceEventKind, EventListener, Listeners};
+use crate::registry::{AssetOperation, AssetRegistry};
+use crate::snapshot::{Decoder, Encoder};
+use crate::types::{Asset, DepositID, OrderID, TradeID, TransferID, UserID, WithdrawalID};
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
+use thiserror::Error;
 
-#[derive(Debug,Default, Clone)]
+// 分页查询结果，next_cursor 为 None 表示没有下一页
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<UserID>,
+}
+
+#[derive(Debug,Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Balance {
     pub available: Decimal,
     pub frozen: Decimal,
+    pub withdraw_hold: Decimal, // 提现处理中，已从可用中扣出
+    pub pending_deposit: Decimal, // 充值确认中，尚不可用
+}
+
+impl Balance {
+    // 该用户在系统内持有的全部资金
+    pub fn total(&self) -> Decimal {
+        self.available + self.frozen + self.withdraw_hold + self.pending_deposit
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Error)]
 pub enum AccountError {
-    UserNotFound,
-    AssetNotFound,
-    InsufficientAvailable,
-    InsufficientFrozen,
-    Overflow, // 极其罕见，但理论上存在
+    #[error("用户 {user_id} 不存在")]
+    UserNotFound { user_id: UserID },
+    #[error("用户 {user_id} 没有 {asset} 资产")]
+    AssetNotFound { user_id: UserID, asset: Asset },
+    #[error("用户 {user_id} 可用 {asset} 不足: 需要 {requested}, 可用 {available}")]
+    InsufficientAvailable { user_id: UserID, asset: Asset, requested: Decimal, available: Decimal },
+    #[error("用户 {user_id} 冻结 {asset} 不足: 需要 {requested}, 冻结 {frozen}")]
+    InsufficientFrozen { user_id: UserID, asset: Asset, requested: Decimal, frozen: Decimal },
+    #[error("用户 {user_id} 提现冻结 {asset} 不足: 需要 {requested}, 冻结 {held}")]
+    InsufficientHold { user_id: UserID, asset: Asset, requested: Decimal, held: Decimal },
+    #[error("用户 {user_id} 确认中的 {asset} 充值不足: 需要 {requested}, 确认中 {pending}")]
+    InsufficientPendingDeposit { user_id: UserID, asset: Asset, requested: Decimal, pending: Decimal },
+    #[error("用户 {user_id} 的 {asset} 金额无效: {amount}")]
+    InvalidAmount { user_id: UserID, asset: Asset, amount: Decimal },
+    #[error("用户 {user_id} 不能给自己转账")]
+    SelfTransfer { user_id: UserID },
+    #[error("用户 {sub} 不是 {parent} 的子账户")]
+    NotSubAccount { parent: UserID, sub: UserID },
+    #[error("用户 {sub} 已经是其他账户的子账户，或会形成循环")]
+    InvalidSubAccount { sub: UserID },
+    #[error("用户 {user_id} 的 {asset} 余额溢出")]
+    Overflow { user_id: UserID, asset: Asset }, // 极其罕见，但理论上存在
+    #[error("用户 {user_id} 操作的资产 {asset} 未注册")]
+    UnknownAsset { user_id: UserID, asset: Asset },
+    #[error("资产 {asset} 当前不允许{operation} (用户 {user_id})")]
+    AssetNotAllowed { user_id: UserID, asset: Asset, operation: AssetOperation },
+    #[error("用户 {user_id} 的 {asset} 金额 {amount} 超过精度 {precision} 位小数")]
+    ExcessPrecision { user_id: UserID, asset: Asset, amount: Decimal, precision: u32 },
 }
 
+#[derive(Default)]
 pub struct AccountManager {
     accounts: HashMap<UserID,HashMap<Asset,Balance>>,
+    // 资产 -> 持有该资产的用户，用于按资产分页查询
+    holders: HashMap<Asset, BTreeSet<UserID>>,
+    // 子账户 -> 母账户
+    parents: HashMap<UserID, UserID>,
+    // 母账户 -> 子账户列表
+    sub_accounts: HashMap<UserID, Vec<UserID>>,
+    // 未配置时不校验资产，兼容旧用法
+    registry: Option<AssetRegistry>,
+    ledger: Ledger,
+    listeners: Listeners,
 }
 impl AccountManager {
     pub fn new() -> Self {
         Self {
             accounts: HashMap::new(),
+            holders: HashMap::new(),
+            parents: HashMap::new(),
+            sub_accounts: HashMap::new(),
+            registry: None,
+            ledger: Ledger::new(),
+            listeners: Listeners::default(),
+        }
+    }
+
+    // 只接受注册表中的资产，并按资产状态与精度校验新发起的业务
+    pub fn with_registry(registry: AssetRegistry) -> Self {
+        Self { registry: Some(registry), ..Self::new() }
+    }
+
+    pub fn registry(&self) -> Option<&AssetRegistry> {
+        self.registry.as_ref()
+    }
+
+    // 运行中调整资产状态 (例如暂停提现)
+    pub fn registry_mut(&mut self) -> Option<&mut AssetRegistry> {
+        self.registry.as_mut()
+    }
+
+    pub fn add_listener(&mut self, listener: Box<dyn EventListener>) {
+        self.listeners.add(listener);
+    }
+
+    // 校验资产已注册、状态允许该业务，且金额 (如有) 不超过资产精度
+    // 撤单解冻、成交扣款、提现退回等收尾操作不做状态校验，避免资金被卡住
+    fn check_asset(&self, user_id: UserID, asset: Asset, operation: Option<AssetOperation>, amount: Option<Decimal>) -> Result<(), AccountError> {
+        let Some(registry) = &self.registry else {
+            return Ok(());
+        };
+        let info = registry.get(asset).ok_or(AccountError::UnknownAsset { user_id, asset })?;
+        if let Some(operation) = operation
+            && !info.status.allows(operation)
+        {
+            return Err(AccountError::AssetNotAllowed { user_id, asset, operation });
+        }
+        if let Some(amount) = amount
+            && !info.fits_precision(amount)
+        {
+            return Err(AccountError::ExcessPrecision { user_id, asset, amount, precision: info.precision });
         }
+        Ok(())
     }
 
+    fn notify(&mut self, kind: BalanceEventKind, user_id: UserID, asset: Asset, amount: Decimal) {
+        if self.listeners.is_empty() {
+            return;
+        }
+        let (available, frozen) = self.get_balance(user_id, asset);
+        self.listeners.balance(BalanceEvent { kind, user_id, asset, amount, available, frozen });
+    }
+
+
+    // 取余额，不存在时创建并登记持有人索引
+    fn balance_entry(&mut self, user_id: UserID, asset: Asset) -> &mut Balance {
+        let assets = self.accounts.entry(user_id).or_default();
+        if !assets.contains_key(&asset) {
+            self.holders.entry(asset).or_default().insert(user_id);
+        }
+        assets.entry(asset).or_default()
+    }
 
     fn get_balance_mut(&mut self, user_id: &UserID, asset: Asset) -> Result<&mut Balance, AccountError> {
-        let user_accounts = self.accounts.get_mut(user_id).ok_or(AccountError::UserNotFound)?;
-        let balance = user_accounts.get_mut(&asset).ok_or(AccountError::AssetNotFound)?;
+        let user_accounts = self.accounts.get_mut(user_id).ok_or(AccountError::UserNotFound { user_id: *user_id })?;
+        let balance = user_accounts.get_mut(&asset).ok_or(AccountError::AssetNotFound { user_id: *user_id, asset })?;
         Ok(balance)
     }
 
     // 充值
     pub fn deposit(&mut self, user_id: UserID, asset: Asset, amount: Decimal) -> Result<(), AccountError> {
-        let balance = self.accounts
-            .entry(user_id).or_default()
-            .entry(asset.clone()).or_default(); // 这里 clone 是为了后面打印日志，如果追求极致性能可以优化
-
-        balance.available = balance.available.checked_add(amount).ok_or(AccountError::Overflow)?;
+        if amount <= dec!(0) {
+            return Err(AccountError::InvalidAmount { user_id, asset, amount });
+        }
+        self.credit(user_id, asset, amount, EntryReason::Deposit, EntryRef::None)
+    }
 
-        println!("用户 {} 充值 {} {}, 当前可用: {}", user_id, amount, asset, balance.available);
-        Ok(())
+    // 充值入账，分录关联充值单
+    pub(crate) fn deposit_with_ref(&mut self, user_id: UserID, asset: Asset, amount: Decimal, id: DepositID) -> Result<(), AccountError> {
+        if amount <= dec!(0) {
+            return Err(AccountError::InvalidAmount { user_id, asset, amount });
+        }
+        self.credit(user_id, asset, amount, EntryReason::Deposit, EntryRef::Deposit(id))
     }
 
     // 尝试冻结资金 (下单前预扣)
     pub fn try_freeze(&mut self, user_id: UserID, asset: Asset, amount: Decimal) -> Result<(), AccountError> {
+        self.freeze(user_id, asset, amount, EntryRef::None)
+    }
+
+    // 为指定订单冻结资金，分录关联订单号
+    pub fn freeze_for_order(&mut self, user_id: UserID, asset: Asset, amount: Decimal, order_id: OrderID) -> Result<(), AccountError> {
+        self.freeze(user_id, asset, amount, EntryRef::Order(order_id))
+    }
+
+    // 撤单：解冻资金
+    pub fn unlock(&mut self, user_id: UserID, asset: Asset, amount: Decimal) -> Result<(), AccountError> {
+        self.release(user_id, asset, amount, EntryRef::None)
+    }
+
+    // 撤销指定订单后解冻资金
+    pub fn unlock_for_order(&mut self, user_id: UserID, asset: Asset, amount: Decimal, order_id: OrderID) -> Result<(), AccountError> {
+        self.release(user_id, asset, amount, EntryRef::Order(order_id))
+    }
+
+    // 成交：扣除冻结资金
+    pub fn confirm_trade(&mut self, user_id: UserID, asset: Asset, amount: Decimal) -> Result<(), AccountError> {
+        self.debit_frozen(user_id, asset, amount, EntryRef::None)
+    }
+
+    // 成交结算: 扣除付款方冻结资金，分录关联成交号
+    pub fn settle_debit(&mut self, user_id: UserID, asset: Asset, amount: Decimal, trade_id: TradeID) -> Result<(), AccountError> {
+        self.debit_frozen(user_id, asset, amount, EntryRef::Trade(trade_id))
+    }
+
+    // 成交结算: 收款方入账
+    pub fn settle_credit(&mut self, user_id: UserID, asset: Asset, amount: Decimal, trade_id: TradeID) -> Result<(), AccountError> {
+        self.credit(user_id, asset, amount, EntryReason::TradeCredit, EntryRef::Trade(trade_id))
+    }
+
+    // 成交结算前的整体校验: frozen_out 为每个 (用户, 资产) 将从冻结中扣除或退回的总额，
+    // to_available 为将计入可用余额的总额 (入账与退回)。通过后逐笔执行不会失败
+    pub(crate) fn check_settlement(
+        &self,
+        frozen_out: &HashMap<(UserID, Asset), Decimal>,
+        to_available: &HashMap<(UserID, Asset), Decimal>,
+    ) -> Result<(), AccountError> {
+        for (&(user_id, asset), &amount) in frozen_out {
+            if amount <= dec!(0) {
+                continue;
+            }
+            let balance = self.balance(user_id, asset)?;
+            if balance.frozen < amount {
+                return Err(AccountError::InsufficientFrozen { user_id, asset, requested: amount, frozen: balance.frozen });
+            }
+        }
+        for (&(user_id, asset), &amount) in to_available {
+            self.check_asset(user_id, asset, None, None)?;
+            let available = self.balance(user_id, asset).map_or(dec!(0), |b| b.available);
+            available.checked_add(amount).ok_or(AccountError::Overflow { user_id, asset })?;
+        }
+        Ok(())
+    }
+
+    // 收取手续费: 从可用余额扣到手续费科目
+    pub fn charge_fee(&mut self, user_id: UserID, asset: Asset, amount: Decimal, trade_id: TradeID) -> Result<(), AccountError> {
+        let balance = self.get_balance_mut(&user_id, asset)?;
+        if balance.available < amount {
+            return Err(AccountError::InsufficientAvailable { user_id, asset, requested: amount, available: balance.available });
+        }
+        balance.available -= amount;
+
+        self.ledger.record(EntryReason::Fee, EntryRef::Trade(trade_id), asset, amount, LedgerAccount::Available(user_id), LedgerAccount::Fees);
+        self.notify(BalanceEventKind::Fee, user_id, asset, amount);
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), err))]
+    fn credit(&mut self, user_id: UserID, asset: Asset, amount: Decimal, reason: EntryReason, reference: EntryRef) -> Result<(), AccountError> {
+        match reason {
+            EntryReason::TradeCredit => self.check_asset(user_id, asset, None, None)?,
+            _ => self.check_asset(user_id, asset, Some(AssetOperation::Deposit), Some(amount))?,
+        }
+        let balance = self.balance_entry(user_id, asset);
+
+        balance.available = balance.available.checked_add(amount).ok_or(AccountError::Overflow { user_id, asset })?;
+
+        let (from, kind) = match reason {
+            EntryReason::TradeCredit => (LedgerAccount::Clearing, BalanceEventKind::TradeCredit),
+            _ => (LedgerAccount::External, BalanceEventKind::Deposit),
+        };
+        self.ledger.record(reason, reference, asset, amount, from, LedgerAccount::Available(user_id));
+        self.notify(kind, user_id, asset, amount);
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), err))]
+    fn freeze(&mut self, user_id: UserID, asset: Asset, amount: Decimal, reference: EntryRef) -> Result<(), AccountError> {
+        self.check_asset(user_id, asset, Some(AssetOperation::Trade), None)?;
         let balance = self.get_balance_mut(&user_id, asset)?;
 
         // 检查余额是否足够
         if balance.available < amount {
-            return Err(AccountError::InsufficientAvailable);
+            return Err(AccountError::InsufficientAvailable { user_id, asset, requested: amount, available: balance.available });
         }
 
-        balance.available = balance.available.checked_sub(amount).ok_or(AccountError::Overflow)?;
-        balance.frozen = balance.frozen.checked_add(amount).ok_or(AccountError::Overflow)?;
+        let frozen = balance.frozen.checked_add(amount).ok_or(AccountError::Overflow { user_id, asset })?;
+        balance.available -= amount;
+        balance.frozen = frozen;
 
-        println!("用户 {} 冻结 {} {} 成功", user_id, amount, asset);
+        self.ledger.record(EntryReason::Freeze, reference, asset, amount, LedgerAccount::Available(user_id), LedgerAccount::Frozen(user_id));
+        self.notify(BalanceEventKind::Freeze, user_id, asset, amount);
         Ok(())
     }
 
-    // 撤单：解冻资金
-    pub fn unlock(&mut self, user_id: UserID, asset: Asset, amount: Decimal) -> Result<(), AccountError> {
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), err))]
+    fn release(&mut self, user_id: UserID, asset: Asset, amount: Decimal, reference: EntryRef) -> Result<(), AccountError> {
         let balance = self.get_balance_mut(&user_id, asset)?;
 
         if balance.frozen < amount {
-            eprintln!("CRITICAL: 试图解冻超出冻结金额!");
-            return Err(AccountError::InsufficientFrozen);
+            return Err(AccountError::InsufficientFrozen { user_id, asset, requested: amount, frozen: balance.frozen });
         }
 
-        balance.frozen = balance.frozen.checked_sub(amount).ok_or(AccountError::Overflow)?;
-        balance.available = balance.available.checked_add(amount).ok_or(AccountError::Overflow)?;
+        let available = balance.available.checked_add(amount).ok_or(AccountError::Overflow { user_id, asset })?;
+        balance.frozen -= amount;
+        balance.available = available;
 
-        println!("用户 {} 解冻 {} {}, 资金回退", user_id, amount, asset);
+        self.ledger.record(EntryReason::Unlock, reference, asset, amount, LedgerAccount::Frozen(user_id), LedgerAccount::Available(user_id));
+        self.notify(BalanceEventKind::Unlock, user_id, asset, amount);
         Ok(())
     }
 
-    // 成交：扣除冻结资金
-    pub fn confirm_trade(&mut self, user_id: UserID, asset: Asset, amount: Decimal) -> Result<(), AccountError> {
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), err))]
+    fn debit_frozen(&mut self, user_id: UserID, asset: Asset, amount: Decimal, reference: EntryRef) -> Result<(), AccountError> {
         let balance = self.get_balance_mut(&user_id, asset)?;
 
         if balance.frozen < amount {
-            eprintln!("CRITICAL: 试图扣除超出冻结金额!");
-            return Err(AccountError::InsufficientFrozen);
+            return Err(AccountError::InsufficientFrozen { user_id, asset, requested: amount, frozen: balance.frozen });
+        }
+
+        balance.frozen -= amount;
+
+        self.ledger.record(EntryReason::TradeDebit, reference, asset, amount, LedgerAccount::Frozen(user_id), LedgerAccount::Clearing);
+        self.notify(BalanceEventKind::ConfirmTrade, user_id, asset, amount);
+        Ok(())
+    }
+
+    // 用户间划转 (如 OTC 结算): 一步完成扣款与入账，任一方失败则两边都不变
+    pub fn transfer(&mut self, from: UserID, to: UserID, asset: Asset, amount: Decimal, transfer_id: TransferID) -> Result<(), AccountError> {
+        if from == to {
+            return Err(AccountError::SelfTransfer { user_id: from });
+        }
+        if amount <= dec!(0) {
+            return Err(AccountError::InvalidAmount { user_id: from, asset, amount });
+        }
+        self.check_asset(from, asset, Some(AssetOperation::Transfer), Some(amount))?;
+
+        let source = self.get_balance_mut(&from, asset)?;
+        if source.available < amount {
+            return Err(AccountError::InsufficientAvailable { user_id: from, asset, requested: amount, available: source.available });
+        }
+        let target_available = self.accounts
+            .get(&to)
+            .and_then(|assets| assets.get(&asset))
+            .map_or(dec!(0), |b| b.available);
+        let credited = target_available.checked_add(amount).ok_or(AccountError::Overflow { user_id: to, asset })?;
+
+        // 校验通过后再修改
+        self.get_balance_mut(&from, asset)?.available -= amount;
+        self.balance_entry(to, asset).available = credited;
+
+        self.ledger.record(EntryReason::Transfer, EntryRef::Transfer(transfer_id), asset, amount, LedgerAccount::Available(from), LedgerAccount::Available(to));
+        self.notify(BalanceEventKind::TransferOut, from, asset, amount);
+        self.notify(BalanceEventKind::TransferIn, to, asset, amount);
+        Ok(())
+    }
+
+    // 清算科目中剩余的零头 (结算扣款向上取整、入账向下取整的差额)
+    pub fn clearing_dust(&self, asset: Asset) -> Decimal {
+        self.ledger.balance_of(LedgerAccount::Clearing, asset)
+    }
+
+    // 把清算零头归集到平台账户，返回归集的金额
+    pub fn sweep_dust(&mut self, asset: Asset, house: UserID) -> Result<Decimal, AccountError> {
+        let dust = self.clearing_dust(asset);
+        if dust <= dec!(0) {
+            return Ok(dec!(0));
         }
+        self.check_asset(house, asset, None, None)?;
+        let balance = self.balance_entry(house, asset);
+        balance.available = balance.available.checked_add(dust).ok_or(AccountError::Overflow { user_id: house, asset })?;
 
-        balance.frozen = balance.frozen.checked_sub(amount).ok_or(AccountError::Overflow)?;
+        self.ledger.record(EntryReason::DustSweep, EntryRef::None, asset, dust, LedgerAccount::Clearing, LedgerAccount::Available(house));
+        self.notify(BalanceEventKind::DustSweep, house, asset, dust);
+        Ok(dust)
+    }
+
+    // 挂载子账户，子账户只能有一个母账户，且不能形成循环
+    pub fn add_sub_account(&mut self, parent: UserID, sub: UserID) -> Result<(), AccountError> {
+        if parent == sub || self.parents.contains_key(&sub) {
+            return Err(AccountError::InvalidSubAccount { sub });
+        }
+        let mut ancestor = Some(parent);
+        while let Some(id) = ancestor {
+            if id == sub {
+                return Err(AccountError::InvalidSubAccount { sub });
+            }
+            ancestor = self.parents.get(&id).copied();
+        }
+
+        self.parents.insert(sub, parent);
+        self.sub_accounts.entry(parent).or_default().push(sub);
+        Ok(())
+    }
+
+    pub fn sub_accounts(&self, parent: UserID) -> &[UserID] {
+        self.sub_accounts.get(&parent).map_or(&[], |v| v.as_slice())
+    }
+
+    pub fn parent_of(&self, sub: UserID) -> Option<UserID> {
+        self.parents.get(&sub).copied()
+    }
+
+    // 母子账户之间划转，方向任意，但双方必须是直接的母子关系
+    pub fn sub_account_transfer(&mut self, from: UserID, to: UserID, asset: Asset, amount: Decimal, transfer_id: TransferID) -> Result<(), AccountError> {
+        let related = self.parent_of(to) == Some(from) || self.parent_of(from) == Some(to);
+        if !related {
+            let (parent, sub) = if self.parent_of(from).is_none() { (from, to) } else { (to, from) };
+            return Err(AccountError::NotSubAccount { parent, sub });
+        }
+        self.transfer(from, to, asset, amount, transfer_id)
+    }
+
+    // 提现申请: 可用 -> 提现冻结
+    pub(crate) fn hold_withdrawal(&mut self, user_id: UserID, asset: Asset, amount: Decimal, id: WithdrawalID) -> Result<(), AccountError> {
+        self.check_asset(user_id, asset, Some(AssetOperation::Withdraw), Some(amount))?;
+        let balance = self.get_balance_mut(&user_id, asset)?;
+        if balance.available < amount {
+            return Err(AccountError::InsufficientAvailable { user_id, asset, requested: amount, available: balance.available });
+        }
+        let hold = balance.withdraw_hold.checked_add(amount).ok_or(AccountError::Overflow { user_id, asset })?;
+        balance.available -= amount;
+        balance.withdraw_hold = hold;
+
+        self.ledger.record(EntryReason::WithdrawalHold, EntryRef::Withdrawal(id), asset, amount, LedgerAccount::Available(user_id), LedgerAccount::WithdrawHold(user_id));
+        self.notify(BalanceEventKind::WithdrawalHold, user_id, asset, amount);
+        Ok(())
+    }
+
+    // 提现被拒绝: 提现冻结 -> 可用
+    pub(crate) fn release_withdrawal(&mut self, user_id: UserID, asset: Asset, amount: Decimal, id: WithdrawalID) -> Result<(), AccountError> {
+        let balance = self.take_hold(user_id, asset, amount)?;
+        balance.available = balance.available.checked_add(amount).ok_or(AccountError::Overflow { user_id, asset })?;
 
-        println!("用户 {} 支出 {} {}, 交易完成", user_id, amount, asset);
+        self.ledger.record(EntryReason::WithdrawalRelease, EntryRef::Withdrawal(id), asset, amount, LedgerAccount::WithdrawHold(user_id), LedgerAccount::Available(user_id));
+        self.notify(BalanceEventKind::WithdrawalRelease, user_id, asset, amount);
         Ok(())
     }
 
-    pub fn get_balance(&mut self, user_id: UserID, asset: Asset) -> (Decimal,Decimal) {
-        let balance = self.get_balance_mut(&user_id, asset);
-        match balance {
+    // 提现完成: 扣除提现冻结，amount - fee 流出系统，fee 进入手续费科目
+    pub(crate) fn complete_withdrawal(&mut self, user_id: UserID, asset: Asset, amount: Decimal, fee: Decimal, id: WithdrawalID) -> Result<(), AccountError> {
+        self.take_hold(user_id, asset, amount)?;
+
+        self.ledger.record(EntryReason::Withdrawal, EntryRef::Withdrawal(id), asset, amount - fee, LedgerAccount::WithdrawHold(user_id), LedgerAccount::External);
+        if !fee.is_zero() {
+            self.ledger.record(EntryReason::Fee, EntryRef::Withdrawal(id), asset, fee, LedgerAccount::WithdrawHold(user_id), LedgerAccount::Fees);
+        }
+        self.notify(BalanceEventKind::Withdrawal, user_id, asset, amount);
+        Ok(())
+    }
+
+    // 检测到充值但确认数不足: 外部 -> 充值确认中
+    pub(crate) fn credit_pending_deposit(&mut self, user_id: UserID, asset: Asset, amount: Decimal, id: DepositID) -> Result<(), AccountError> {
+        if amount <= dec!(0) {
+            return Err(AccountError::InvalidAmount { user_id, asset, amount });
+        }
+        self.check_asset(user_id, asset, Some(AssetOperation::Deposit), Some(amount))?;
+        let balance = self.balance_entry(user_id, asset);
+        balance.pending_deposit = balance.pending_deposit.checked_add(amount).ok_or(AccountError::Overflow { user_id, asset })?;
+
+        self.ledger.record(EntryReason::DepositPending, EntryRef::Deposit(id), asset, amount, LedgerAccount::External, LedgerAccount::PendingDeposit(user_id));
+        self.notify(BalanceEventKind::DepositPending, user_id, asset, amount);
+        Ok(())
+    }
+
+    // 确认数达标: 充值确认中 -> 可用
+    pub(crate) fn confirm_pending_deposit(&mut self, user_id: UserID, asset: Asset, amount: Decimal, id: DepositID) -> Result<(), AccountError> {
+        let balance = self.take_pending_deposit(user_id, asset, amount)?;
+        balance.available = balance.available.checked_add(amount).ok_or(AccountError::Overflow { user_id, asset })?;
+
+        self.ledger.record(EntryReason::Deposit, EntryRef::Deposit(id), asset, amount, LedgerAccount::PendingDeposit(user_id), LedgerAccount::Available(user_id));
+        self.notify(BalanceEventKind::Deposit, user_id, asset, amount);
+        Ok(())
+    }
+
+    // 充值被回滚 (链上孤块等): 确认中的直接撤回，已入账的从可用余额扣回
+    pub(crate) fn reverse_deposit(&mut self, user_id: UserID, asset: Asset, amount: Decimal, pending: bool, id: DepositID) -> Result<(), AccountError> {
+        let from = if pending {
+            self.take_pending_deposit(user_id, asset, amount)?;
+            LedgerAccount::PendingDeposit(user_id)
+        } else {
+            let balance = self.get_balance_mut(&user_id, asset)?;
+            if balance.available < amount {
+                return Err(AccountError::InsufficientAvailable { user_id, asset, requested: amount, available: balance.available });
+            }
+            balance.available -= amount;
+            LedgerAccount::Available(user_id)
+        };
+
+        self.ledger.record(EntryReason::DepositReversal, EntryRef::Deposit(id), asset, amount, from, LedgerAccount::External);
+        self.notify(BalanceEventKind::DepositReversal, user_id, asset, amount);
+        Ok(())
+    }
+
+    fn take_pending_deposit(&mut self, user_id: UserID, asset: Asset, amount: Decimal) -> Result<&mut Balance, AccountError> {
+        let balance = self.get_balance_mut(&user_id, asset)?;
+        if balance.pending_deposit < amount {
+            return Err(AccountError::InsufficientPendingDeposit { user_id, asset, requested: amount, pending: balance.pending_deposit });
+        }
+        balance.pending_deposit -= amount;
+        Ok(balance)
+    }
+
+    fn take_hold(&mut self, user_id: UserID, asset: Asset, amount: Decimal) -> Result<&mut Balance, AccountError> {
+        let balance = self.get_balance_mut(&user_id, asset)?;
+        if balance.withdraw_hold < amount {
+            return Err(AccountError::InsufficientHold { user_id, asset, requested: amount, held: balance.withdraw_hold });
+        }
+        balance.withdraw_hold -= amount;
+        Ok(balance)
+    }
+
+    // 兼容旧接口: 不存在时返回 (0, 0)，需要区分时使用 balance()
+    pub fn get_balance(&self, user_id: UserID, asset: Asset) -> (Decimal,Decimal) {
+        match self.balance(user_id, asset) {
             Ok(balance) => (balance.available, balance.frozen),
             Err(_) => (dec!(0), dec!(0))
         }
     }
-}
\ No newline at end of file
+
+    // 只读查询: 用户或资产不存在时返回错误
+    pub fn balance(&self, user_id: UserID, asset: Asset) -> Result<&Balance, AccountError> {
+        let user_accounts = self.accounts.get(&user_id).ok_or(AccountError::UserNotFound { user_id })?;
+        user_accounts.get(&asset).ok_or(AccountError::AssetNotFound { user_id, asset })
+    }
+
+    // 用户的全部资产余额，按资产排序
+    pub fn balances_of(&self, user_id: UserID) -> Result<Vec<(Asset, &Balance)>, AccountError> {
+        let user_accounts = self.accounts.get(&user_id).ok_or(AccountError::UserNotFound { user_id })?;
+        let mut list: Vec<(Asset, &Balance)> = user_accounts.iter().map(|(a, b)| (*a, b)).collect();
+        list.sort_by_key(|(a, _)| *a);
+        Ok(list)
+    }
+
+    // 某资产的全部持有人，按用户 ID 升序分页: after 为上一页的 next_cursor
+    pub fn holders(&self, asset: Asset, after: Option<UserID>, limit: usize) -> Page<(UserID, &Balance)> {
+        let Some(users) = self.holders.get(&asset) else {
+            return Page { items: Vec::new(), next_cursor: None };
+        };
+        let range = match after {
+            Some(cursor) => users.range((std::ops::Bound::Excluded(cursor), std::ops::Bound::Unbounded)),
+            None => users.range(..),
+        };
+
+        let mut items = Vec::with_capacity(limit.min(users.len()));
+        let mut iter = range.filter_map(|u| self.accounts.get(u)?.get(&asset).map(|b| (*u, b)));
+        for item in iter.by_ref().take(limit) {
+            items.push(item);
+        }
+        let next_cursor = match iter.next() {
+            Some(_) => items.last().map(|(u, _)| *u),
+            None => None,
+        };
+        Page { items, next_cursor }
+    }
+
+    // 遍历全部余额 (用户, 资产, 余额)
+    pub(crate) fn iter_balances(&self) -> impl Iterator<Item = (UserID, Asset, &Balance)> {
+        self.accounts
+            .iter()
+            .flat_map(|(user_id, assets)| assets.iter().map(move |(asset, b)| (*user_id, *asset, b)))
+    }
+
+    // 快照: 余额、母子账户与资产注册表。账本只保存系统科目余额，恢复时连同用户余额记为期初分录
+    pub(crate) fn encode(&self, e: &mut Encoder) {
+        let mut users: Vec<&UserID> = self.accounts.keys().collect();
+        users.sort_unstable();
+        e.len(users.len());
+        for user_id in users {
+            let mut assets: Vec<(&Asset, &Balance)> = self.accounts[user_id].iter().collect();
+            assets.sort_by_key(|(a, _)| **a);
+            e.u64(*user_id);
+            e.len(assets.len());
+            for (asset, b) in assets {
+                e.asset(*asset);
+                for amount in [b.available, b.frozen, b.withdraw_hold, b.pending_deposit] {
+                    e.decimal(amount);
+                }
+            }
+        }
+
+        let mut parents: Vec<(&UserID, &Vec<UserID>)> = self.sub_accounts.iter().collect();
+        parents.sort_by_key(|(p, _)| **p);
+        e.len(parents.len());
+        for (parent, subs) in parents {
+            e.u64(*parent);
+            e.len(subs.len());
+            for sub in subs {
+                e.u64(*sub);
+            }
+        }
+
+        match &self.registry {
+            Some(registry) => {
+                e.u8(1);
+                registry.encode(e);
+            }
+            None => e.u8(0),
+        }
+
+        let mut system: Vec<(u8, Asset, Decimal)> = self.ledger
+            .totals()
+            .iter()
+            .filter_map(|((account, asset), amount)| match account {
+                LedgerAccount::Clearing => Some((0, *asset, *amount)),
+                LedgerAccount::Fees => Some((1, *asset, *amount)),
+                _ => None,
+            })
+            .filter(|(_, _, amount)| !amount.is_zero())
+            .collect();
+        system.sort_by_key(|(kind, asset, _)| (*kind, *asset));
+        e.len(system.len());
+        for (kind, asset, amount) in system {
+            e.u8(kind);
+            e.asset(asset);
+            e.decimal(amount);
+        }
+    }
+
+    pub(crate) fn decode(d: &mut Decoder) -> Option<Self> {
+        let mut account = AccountManager::new();
+        for _ in 0..d.len()? {
+            let user_id = d.u64()?;
+            for _ in 0..d.len()? {
+                let asset = d.asset()?;
+                let balance = Balance {
+                    available: d.decimal()?,
+                    frozen: d.decimal()?,
+                    withdraw_hold: d.decimal()?,
+                    pending_deposit: d.decimal()?,
+                };
+                for (bucket, amount) in [
+                    (LedgerAccount::Available(user_id), balance.available),
+                    (LedgerAccount::Frozen(user_id), balance.frozen),
+                    (LedgerAccount::WithdrawHold(user_id), balance.withdraw_hold),
+                    (LedgerAccount::PendingDeposit(user_id), balance.pending_deposit),
+                ] {
+                    if !amount.is_zero() {
+                        account.ledger.record(EntryReason::Opening, EntryRef::None, asset, amount, LedgerAccount::External, bucket);
+                    }
+                }
+                *account.balance_entry(user_id, asset) = balance;
+            }
+        }
+
+        for _ in 0..d.len()? {
+            let parent = d.u64()?;
+            for _ in 0..d.len()? {
+                let sub = d.u64()?;
+                account.parents.insert(sub, parent);
+                account.sub_accounts.entry(parent).or_default().push(sub);
+            }
+        }
+
+        account.registry = match d.u8()? {
+            0 => None,
+            1 => Some(AssetRegistry::decode(d)?),
+            _ => return None,
+        };
+
+        for _ in 0..d.len()? {
+            let to = match d.u8()? {
+                0 => LedgerAccount::Clearing,
+                1 => LedgerAccount::Fees,
+                _ => return None,
+            };
+            let asset = d.asset()?;
+            let amount = d.decimal()?;
+            account.ledger.record(EntryReason::Opening, EntryRef::None, asset, amount, LedgerAccount::External, to);
+        }
+        Some(account)
+    }
+
+    pub fn ledger(&self) -> &Ledger {
+        &self.ledger
+    }
+
+    // 裁剪分录明细，只保留最近 keep 笔 (例如写出快照之后)，科目余额不受影响
+    pub fn prune_ledger(&mut self, keep: usize) {
+        self.ledger.prune(keep);
+    }
+
+    // 对账: 账本按科目累计的余额必须与用户当前余额一致
+    pub fn verify_ledger(&self) -> Result<(), LedgerError> {
+        let totals = self.ledger.totals();
+        for (user_id, assets) in &self.accounts {
+            for (asset, balance) in assets {
+                for (account, actual) in [
+                    (LedgerAccount::Available(*user_id), balance.available),
+                    (LedgerAccount::Frozen(*user_id), balance.frozen),
+                    (LedgerAccount::WithdrawHold(*user_id), balance.withdraw_hold),
+                    (LedgerAccount::PendingDeposit(*user_id), balance.pending_deposit),
+                ] {
+                    let ledger = totals.get(&(account, *asset)).copied().unwrap_or_default();
+                    if ledger != actual {
+                        return Err(LedgerError::BalanceMismatch { account, asset: *asset, ledger, balance: actual });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+use std::io::Write;
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use thiserror::Error;
+use crate::error::Error;
+use crate::exchange::{Command, Exchange};
+use crate::journal::Journal;
+use crate::observer::{BalanceEvent, EventListener, OrderEvent};
+use crate::types::TradeEvent;
+
+pub type Outcome = Result<Vec<TradeEvent>, Error>;
+
+// 输出通道默认容量
+pub const OUTPUT_CAPACITY: usize = 65_536;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("撮合引擎已停止")]
+pub struct EngineStopped;
+
+// 引擎线程的输出，按产生顺序发布: 一条指令的订单 / 余额事件在它的 Processed 之前
+#[derive(Debug, Clone)]
+pub enum EngineOutput {
+    Order(OrderEvent),
+    Balance(BalanceEvent),
+    Processed { seq: u64, command: Command, outcome: Outcome },
+    // 指令未通过校验 (见 Command::validate)，未分配序号也未写日志
+    Rejected { command: Command, error: Error },
+    // 日志写入失败，指令未执行，引擎线程随即退出
+    Halted { command: Command, error: Error },
+}
+
+enum Request {
+    Command { command: Command, reply: Option<Sender<Outcome>> },
+    Query(Box<dyn FnOnce(&Exchange) + Send>),
+    Stop,
+}
+
+// 输出通道的发送端，引擎线程和事件监听器共用
+// 通道写满说明消费方跟不上: 引擎不等待，直接丢弃发送端，
+// 之后的输出不再发布，消费方读完已缓冲的输出后看到通道断开
+#[derive(Clone)]
+struct Output(Arc<Mutex<Option<SyncSender<EngineOutput>>>>);
+
+impl Output {
+    fn send(&self, output: EngineOutput) {
+        let mut sender = self.0.lock().unwrap();
+        if let Some(tx) = sender.as_ref()
+            && tx.try_send(output).is_err()
+        {
+            *sender = None;
+        }
+    }
+}
+
+// 把事件转发到输出通道
+#[derive(Clone)]
+struct ChannelListener(Output);
+
+impl EventListener for ChannelListener {
+    fn on_balance(&mut self, event: &BalanceEvent) {
+        self.0.send(EngineOutput::Balance(event.clone()));
+    }
+
+    fn on_order(&mut self, event: &OrderEvent) {
+        self.0.send(EngineOutput::Order(event.clone()));
+    }
+}
+
+// 提交指令的句柄，可以克隆给多个网关线程
+// 输入通道有界: 引擎处理不过来时 submit / call 阻塞，形成背压
+#[derive(Clone)]
+pub struct EngineHandle {
+    sender: SyncSender<Request>,
+}
+
+impl EngineHandle {
+    // 只提交，结果从输出通道获取
+    pub fn submit(&self, command: Command) -> Result<(), EngineStopped> {
+        self.send(Request::Command { command, reply: None })
+    }
+
+    // 提交并等待这条指令的处理结果 (同时也会发布到输出通道)
+    pub fn call(&self, command: Command) -> Result<Outcome, EngineStopped> {
+        let (reply, rx) = mpsc::channel();
+        self.send(Request::Command { command, reply: Some(reply) })?;
+        rx.recv().map_err(|_| EngineStopped)
+    }
+
+    // 在引擎线程上只读查询，看到的是之前提交的指令全部处理完后的状态
+    pub fn query<R, F>(&self, f: F) -> Result<R, EngineStopped>
+    where
+        R: Send + 'static,
+        F: FnOnce(&Exchange) -> R + Send + 'static,
+    {
+        let (reply, rx) = mpsc::channel();
+        self.send(Request::Query(Box::new(move |exchange| {
+            let _ = reply.send(f(exchange));
+        })))?;
+        rx.recv().map_err(|_| EngineStopped)
+    }
+
+    fn send(&self, request: Request) -> Result<(), EngineStopped> {
+        self.sender.send(request).map_err(|_| EngineStopped)
+    }
+}
+
+// 单线程撮合引擎: 全部状态由一个线程独占，指令按到达顺序串行处理并分配序号
+pub struct Engine {
+    handle: EngineHandle,
+    thread: JoinHandle<Exchange>,
+}
+
+impl Engine {
+    // capacity 为输入通道容量，输出通道容量为 OUTPUT_CAPACITY
+    pub fn spawn(exchange: Exchange, capacity: usize) -> (Self, Receiver<EngineOutput>) {
+        Self::spawn_with_output_capacity(exchange, capacity, OUTPUT_CAPACITY)
+    }
+
+    // 同 spawn，指定输出通道容量
+    pub fn spawn_with_output_capacity(
+        exchange: Exchange,
+        capacity: usize,
+        output_capacity: usize,
+    ) -> (Self, Receiver<EngineOutput>) {
+        Self::start(exchange, capacity, output_capacity, |exchange, command| {
+            let seq = exchange.last_seq() + 1;
+            Ok((seq, exchange.apply(seq, command)))
+        })
+    }
+
+    // 指令先写入日志再执行，序号由日志分配
+    pub fn spawn_journaled<W: Write + Send + 'static>(
+        exchange: Exchange,
+        mut journal: Journal<W>,
+        capacity: usize,
+    ) -> (Self, Receiver<EngineOutput>) {
+        Self::start(exchange, capacity, OUTPUT_CAPACITY, move |exchange, command| {
+            let seq = journal.append(command)?;
+            Ok((seq, exchange.apply(seq, command)))
+        })
+    }
+
+    fn start<F>(
+        mut exchange: Exchange,
+        capacity: usize,
+        output_capacity: usize,
+        mut process: F,
+    ) -> (Self, Receiver<EngineOutput>)
+    where
+        F: FnMut(&mut Exchange, &Command) -> Result<(u64, Outcome), Error> + Send + 'static,
+    {
+        let (sender, requests) = mpsc::sync_channel(capacity);
+        let (output, outputs) = mpsc::sync_channel(output_capacity);
+        let output = Output(Arc::new(Mutex::new(Some(output))));
+        exchange.add_listener(ChannelListener(output.clone()));
+
+        let thread = thread::spawn(move || {
+            for request in requests {
+                match request {
+                    Request::Command { command, reply } if let Err(error) = command.validate() => {
+                        if let Some(reply) = reply {
+                            let _ = reply.send(Err(error.clone()));
+                        }
+                        output.send(EngineOutput::Rejected { command, error });
+                    }
+                    Request::Command { command, reply } => match process(&mut exchange, &command) {
+                        Ok((seq, outcome)) => {
+                            if let Some(reply) = reply {
+                                let _ = reply.send(outcome.clone());
+                            }
+                            output.send(EngineOutput::Processed { seq, command, outcome });
+                        }
+                        Err(error) => {
+                            output.send(EngineOutput::Halted { command, error });
+                            break;
+                        }
+                    },
+                    Request::Query(f) => f(&exchange),
+                    Request::Stop => break,
+                }
+            }
+            exchange
+        });
+        (Self { handle: EngineHandle { sender }, thread }, outputs)
+    }
+
+    pub fn handle(&self) -> EngineHandle {
+        self.handle.clone()
+    }
+
+    // 处理完已提交的指令后停止，交回状态 (可用于写快照)
+    pub fn shutdown(self) -> Exchange {
+        let _ = self.handle.send(Request::Stop);
+        self.thread.join().expect("撮合线程异常退出")
+    }
+}
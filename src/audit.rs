@@ -0,0 +1,94 @@
+use std::collections::{BTreeMap, HashMap};
+use rust_decimal::Decimal;
+use thiserror::Error;
+use crate::account::AccountManager;
+use crate::settlement::Settlement;
+use crate::ledger::LedgerAccount;
+use crate::types::{Asset, UserID};
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum AuditError {
+    // 用户余额 + 系统科目 与 总供应量不一致: 结算凭空造币或销毁
+    #[error("{asset} 总量不守恒: 供应 {supply}, 实际持有 {held}")]
+    SupplyMismatch { asset: Asset, supply: Decimal, held: Decimal },
+    // 用户冻结资金与结算模块登记的挂单占用不一致
+    #[error("用户 {user_id} 的 {asset} 冻结 {frozen} 与挂单占用 {reserved} 不一致")]
+    ReservationMismatch { user_id: UserID, asset: Asset, frozen: Decimal, reserved: Decimal },
+}
+
+// 全局守恒检查: 由入金 / 出金入口喂给审计器总供应量，与账户实际持有对比
+#[derive(Default)]
+pub struct Auditor {
+    supply: HashMap<Asset, Decimal>,
+}
+
+impl Auditor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on_deposit(&mut self, asset: Asset, amount: Decimal) {
+        *self.supply.entry(asset).or_default() += amount;
+    }
+
+    pub fn on_withdrawal(&mut self, asset: Asset, amount: Decimal) {
+        *self.supply.entry(asset).or_default() -= amount;
+    }
+
+    pub fn supply(&self, asset: Asset) -> Decimal {
+        self.supply.get(&asset).copied().unwrap_or_default()
+    }
+
+    // 所有用户持有的资金 (含提现中、充值确认中)，加上手续费与清算科目，必须等于总供应量
+    pub fn check_conservation(&self, account: &AccountManager) -> Result<(), AuditError> {
+        let mut held: BTreeMap<Asset, Decimal> = BTreeMap::new();
+        for (_, asset, balance) in account.iter_balances() {
+            *held.entry(asset).or_default() += balance.total();
+        }
+        for asset in self.supply.keys() {
+            held.entry(*asset).or_default();
+        }
+
+        let ledger = account.ledger();
+        for (asset, mut total) in held {
+            total += ledger.balance_of(LedgerAccount::Fees, asset);
+            total += ledger.balance_of(LedgerAccount::Clearing, asset);
+            let supply = self.supply(asset);
+            if total != supply {
+                return Err(AuditError::SupplyMismatch { asset, supply, held: total });
+            }
+        }
+        Ok(())
+    }
+
+    // 每个用户的冻结资金必须恰好等于结算模块中各挂单剩余的冻结 (已冻结 - 已扣款)。
+    // 取整与价格改善都由结算模块处理，不能按限价 × 剩余数量推算
+    pub fn check_reservations(&self, account: &AccountManager, settlements: &[&Settlement]) -> Result<(), AuditError> {
+        let mut reserved: HashMap<(UserID, Asset), Decimal> = HashMap::new();
+        for settlement in settlements {
+            for (user_id, asset, amount) in settlement.outstanding() {
+                *reserved.entry((user_id, asset)).or_default() += amount;
+            }
+        }
+
+        let mut balances: Vec<_> = account.iter_balances().collect();
+        balances.sort_by_key(|(user_id, asset, _)| (*user_id, *asset));
+        for (user_id, asset, balance) in balances {
+            let expected = reserved.remove(&(user_id, asset)).unwrap_or_default();
+            if balance.frozen != expected {
+                return Err(AuditError::ReservationMismatch { user_id, asset, frozen: balance.frozen, reserved: expected });
+            }
+        }
+        // 有挂单却没有余额记录
+        if let Some(((user_id, asset), reserved)) = reserved.into_iter().find(|(_, r)| !r.is_zero()) {
+            return Err(AuditError::ReservationMismatch { user_id, asset, frozen: Decimal::ZERO, reserved });
+        }
+        Ok(())
+    }
+
+    // 每条指令执行后或按需调用
+    pub fn audit(&self, account: &AccountManager, settlements: &[&Settlement]) -> Result<(), AuditError> {
+        self.check_conservation(account)?;
+        self.check_reservations(account, settlements)
+    }
+}
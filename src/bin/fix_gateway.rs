@@ -0,0 +1,132 @@
+use std::env;
+use std::fs;
+use std::net::TcpListener;
+use std::process;
+use rust_decimal::Decimal;
+use mach_rs::journal::replay;
+use mach_rs::snapshot;
+use mach_rs::{Asset, Command, Engine, Exchange, FixAcceptor, FixConfig, Instrument, Journal};
+
+const USAGE: &str = "用法: fix_gateway [--listen 127.0.0.1:9878] [--comp-id MACH] [--store ./fix-store] \
+                     [--instrument BTC/USDT] [--precision 8:2] --counterparty <CompID>:<用户ID>[:<密码>]... \
+                     [--deposit <用户ID>:<资产>:<数量>...]\n\
+                     指令日志写在序号目录下的 <CompID>.journal，重启时从同目录的 <CompID>.snapshot (如果有) 与日志恢复，\
+                     FIX 订单的 ClOrdID 由同目录的 <CompID>.orders 恢复，\
+                     发给各对手方的回报保存在 <CompID>-<对手方>.messages 供 ResendRequest 补发；\
+                     --deposit 只在首次启动 (没有日志与快照) 时执行";
+
+struct Config {
+    listen: String,
+    instrument: Instrument,
+    precision: (u32, u32),
+    fix: FixConfig,
+    deposits: Vec<Command>,
+}
+
+fn parse_args() -> Result<Config, String> {
+    let mut config = Config {
+        listen: String::from("127.0.0.1:9878"),
+        instrument: Instrument::new(Asset::from("BTC"), Asset::from("USDT")),
+        precision: (8, 2),
+        fix: FixConfig::new("MACH", "fix-store"),
+        deposits: Vec::new(),
+    };
+    let mut args = env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or(format!("{flag} 缺少参数"))?;
+        let parts: Vec<&str> = value.split([':', '/']).collect();
+        let invalid = || format!("无效的参数 {flag} {value}");
+        match (flag.as_str(), &parts[..]) {
+            ("--listen", _) => config.listen = value.clone(),
+            ("--comp-id", _) => config.fix.comp_id = value.clone(),
+            ("--store", _) => config.fix.store_dir = value.clone().into(),
+            ("--instrument", [base, quote]) => {
+                config.instrument = Instrument::new(Asset::try_new(base).map_err(|_| invalid())?, Asset::try_new(quote).map_err(|_| invalid())?);
+            }
+            ("--precision", [base, quote]) => {
+                config.precision = (base.parse().map_err(|_| invalid())?, quote.parse().map_err(|_| invalid())?);
+            }
+            ("--counterparty", [comp_id, user_id, password @ ..]) if password.len() <= 1 => {
+                let user_id = user_id.parse().map_err(|_| invalid())?;
+                config.fix = config.fix.counterparty(*comp_id, user_id, password.first().copied());
+            }
+            ("--deposit", [user_id, asset, amount]) => config.deposits.push(Command::Deposit {
+                user_id: user_id.parse().map_err(|_| invalid())?,
+                asset: Asset::try_new(asset).map_err(|_| invalid())?,
+                amount: amount.parse::<Decimal>().map_err(|_| invalid())?,
+            }),
+            _ => return Err(invalid()),
+        }
+    }
+    if config.fix.counterparties.is_empty() {
+        return Err(String::from("至少需要一个 --counterparty"));
+    }
+    Ok(config)
+}
+
+fn main() {
+    let config = parse_args().unwrap_or_else(|e| {
+        eprintln!("{e}\n{USAGE}");
+        process::exit(2);
+    });
+
+    let (base_precision, quote_precision) = config.precision;
+    let store = config.fix.store_dir.clone();
+    if let Err(e) = fs::create_dir_all(&store) {
+        eprintln!("无法创建序号目录: {e}");
+        process::exit(1);
+    }
+    let journal_path = store.join(format!("{}.journal", config.fix.comp_id));
+    let (journal, records) = Journal::open(&journal_path).unwrap_or_else(|e| {
+        eprintln!("无法打开日志 {}: {e}", journal_path.display());
+        process::exit(1);
+    });
+    let snapshot_path = store.join(format!("{}.snapshot", config.fix.comp_id));
+    let fresh = records.is_empty() && !snapshot_path.exists();
+    let recovered = if snapshot_path.exists() {
+        snapshot::recover(&snapshot_path, &records).map_err(|e| e.to_string())
+    } else {
+        let mut exchange = Exchange::new(config.instrument, base_precision, quote_precision);
+        replay(&records, &mut exchange).map(|_| exchange).map_err(|e| e.to_string())
+    };
+    let exchange = recovered.unwrap_or_else(|e| {
+        eprintln!("恢复失败: {e}");
+        process::exit(1);
+    });
+    if exchange.instrument() != config.instrument {
+        eprintln!("恢复的交易对 {} 与 --instrument {} 不一致", exchange.instrument(), config.instrument);
+        process::exit(1);
+    }
+    if journal.next_seq() != exchange.last_seq() + 1 {
+        eprintln!("日志下一个序号 {} 与恢复的状态 (序号 {}) 不衔接", journal.next_seq(), exchange.last_seq());
+        process::exit(1);
+    }
+    if !fresh {
+        println!("恢复到序号 {}", exchange.last_seq());
+    }
+
+    let (engine, outputs) = Engine::spawn_journaled(exchange, journal, 1024);
+    let handle = engine.handle();
+    if fresh {
+        for deposit in config.deposits {
+            if let Ok(Err(e)) = handle.call(deposit) {
+                eprintln!("初始充值失败: {e}");
+                process::exit(1);
+            }
+        }
+    }
+
+    let acceptor = FixAcceptor::new(handle, outputs, config.instrument, config.fix).unwrap_or_else(|e| {
+        eprintln!("无法创建序号目录: {e}");
+        process::exit(1);
+    });
+    let listener = TcpListener::bind(&config.listen).unwrap_or_else(|e| {
+        eprintln!("无法监听 {}: {e}", config.listen);
+        process::exit(1);
+    });
+    println!("{} FIX 4.4 接入监听 {}", config.instrument, config.listen);
+    if let Err(e) = acceptor.serve(listener) {
+        eprintln!("FIX 接入退出: {e}");
+        process::exit(1);
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::net::TcpListener;
+use std::process;
+use rust_decimal::Decimal;
+use std::thread;
+use mach_rs::{Asset, Command, Engine, Exchange, Gateway, Instrument, MarketDataServer};
+
+const USAGE: &str = "用法: gateway [--listen 127.0.0.1:7878] [--instrument BTC/USDT] [--precision 8:2] [--market-data 127.0.0.1:7879] \
+                     [--credentials <文件>] [--deposit <用户ID>:<资产>:<数量>...]\n\
+                     令牌不通过命令行传入: 凭据文件中每项为 <用户ID>:<令牌>，以空白分隔；\
+                     未指定文件时从环境变量 MACH_GATEWAY_USERS 读取，格式相同";
+
+const CREDENTIALS_ENV: &str = "MACH_GATEWAY_USERS";
+
+struct Config {
+    listen: String,
+    market_data: Option<String>,
+    instrument: Instrument,
+    precision: (u32, u32),
+    credentials: HashMap<u64, String>,
+    deposits: Vec<Command>,
+}
+
+fn parse_args() -> Result<Config, String> {
+    let mut config = Config {
+        listen: String::from("127.0.0.1:7878"),
+        market_data: None,
+        instrument: Instrument::new(Asset::from("BTC"), Asset::from("USDT")),
+        precision: (8, 2),
+        credentials: HashMap::new(),
+        deposits: Vec::new(),
+    };
+    let mut args = env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or(format!("{flag} 缺少参数"))?;
+        let parts: Vec<&str> = value.split([':', '/']).collect();
+        let invalid = || format!("无效的参数 {flag} {value}");
+        match (flag.as_str(), &parts[..]) {
+            ("--listen", _) => config.listen = value.clone(),
+            ("--market-data", _) => config.market_data = Some(value.clone()),
+            ("--instrument", [base, quote]) => {
+                config.instrument = Instrument::new(Asset::try_new(base).map_err(|_| invalid())?, Asset::try_new(quote).map_err(|_| invalid())?);
+            }
+            ("--precision", [base, quote]) => {
+                config.precision = (base.parse().map_err(|_| invalid())?, quote.parse().map_err(|_| invalid())?);
+            }
+            ("--credentials", _) => {
+                let text = fs::read_to_string(&value).map_err(|e| format!("无法读取凭据文件 {value}: {e}"))?;
+                config.credentials = parse_credentials(&text)?;
+            }
+            ("--deposit", [user_id, asset, amount]) => config.deposits.push(Command::Deposit {
+                user_id: user_id.parse().map_err(|_| invalid())?,
+                asset: Asset::try_new(asset).map_err(|_| invalid())?,
+                amount: amount.parse::<Decimal>().map_err(|_| invalid())?,
+            }),
+            _ => return Err(invalid()),
+        }
+    }
+    if config.credentials.is_empty()
+        && let Ok(text) = env::var(CREDENTIALS_ENV)
+    {
+        config.credentials = parse_credentials(&text)?;
+    }
+    if config.credentials.is_empty() {
+        return Err(format!("至少需要一个用户: 使用 --credentials 或环境变量 {CREDENTIALS_ENV}"));
+    }
+    Ok(config)
+}
+
+// 每项为 <用户ID>:<令牌>，以空白分隔；出错时不回显令牌
+fn parse_credentials(text: &str) -> Result<HashMap<u64, String>, String> {
+    text.split_whitespace()
+        .enumerate()
+        .map(|(i, entry)| match entry.split_once(':') {
+            Some((user_id, token)) if !token.is_empty() => user_id
+                .parse()
+                .map(|user_id| (user_id, token.to_string()))
+                .map_err(|_| format!("第 {} 项凭据的用户 ID 无效", i + 1)),
+            _ => Err(format!("第 {} 项凭据应为 <用户ID>:<令牌>", i + 1)),
+        })
+        .collect()
+}
+
+fn main() {
+    let config = parse_args().unwrap_or_else(|e| {
+        eprintln!("{e}\n{USAGE}");
+        process::exit(2);
+    });
+
+    let (base_precision, quote_precision) = config.precision;
+    let mut exchange = Exchange::new(config.instrument, base_precision, quote_precision);
+    if let Some(addr) = config.market_data {
+        let server = MarketDataServer::new(1024);
+        exchange.add_listener(server.feed(config.instrument, exchange.book()));
+        let listener = TcpListener::bind(&addr).unwrap_or_else(|e| {
+            eprintln!("无法监听 {addr}: {e}");
+            process::exit(1);
+        });
+        println!("{} 行情 WebSocket 监听 {addr}", config.instrument);
+        thread::spawn(move || server.serve(listener));
+    }
+    let (engine, outputs) = Engine::spawn(exchange, 1024);
+    let handle = engine.handle();
+    for deposit in config.deposits {
+        if let Ok(Err(e)) = handle.call(deposit) {
+            eprintln!("初始充值失败: {e}");
+            process::exit(1);
+        }
+    }
+
+    let listener = TcpListener::bind(&config.listen).unwrap_or_else(|e| {
+        eprintln!("无法监听 {}: {e}", config.listen);
+        process::exit(1);
+    });
+    println!("{} 网关监听 {}", config.instrument, config.listen);
+    if let Err(e) = Gateway::new(handle, outputs, config.credentials).serve(listener) {
+        eprintln!("网关退出: {e}");
+        process::exit(1);
+    }
+}
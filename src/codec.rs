@@ -0,0 +1,260 @@
+use rust_decimal::Decimal;
+use thiserror::Error;
+use crate::types::{Asset, Instrument, Order, OrderID, OrderSide, Price, Quantity, TradeEvent, TradeID, UserID};
+
+// 内部总线的定长二进制编码 (参考 SBE):
+// 每条消息 = 8 字节消息头 + 定长消息体，全部小端。
+// 消息头: block_length u16 | template_id u16 | schema_id u16 | version u16
+// 消息体开头都是交易对 (base 8 字节 + quote 8 字节，与 Asset 内存布局一致)，
+// Decimal 为 16 字节 Decimal::serialize 的结果，无损。
+// 新版本只允许在消息体末尾追加字段并提升 SCHEMA_VERSION；解码器拒绝未知 (为 0 或比自己新) 的版本，
+// 消息体比当前长度长时按 block_length 跳过多出的部分。
+
+pub const SCHEMA_ID: u16 = 1;
+pub const SCHEMA_VERSION: u16 = 1;
+pub const HEADER_LEN: usize = 8;
+
+pub const TEMPLATE_NEW_ORDER: u16 = 1;
+pub const TEMPLATE_TRADE: u16 = 2;
+pub const TEMPLATE_CANCEL: u16 = 3;
+pub const TEMPLATE_AMEND: u16 = 4;
+
+// 各消息体在当前版本的长度
+const NEW_ORDER_LEN: usize = 72;
+const TRADE_LEN: usize = 88;
+const CANCEL_LEN: usize = 32;
+const AMEND_LEN: usize = 64;
+
+const DECIMAL_LEN: usize = 16;
+const MAX_SCALE: u8 = 28;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum CodecError {
+    #[error("消息不完整: 需要 {needed} 字节, 实际 {available} 字节")]
+    Truncated { needed: usize, available: usize },
+    #[error("未知的 schema {schema_id}")]
+    SchemaMismatch { schema_id: u16 },
+    #[error("不支持的 schema 版本 {version}")]
+    UnsupportedVersion { version: u16 },
+    #[error("未知的消息模板 {template_id}")]
+    UnknownTemplate { template_id: u16 },
+    #[error("模板 {template_id} 的消息体长度 {block_length} 小于最小长度")]
+    InvalidBlockLength { template_id: u16, block_length: u16 },
+    #[error("偏移 {offset} 处的字段取值无效")]
+    InvalidField { offset: usize },
+}
+
+// 解码结果: 各视图直接引用输入缓冲区，访问字段时才读取
+#[derive(Debug, Clone, Copy)]
+pub enum Message<'a> {
+    NewOrder(OrderView<'a>),
+    Trade(TradeView<'a>),
+    Cancel(CancelView<'a>),
+    Amend(AmendView<'a>),
+}
+
+impl<'a> Message<'a> {
+    pub fn instrument(&self) -> Instrument {
+        let body = match self {
+            Message::NewOrder(v) => v.0,
+            Message::Trade(v) => v.0,
+            Message::Cancel(v) => v.0,
+            Message::Amend(v) => v.0,
+        };
+        Instrument::new(*asset_at(body, 0), *asset_at(body, 8))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct OrderView<'a>(&'a [u8]);
+
+impl<'a> OrderView<'a> {
+    pub fn base(&self) -> &'a Asset { asset_at(self.0, 0) }
+    pub fn quote(&self) -> &'a Asset { asset_at(self.0, 8) }
+    pub fn order_id(&self) -> OrderID { u64_at(self.0, 16) }
+    pub fn user_id(&self) -> UserID { u64_at(self.0, 24) }
+    pub fn price(&self) -> Price { decimal_at(self.0, 32) }
+    pub fn quantity(&self) -> Quantity { decimal_at(self.0, 48) }
+    pub fn side(&self) -> OrderSide { side_at(self.0, 64) }
+
+    pub fn to_order(&self) -> Order {
+        Order { id: self.order_id(), price: self.price(), quantity: self.quantity(), side: self.side(), user_id: self.user_id() }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TradeView<'a>(&'a [u8]);
+
+impl<'a> TradeView<'a> {
+    pub fn base(&self) -> &'a Asset { asset_at(self.0, 0) }
+    pub fn quote(&self) -> &'a Asset { asset_at(self.0, 8) }
+    pub fn trade_id(&self) -> TradeID { u64_at(self.0, 16) }
+    pub fn maker_order_id(&self) -> OrderID { u64_at(self.0, 24) }
+    pub fn maker_user_id(&self) -> UserID { u64_at(self.0, 32) }
+    pub fn taker_order_id(&self) -> OrderID { u64_at(self.0, 40) }
+    pub fn taker_user_id(&self) -> UserID { u64_at(self.0, 48) }
+    pub fn price(&self) -> Price { decimal_at(self.0, 56) }
+    pub fn quantity(&self) -> Quantity { decimal_at(self.0, 72) }
+
+    pub fn to_trade(&self) -> TradeEvent {
+        TradeEvent {
+            trade_id: self.trade_id(),
+            maker_order_id: self.maker_order_id(),
+            maker_user_id: self.maker_user_id(),
+            taker_order_id: self.taker_order_id(),
+            taker_user_id: self.taker_user_id(),
+            price: self.price(),
+            quantity: self.quantity(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CancelView<'a>(&'a [u8]);
+
+impl<'a> CancelView<'a> {
+    pub fn base(&self) -> &'a Asset { asset_at(self.0, 0) }
+    pub fn quote(&self) -> &'a Asset { asset_at(self.0, 8) }
+    pub fn order_id(&self) -> OrderID { u64_at(self.0, 16) }
+    pub fn user_id(&self) -> UserID { u64_at(self.0, 24) }
+}
+
+// quantity 为改单后的剩余数量
+#[derive(Debug, Clone, Copy)]
+pub struct AmendView<'a>(&'a [u8]);
+
+impl<'a> AmendView<'a> {
+    pub fn base(&self) -> &'a Asset { asset_at(self.0, 0) }
+    pub fn quote(&self) -> &'a Asset { asset_at(self.0, 8) }
+    pub fn order_id(&self) -> OrderID { u64_at(self.0, 16) }
+    pub fn user_id(&self) -> UserID { u64_at(self.0, 24) }
+    pub fn price(&self) -> Price { decimal_at(self.0, 32) }
+    pub fn quantity(&self) -> Quantity { decimal_at(self.0, 48) }
+}
+
+pub fn encode_order(instrument: Instrument, order: &Order, buf: &mut Vec<u8>) {
+    header(buf, TEMPLATE_NEW_ORDER, NEW_ORDER_LEN);
+    put_instrument(buf, instrument);
+    buf.extend_from_slice(&order.id.to_le_bytes());
+    buf.extend_from_slice(&order.user_id.to_le_bytes());
+    buf.extend_from_slice(&order.price.serialize());
+    buf.extend_from_slice(&order.quantity.serialize());
+    buf.push(match order.side {
+        OrderSide::Bid => 0,
+        OrderSide::Ask => 1,
+    });
+    buf.extend_from_slice(&[0; 7]); // 对齐到 8 字节
+}
+
+pub fn encode_trade(instrument: Instrument, trade: &TradeEvent, buf: &mut Vec<u8>) {
+    header(buf, TEMPLATE_TRADE, TRADE_LEN);
+    put_instrument(buf, instrument);
+    for v in [trade.trade_id, trade.maker_order_id, trade.maker_user_id, trade.taker_order_id, trade.taker_user_id] {
+        buf.extend_from_slice(&v.to_le_bytes());
+    }
+    buf.extend_from_slice(&trade.price.serialize());
+    buf.extend_from_slice(&trade.quantity.serialize());
+}
+
+pub fn encode_cancel(instrument: Instrument, order_id: OrderID, user_id: UserID, buf: &mut Vec<u8>) {
+    header(buf, TEMPLATE_CANCEL, CANCEL_LEN);
+    put_instrument(buf, instrument);
+    buf.extend_from_slice(&order_id.to_le_bytes());
+    buf.extend_from_slice(&user_id.to_le_bytes());
+}
+
+pub fn encode_amend(instrument: Instrument, order_id: OrderID, user_id: UserID, price: Price, quantity: Quantity, buf: &mut Vec<u8>) {
+    header(buf, TEMPLATE_AMEND, AMEND_LEN);
+    put_instrument(buf, instrument);
+    buf.extend_from_slice(&order_id.to_le_bytes());
+    buf.extend_from_slice(&user_id.to_le_bytes());
+    buf.extend_from_slice(&price.serialize());
+    buf.extend_from_slice(&quantity.serialize());
+}
+
+// 从缓冲区头部解码一条消息，返回消息与其占用的字节数，便于在流中连续解码
+pub fn decode(buf: &[u8]) -> Result<(Message<'_>, usize), CodecError> {
+    if buf.len() < HEADER_LEN {
+        return Err(CodecError::Truncated { needed: HEADER_LEN, available: buf.len() });
+    }
+    let block_length = u16_at(buf, 0);
+    let template_id = u16_at(buf, 2);
+    let schema_id = u16_at(buf, 4);
+    if schema_id != SCHEMA_ID {
+        return Err(CodecError::SchemaMismatch { schema_id });
+    }
+    let version = u16_at(buf, 6);
+    if version == 0 || version > SCHEMA_VERSION {
+        return Err(CodecError::UnsupportedVersion { version });
+    }
+
+    let (min_len, decimals, side): (usize, &[usize], Option<usize>) = match template_id {
+        TEMPLATE_NEW_ORDER => (NEW_ORDER_LEN, &[32, 48], Some(64)),
+        TEMPLATE_TRADE => (TRADE_LEN, &[56, 72], None),
+        TEMPLATE_CANCEL => (CANCEL_LEN, &[], None),
+        TEMPLATE_AMEND => (AMEND_LEN, &[32, 48], None),
+        _ => return Err(CodecError::UnknownTemplate { template_id }),
+    };
+    if (block_length as usize) < min_len {
+        return Err(CodecError::InvalidBlockLength { template_id, block_length });
+    }
+    let total = HEADER_LEN + block_length as usize;
+    if buf.len() < total {
+        return Err(CodecError::Truncated { needed: total, available: buf.len() });
+    }
+
+    // 先校验一遍，之后视图的访问方法都不会失败
+    let body = &buf[HEADER_LEN..total];
+    for &offset in decimals {
+        if body[offset + 2] > MAX_SCALE {
+            return Err(CodecError::InvalidField { offset: HEADER_LEN + offset });
+        }
+    }
+    if let Some(offset) = side
+        && body[offset] > 1
+    {
+        return Err(CodecError::InvalidField { offset: HEADER_LEN + offset });
+    }
+
+    let message = match template_id {
+        TEMPLATE_NEW_ORDER => Message::NewOrder(OrderView(body)),
+        TEMPLATE_TRADE => Message::Trade(TradeView(body)),
+        TEMPLATE_CANCEL => Message::Cancel(CancelView(body)),
+        _ => Message::Amend(AmendView(body)),
+    };
+    Ok((message, total))
+}
+
+fn header(buf: &mut Vec<u8>, template_id: u16, block_length: usize) {
+    for v in [block_length as u16, template_id, SCHEMA_ID, SCHEMA_VERSION] {
+        buf.extend_from_slice(&v.to_le_bytes());
+    }
+}
+
+fn put_instrument(buf: &mut Vec<u8>, instrument: Instrument) {
+    buf.extend_from_slice(instrument.base.as_bytes());
+    buf.extend_from_slice(instrument.quote.as_bytes());
+}
+
+fn u16_at(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(buf[offset..offset + 2].try_into().unwrap())
+}
+
+fn u64_at(buf: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+}
+
+fn decimal_at(buf: &[u8], offset: usize) -> Decimal {
+    Decimal::deserialize(buf[offset..offset + DECIMAL_LEN].try_into().unwrap())
+}
+
+fn asset_at(buf: &[u8], offset: usize) -> &Asset {
+    Asset::from_bytes_ref(buf[offset..offset + 8].try_into().unwrap())
+}
+
+fn side_at(buf: &[u8], offset: usize) -> OrderSide {
+    match buf[offset] {
+        0 => OrderSide::Bid,
+        _ => OrderSide::Ask,
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use rust_decimal::Decimal;
+use thiserror::Error;
+use crate::account::{AccountManager, AccountError};
+use crate::types::{Asset, DepositID, UserID};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DepositStatus {
+    Confirming, // 已检测到，确认数不足，资金不可用
+    Credited,   // 已入账
+    Reversed,   // 已回滚 (孤块 / 双花)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Deposit {
+    pub id: DepositID,
+    pub tx_id: String, // 外部交易号，用于去重
+    pub user_id: UserID,
+    pub asset: Asset,
+    pub amount: Decimal,
+    pub confirmations: u32,
+    pub status: DepositStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum DepositError {
+    #[error("充值交易 {tx_id} 不存在")]
+    NotFound { tx_id: String },
+    #[error("充值交易 {tx_id} 已存在且内容不一致")]
+    DuplicateMismatch { tx_id: String },
+    #[error("充值交易 {tx_id} 当前状态 {status:?} 不允许该操作")]
+    InvalidState { tx_id: String, status: DepositStatus },
+    #[error(transparent)]
+    Account(#[from] AccountError),
+}
+
+// 钱包服务的入金入口: 以外部交易号去重，可按资产配置所需确认数
+#[derive(Default)]
+pub struct DepositManager {
+    required_confirmations: HashMap<Asset, u32>,
+    deposits: HashMap<String, Deposit>,
+    next_id: DepositID,
+}
+
+impl DepositManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 0 表示无需等待确认，直接入账
+    pub fn set_required_confirmations(&mut self, asset: Asset, confirmations: u32) {
+        self.required_confirmations.insert(asset, confirmations);
+    }
+
+    pub fn get(&self, tx_id: &str) -> Option<&Deposit> {
+        self.deposits.get(tx_id)
+    }
+
+    // 入金通知: 重复通知不会重复入账，只更新确认数
+    pub fn credit(
+        &mut self,
+        account: &mut AccountManager,
+        tx_id: &str,
+        user_id: UserID,
+        asset: Asset,
+        amount: Decimal,
+        confirmations: u32,
+    ) -> Result<DepositStatus, DepositError> {
+        // 非正金额直接拒绝，不登记交易号
+        if amount <= Decimal::ZERO {
+            return Err(AccountError::InvalidAmount { user_id, asset, amount }.into());
+        }
+        if let Some(d) = self.deposits.get(tx_id) {
+            if d.user_id != user_id || d.asset != asset || d.amount != amount {
+                return Err(DepositError::DuplicateMismatch { tx_id: tx_id.to_string() });
+            }
+            return self.update_confirmations(account, tx_id, confirmations);
+        }
+
+        let id = self.next_id + 1;
+        let required = self.required_confirmations.get(&asset).copied().unwrap_or(0);
+        let status = if confirmations >= required {
+            account.deposit_with_ref(user_id, asset, amount, id)?;
+            DepositStatus::Credited
+        } else {
+            account.credit_pending_deposit(user_id, asset, amount, id)?;
+            DepositStatus::Confirming
+        };
+        self.next_id = id;
+
+        self.deposits.insert(tx_id.to_string(), Deposit {
+            id,
+            tx_id: tx_id.to_string(),
+            user_id,
+            asset,
+            amount,
+            confirmations,
+            status,
+        });
+        Ok(status)
+    }
+
+    // 更新确认数，达到要求后资金转为可用
+    pub fn update_confirmations(
+        &mut self,
+        account: &mut AccountManager,
+        tx_id: &str,
+        confirmations: u32,
+    ) -> Result<DepositStatus, DepositError> {
+        let d = self.deposits.get_mut(tx_id).ok_or_else(|| DepositError::NotFound { tx_id: tx_id.to_string() })?;
+        if d.status != DepositStatus::Confirming {
+            return Ok(d.status);
+        }
+
+        d.confirmations = d.confirmations.max(confirmations);
+        let required = self.required_confirmations.get(&d.asset).copied().unwrap_or(0);
+        if d.confirmations >= required {
+            account.confirm_pending_deposit(d.user_id, d.asset, d.amount, d.id)?;
+            d.status = DepositStatus::Credited;
+        }
+        Ok(d.status)
+    }
+
+    // 回滚充值: 已入账的资金若已被使用 (冻结 / 提现) 会返回余额不足
+    pub fn reverse(&mut self, account: &mut AccountManager, tx_id: &str) -> Result<(), DepositError> {
+        let d = self.deposits.get_mut(tx_id).ok_or_else(|| DepositError::NotFound { tx_id: tx_id.to_string() })?;
+        if d.status == DepositStatus::Reversed {
+            return Err(DepositError::InvalidState { tx_id: tx_id.to_string(), status: d.status });
+        }
+
+        account.reverse_deposit(d.user_id, d.asset, d.amount, d.status == DepositStatus::Confirming, d.id)?;
+        d.status = DepositStatus::Reversed;
+        Ok(())
+    }
+}
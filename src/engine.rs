@@ -1,31 +1,187 @@
-use std::collections::{BTreeMap, VecDeque,HashMap};
+use std::collections::{BTreeMap, VecDeque, HashMap, HashSet};
+use std::ops::RangeBounds;
 use rust_decimal_macros::dec;
-use crate::types::{Order, OrderSide, Price, TradeEvent, OrderID};
+use rust_decimal::Decimal;
+use thiserror::Error;
+use crate::observer::{EventListener, Listeners, OrderEvent};
+use crate::snapshot::{Decoder, Encoder};
+use crate::types::{Order, OrderSide, Price, Quantity, TradeEvent, OrderID, TradeID, UserID, OrderStatus, OrderReport};
 
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum EngineError {
+    #[error("订单 {order_id} 不存在或已结束")]
+    OrderNotFound { order_id: OrderID },
+    #[error("订单号 {order_id} 重复")]
+    DuplicateOrderId { order_id: OrderID },
+    #[error("订单 {order_id} 数量无效: {quantity}")]
+    InvalidQuantity { order_id: OrderID, quantity: Quantity },
+    #[error("订单 {order_id} 价格无效: {price}")]
+    InvalidPrice { order_id: OrderID, price: Price },
+    #[error("订单 {order_id} 的新数量 {quantity} 不大于已成交数量 {filled}")]
+    QuantityNotAboveFilled { order_id: OrderID, quantity: Quantity, filled: Quantity },
+}
+
+// 默认保留的终态订单数量
+pub const DEFAULT_RETENTION: usize = 10_000;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct OrderLocation{
     price: Price,
     side: OrderSide,
+    user_id: UserID,
+    original_quantity: Quantity,
+    filled_quantity: Quantity,
+    filled_notional: Decimal, // 累计成交额，用于计算成交均价
+}
+
+impl OrderLocation {
+    fn new(order: &Order, original_quantity: Quantity) -> Self {
+        OrderLocation {
+            price: order.price,
+            side: order.side.clone(),
+            user_id: order.user_id,
+            original_quantity,
+            filled_quantity: dec!(0),
+            filled_notional: dec!(0),
+        }
+    }
+
+    fn record_fill(&mut self, price: Price, quantity: Quantity) {
+        self.filled_quantity += quantity;
+        self.filled_notional += price * quantity;
+    }
+
+    fn report(&self, order_id: OrderID, status: OrderStatus) -> OrderReport {
+        let remaining_quantity = match status {
+            OrderStatus::Open | OrderStatus::PartiallyFilled => self.original_quantity - self.filled_quantity,
+            OrderStatus::Filled | OrderStatus::Cancelled => dec!(0),
+        };
+        OrderReport {
+            order_id,
+            user_id: self.user_id,
+            side: self.side.clone(),
+            price: self.price,
+            status,
+            original_quantity: self.original_quantity,
+            filled_quantity: self.filled_quantity,
+            remaining_quantity,
+            average_price: if self.filled_quantity > dec!(0) {
+                Some(self.filled_notional / self.filled_quantity)
+            } else {
+                None
+            },
+        }
+    }
+
+    fn open_status(&self) -> OrderStatus {
+        if self.filled_quantity > dec!(0) {
+            OrderStatus::PartiallyFilled
+        } else {
+            OrderStatus::Open
+        }
+    }
 }
+
+// 终态订单 (完全成交 / 已撤销) 的有界存储，超出容量时淘汰最早的记录
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct TerminalOrders {
+    capacity: usize,
+    queue: VecDeque<OrderID>,
+    reports: HashMap<OrderID, OrderReport>,
+    // 被淘汰 (或因容量为 0 未保存) 的订单号，只保留编号用于拒绝重复使用
+    evicted: HashSet<OrderID>,
+}
+
+impl TerminalOrders {
+    fn new(capacity: usize) -> Self {
+        TerminalOrders {
+            capacity,
+            queue: VecDeque::new(),
+            reports: HashMap::new(),
+            evicted: HashSet::new(),
+        }
+    }
+
+    fn contains(&self, order_id: OrderID) -> bool {
+        self.reports.contains_key(&order_id) || self.evicted.contains(&order_id)
+    }
+
+    fn push(&mut self, report: OrderReport) {
+        if self.capacity == 0 {
+            self.evicted.insert(report.order_id);
+            return;
+        }
+        while self.queue.len() >= self.capacity {
+            if let Some(old) = self.queue.pop_front() {
+                self.reports.remove(&old);
+                self.evicted.insert(old);
+            }
+        }
+        self.queue.push_back(report.order_id);
+        self.reports.insert(report.order_id, report);
+    }
+}
+
 //BTreeMap 默认从高到低排序
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OrderBook {
     pub bids: BTreeMap<Price, VecDeque<Order>>,
     pub asks: BTreeMap<Price, VecDeque<Order>>,
     order_index: HashMap<OrderID, OrderLocation>,
+    // 按用户索引挂单，用于批量撤单
+    user_orders: HashMap<UserID, HashSet<OrderID>>,
+    terminal: TerminalOrders,
+    next_trade_id: TradeID,
+    // 监听器属于运行时装配，不随状态序列化
+    #[cfg_attr(feature = "serde", serde(skip))]
+    listeners: Listeners,
+}
+
+impl Default for OrderBook {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
+fn remove_user_order(user_orders: &mut HashMap<UserID, HashSet<OrderID>>, user_id: UserID, order_id: OrderID) {
+    if let Some(ids) = user_orders.get_mut(&user_id) {
+        ids.remove(&order_id);
+        if ids.is_empty() {
+            user_orders.remove(&user_id);
+        }
+    }
+}
 
 impl OrderBook {
     pub fn new() -> Self {
+        Self::with_retention(DEFAULT_RETENTION)
+    }
+
+    // retention: 保留多少条终态订单供事后查询
+    pub fn with_retention(retention: usize) -> Self {
         Self {
             bids: BTreeMap::new(),
             asks: BTreeMap::new(),
             order_index: HashMap::new(),
+            user_orders: HashMap::new(),
+            terminal: TerminalOrders::new(retention),
+            next_trade_id: 0,
+            listeners: Listeners::default(),
         }
     }
 
-    pub fn match_order(&mut self, mut incoming_order: Order) -> Vec<TradeEvent> {
+    pub fn add_listener(&mut self, listener: Box<dyn EventListener>) {
+        self.listeners.add(listener);
+    }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(order_id = incoming_order.id, user_id = incoming_order.user_id)))]
+    pub fn match_order(&mut self, incoming_order: Order) -> Vec<TradeEvent> {
+        let taker = OrderLocation::new(&incoming_order, incoming_order.quantity);
+        self.match_with(incoming_order, taker)
+    }
+
+    // taker 为该订单的成交记录，改单时沿用原有记录
+    fn match_with(&mut self, mut incoming_order: Order, mut taker: OrderLocation) -> Vec<TradeEvent> {
         let mut trades = Vec::new();
 
         loop {
@@ -70,19 +226,33 @@ impl OrderBook {
 
                 incoming_order.quantity -= trade_qty;
                 maker_order.quantity -= trade_qty;
+                taker.record_fill(match_price, trade_qty);
+                if let Some(loc) = self.order_index.get_mut(&maker_order.id) {
+                    loc.record_fill(match_price, trade_qty);
+                }
 
-                trades.push(TradeEvent {
+                self.next_trade_id += 1;
+                let trade = TradeEvent {
+                    trade_id: self.next_trade_id,
                     maker_order_id: maker_order.id,
                     maker_user_id: maker_order.user_id, // 需要 types.rs 加了 user_id 才能用
                     taker_order_id: incoming_order.id,
                     taker_user_id: incoming_order.user_id,
                     price: match_price,
                     quantity: trade_qty,
-                });
+                };
+                // 成交事件随撮合即时发出，未成交部分的 Rested 在撮合结束后才发出
+                if !self.listeners.is_empty() {
+                    self.listeners.order(OrderEvent::Trade(trade.clone()));
+                }
+                trades.push(trade);
 
 
                 if maker_order.quantity == dec!(0.0) {
-                    self.order_index.remove(&maker_order.id);
+                    if let Some(loc) = self.order_index.remove(&maker_order.id) {
+                        self.terminal.push(loc.report(maker_order.id, OrderStatus::Filled));
+                    }
+                    remove_user_order(&mut self.user_orders, maker_order.user_id, maker_order.id);
                     queue.pop_front();
                 } else {
                     break;
@@ -101,27 +271,145 @@ impl OrderBook {
 
         if incoming_order.quantity > dec!(0.0) {
 
-            self.order_index.insert(incoming_order.id, OrderLocation{
-                price:incoming_order.price,
-                side: incoming_order.side.clone(),
-            });
+            self.order_index.insert(incoming_order.id, taker);
+            if !self.listeners.is_empty() {
+                self.listeners.order(OrderEvent::Rested(incoming_order.clone()));
+            }
+            self.user_orders.entry(incoming_order.user_id).or_default().insert(incoming_order.id);
 
             let queue = match incoming_order.side {
                 OrderSide::Bid => self
                     .bids
                     .entry(incoming_order.price)
-                    .or_insert(VecDeque::new()),
+                    .or_default(),
                 OrderSide::Ask => self
                     .asks
                     .entry(incoming_order.price)
-                    .or_insert(VecDeque::new()),
+                    .or_default(),
             };
             queue.push_back(incoming_order);
+        } else {
+            self.terminal.push(taker.report(incoming_order.id, OrderStatus::Filled));
         }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(trades = trades.len(), "撮合完成");
         trades
     }
 
-    pub fn cancel_order(&mut self, order_id: OrderID) -> Option<Order> {
+    // 只读预演: 订单此刻进入撮合会产生的成交 (含成交号)，不修改订单簿
+    pub fn preview_match(&self, order: &Order) -> Vec<TradeEvent> {
+        match order.side {
+            OrderSide::Bid => self.preview_levels(order, self.asks.iter().take_while(|(p, _)| order.price >= **p)),
+            OrderSide::Ask => self.preview_levels(order, self.bids.iter().rev().take_while(|(p, _)| order.price <= **p)),
+        }
+    }
+
+    fn preview_levels<'a>(&self, order: &Order, levels: impl Iterator<Item = (&'a Price, &'a VecDeque<Order>)>) -> Vec<TradeEvent> {
+        let mut remaining = order.quantity;
+        let mut trades = Vec::new();
+        for (price, maker_order) in levels.flat_map(|(p, queue)| queue.iter().map(move |o| (*p, o))) {
+            if remaining == dec!(0.0) {
+                break;
+            }
+            let trade_qty = std::cmp::min(remaining, maker_order.quantity);
+            remaining -= trade_qty;
+            trades.push(TradeEvent {
+                trade_id: self.next_trade_id + trades.len() as TradeID + 1,
+                maker_order_id: maker_order.id,
+                maker_user_id: maker_order.user_id,
+                taker_order_id: order.id,
+                taker_user_id: order.user_id,
+                price,
+                quantity: trade_qty,
+            });
+        }
+        trades
+    }
+
+    // 校验订单后撮合
+    pub fn place_order(&mut self, order: Order) -> Result<Vec<TradeEvent>, EngineError> {
+        self.validate_order(&order)?;
+        Ok(self.match_order(order))
+    }
+
+    // 数量与价格必须为正，订单号不能与已知订单重复；
+    // 终态记录被淘汰后仍保留其订单号，旧订单号不能再次使用
+    pub fn validate_order(&self, order: &Order) -> Result<(), EngineError> {
+        if order.quantity <= dec!(0) {
+            return Err(EngineError::InvalidQuantity { order_id: order.id, quantity: order.quantity });
+        }
+        if order.price <= dec!(0) {
+            return Err(EngineError::InvalidPrice { order_id: order.id, price: order.price });
+        }
+        if self.order_index.contains_key(&order.id) || self.terminal.contains(order.id) {
+            return Err(EngineError::DuplicateOrderId { order_id: order.id });
+        }
+        Ok(())
+    }
+
+    // 校验改单参数，返回改单后的订单 (quantity 为新的剩余数量)
+    pub fn validate_amend(&self, order_id: OrderID, price: Price, quantity: Quantity) -> Result<Order, EngineError> {
+        let loc = self.order_index.get(&order_id).ok_or(EngineError::OrderNotFound { order_id })?;
+        if quantity <= dec!(0) {
+            return Err(EngineError::InvalidQuantity { order_id, quantity });
+        }
+        if price <= dec!(0) {
+            return Err(EngineError::InvalidPrice { order_id, price });
+        }
+        Ok(Order { id: order_id, price, quantity, side: loc.side.clone(), user_id: loc.user_id })
+    }
+
+    // 按新的订单总量 (含已成交部分) 计算改单后的剩余数量，以撮合线程看到的实际成交为准
+    pub fn replace_quantity(&self, order_id: OrderID, order_qty: Quantity) -> Result<Quantity, EngineError> {
+        let loc = self.order_index.get(&order_id).ok_or(EngineError::OrderNotFound { order_id })?;
+        if order_qty <= loc.filled_quantity {
+            return Err(EngineError::QuantityNotAboveFilled { order_id, quantity: order_qty, filled: loc.filled_quantity });
+        }
+        Ok(order_qty - loc.filled_quantity)
+    }
+
+    // 改单: 价格不变且只减少数量时原地修改，保留时间优先级；
+    // 否则从队列中取出，按新价格重新撮合并排到队尾。已成交部分保留在订单记录中
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), err))]
+    pub fn amend_order(&mut self, order_id: OrderID, price: Price, quantity: Quantity) -> Result<Vec<TradeEvent>, EngineError> {
+        let amended = self.validate_amend(order_id, price, quantity)?;
+        let not_found = EngineError::OrderNotFound { order_id };
+        let old_price = self.order_index[&order_id].price;
+
+        let levels = match amended.side {
+            OrderSide::Bid => &mut self.bids,
+            OrderSide::Ask => &mut self.asks,
+        };
+        let queue = levels.get_mut(&old_price).ok_or(not_found.clone())?;
+        let idx = queue.iter().position(|o| o.id == order_id).ok_or(not_found.clone())?;
+
+        let loc = self.order_index.get_mut(&order_id).ok_or(not_found.clone())?;
+        if price == old_price && quantity <= queue[idx].quantity {
+            queue[idx].quantity = quantity;
+            loc.original_quantity = loc.filled_quantity + quantity;
+            if !self.listeners.is_empty() {
+                self.listeners.order(OrderEvent::Reduced(queue[idx].clone()));
+            }
+            return Ok(Vec::new());
+        }
+
+        let replaced = queue.remove(idx).ok_or(not_found.clone())?;
+        if queue.is_empty() {
+            levels.remove(&old_price);
+        }
+        let mut loc = self.order_index.remove(&order_id).ok_or(not_found)?;
+        remove_user_order(&mut self.user_orders, amended.user_id, order_id);
+        loc.price = price;
+        loc.original_quantity = loc.filled_quantity + quantity;
+        if !self.listeners.is_empty() {
+            self.listeners.order(OrderEvent::Replaced(replaced));
+        }
+        Ok(self.match_with(amended, loc))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), err))]
+    pub fn cancel_order(&mut self, order_id: OrderID) -> Result<Order, EngineError> {
         if let Some(loc) = self.order_index.get(&order_id) {
             let price = loc.price;
             let side = loc.side.clone();
@@ -131,25 +419,241 @@ impl OrderBook {
                 OrderSide::Ask => self.asks.get_mut(&price),
             };
 
-            if let Some(q) = queue {
-
-                if let Some(idx) = q.iter().position(|o| o.id == order_id) {
-                    let cancelled_order = match q.remove(idx) {
-                        Some(order) => order,
-                        None => return None,
-                    };
-                    self.order_index.remove(&cancelled_order.id);
-                    if q.is_empty() {
-                        match side {
-                            OrderSide::Bid => {self.bids.remove(&price);},
-                            OrderSide::Ask => {self.asks.remove(&price);},
-                        }
+            if let Some(q) = queue
+                && let Some(idx) = q.iter().position(|o| o.id == order_id) {
+                let cancelled_order = match q.remove(idx) {
+                    Some(order) => order,
+                    None => return Err(EngineError::OrderNotFound { order_id }),
+                };
+                if q.is_empty() {
+                    match side {
+                        OrderSide::Bid => {self.bids.remove(&price);},
+                        OrderSide::Ask => {self.asks.remove(&price);},
                     }
+                }
+
+                self.finish_cancelled(&cancelled_order);
+                return Ok(cancelled_order);
+            }
+        }
+        Err(EngineError::OrderNotFound { order_id })
+    }
+
+    // 撤销某用户的全部挂单 (断线 / 风控触发)
+    pub fn cancel_user_orders(&mut self, user_id: UserID) -> Vec<Order> {
+        self.cancel_user_orders_where(user_id, |_| true)
+    }
+
+    // 撤销某用户某一方向的全部挂单
+    pub fn cancel_user_side_orders(&mut self, user_id: UserID, side: OrderSide) -> Vec<Order> {
+        self.cancel_user_orders_where(user_id, |loc| loc.side == side)
+    }
+
+    fn cancel_user_orders_where(&mut self, user_id: UserID, filter: impl Fn(&OrderLocation) -> bool) -> Vec<Order> {
+        let mut ids: Vec<OrderID> = match self.user_orders.get(&user_id) {
+            Some(ids) => ids
+                .iter()
+                .filter(|id| self.order_index.get(id).is_some_and(&filter))
+                .copied()
+                .collect(),
+            None => return Vec::new(),
+        };
+        // HashSet 无序，按订单号排序保证返回结果稳定
+        ids.sort_unstable();
+        ids.into_iter().filter_map(|id| self.cancel_order(id).ok()).collect()
+    }
+
+    // 撤销价格区间内的全部挂单，side 为 None 时两边都撤
+    pub fn cancel_price_range<R>(&mut self, side: Option<OrderSide>, range: R) -> Vec<Order>
+    where
+        R: RangeBounds<Price> + Clone,
+    {
+        let mut cancelled = Vec::new();
+        if side != Some(OrderSide::Ask) {
+            Self::drain_levels(&mut self.bids, range.clone(), &mut cancelled);
+        }
+        if side != Some(OrderSide::Bid) {
+            Self::drain_levels(&mut self.asks, range, &mut cancelled);
+        }
+        for order in &cancelled {
+            self.finish_cancelled(order);
+        }
+        cancelled
+    }
+
+    // 整档移除区间内的价格档位，按价格从低到高、档内按时间顺序输出
+    fn drain_levels<R: RangeBounds<Price>>(levels: &mut BTreeMap<Price, VecDeque<Order>>, range: R, out: &mut Vec<Order>) {
+        let prices: Vec<Price> = levels.range(range).map(|(p, _)| *p).collect();
+        for price in prices {
+            if let Some(queue) = levels.remove(&price) {
+                out.extend(queue);
+            }
+        }
+    }
+
+    // 撤单后清理索引，并记入终态存储
+    fn finish_cancelled(&mut self, order: &Order) {
+        if let Some(loc) = self.order_index.remove(&order.id) {
+            self.terminal.push(loc.report(order.id, OrderStatus::Cancelled));
+        }
+        remove_user_order(&mut self.user_orders, order.user_id, order.id);
+        if !self.listeners.is_empty() {
+            self.listeners.order(OrderEvent::Cancelled(order.clone()));
+        }
+    }
 
-                    return Some(cancelled_order);
+    // 按订单号查询状态: 先查挂单，再查终态存储 (可能已被淘汰)
+    pub fn order_status(&self, order_id: OrderID) -> Option<OrderReport> {
+        match self.order_index.get(&order_id) {
+            Some(loc) => Some(loc.report(order_id, loc.open_status())),
+            None => self.terminal.reports.get(&order_id).cloned(),
+        }
+    }
+
+    // 某用户的全部挂单，按订单号排序
+    pub fn open_orders(&self, user_id: UserID) -> Vec<OrderReport> {
+        let mut reports: Vec<OrderReport> = match self.user_orders.get(&user_id) {
+            Some(ids) => ids
+                .iter()
+                .filter_map(|id| self.order_index.get(id).map(|loc| loc.report(*id, loc.open_status())))
+                .collect(),
+            None => Vec::new(),
+        };
+        reports.sort_unstable_by_key(|r| r.order_id);
+        reports
+    }
+
+    // 订单簿见过的最大订单号 (挂单、终态记录与已淘汰的)，用于接入层续接分配订单号
+    pub fn last_order_id(&self) -> Option<OrderID> {
+        let open = self.order_index.keys().max().copied();
+        let terminal = self.terminal.reports.keys().max().copied();
+        open.max(terminal).max(self.terminal.evicted.iter().max().copied())
+    }
+
+    // 某用户当前挂单数量
+    pub fn user_order_count(&self, user_id: UserID) -> usize {
+        self.user_orders.get(&user_id).map_or(0, |ids| ids.len())
+    }
+
+    // 快照: 价格档位与队列顺序、订单索引、终态记录；按用户的索引由订单索引重建
+    pub(crate) fn encode(&self, e: &mut Encoder) {
+        e.u64(self.terminal.capacity as u64);
+        e.u64(self.next_trade_id);
+        for levels in [&self.bids, &self.asks] {
+            e.len(levels.len());
+            for (price, queue) in levels {
+                e.decimal(*price);
+                e.len(queue.len());
+                for order in queue {
+                    e.order(order);
+                }
+            }
+        }
+
+        let mut ids: Vec<&OrderID> = self.order_index.keys().collect();
+        ids.sort_unstable();
+        e.len(ids.len());
+        for id in ids {
+            let loc = &self.order_index[id];
+            e.u64(*id);
+            e.decimal(loc.price);
+            e.side(&loc.side);
+            e.u64(loc.user_id);
+            for amount in [loc.original_quantity, loc.filled_quantity, loc.filled_notional] {
+                e.decimal(amount);
+            }
+        }
+
+        let mut evicted: Vec<&OrderID> = self.terminal.evicted.iter().collect();
+        evicted.sort_unstable();
+        e.len(evicted.len());
+        for id in evicted {
+            e.u64(*id);
+        }
+        e.len(self.terminal.queue.len());
+        for id in &self.terminal.queue {
+            let r = &self.terminal.reports[id];
+            e.u64(r.order_id);
+            e.u64(r.user_id);
+            e.side(&r.side);
+            e.decimal(r.price);
+            e.u8(match r.status {
+                OrderStatus::Open => 0,
+                OrderStatus::PartiallyFilled => 1,
+                OrderStatus::Filled => 2,
+                OrderStatus::Cancelled => 3,
+            });
+            for amount in [r.original_quantity, r.filled_quantity, r.remaining_quantity] {
+                e.decimal(amount);
+            }
+            match r.average_price {
+                Some(price) => {
+                    e.u8(1);
+                    e.decimal(price);
                 }
+                None => e.u8(0),
             }
         }
-        None
+    }
+
+    pub(crate) fn decode(d: &mut Decoder) -> Option<Self> {
+        let mut book = OrderBook::with_retention(usize::try_from(d.u64()?).ok()?);
+        book.next_trade_id = d.u64()?;
+        for side in [OrderSide::Bid, OrderSide::Ask] {
+            for _ in 0..d.len()? {
+                let price = d.decimal()?;
+                let mut queue = VecDeque::new();
+                for _ in 0..d.len()? {
+                    queue.push_back(d.order()?);
+                }
+                match side {
+                    OrderSide::Bid => book.bids.insert(price, queue),
+                    OrderSide::Ask => book.asks.insert(price, queue),
+                };
+            }
+        }
+
+        for _ in 0..d.len()? {
+            let id = d.u64()?;
+            let loc = OrderLocation {
+                price: d.decimal()?,
+                side: d.side()?,
+                user_id: d.u64()?,
+                original_quantity: d.decimal()?,
+                filled_quantity: d.decimal()?,
+                filled_notional: d.decimal()?,
+            };
+            book.user_orders.entry(loc.user_id).or_default().insert(id);
+            book.order_index.insert(id, loc);
+        }
+
+        for _ in 0..d.len()? {
+            book.terminal.evicted.insert(d.u64()?);
+        }
+        for _ in 0..d.len()? {
+            let order_id = d.u64()?;
+            let user_id = d.u64()?;
+            let side = d.side()?;
+            let price = d.decimal()?;
+            let status = match d.u8()? {
+                0 => OrderStatus::Open,
+                1 => OrderStatus::PartiallyFilled,
+                2 => OrderStatus::Filled,
+                3 => OrderStatus::Cancelled,
+                _ => return None,
+            };
+            let original_quantity = d.decimal()?;
+            let filled_quantity = d.decimal()?;
+            let remaining_quantity = d.decimal()?;
+            let average_price = match d.u8()? {
+                0 => None,
+                1 => Some(d.decimal()?),
+                _ => return None,
+            };
+            book.terminal.push(OrderReport {
+                order_id, user_id, side, price, status, original_quantity, filled_quantity, remaining_quantity, average_price,
+            });
+        }
+        Some(book)
     }
 }
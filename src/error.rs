@@ -0,0 +1,158 @@
+use thiserror::Error;
+use crate::account::AccountError;
+use crate::deposit::DepositError;
+use crate::engine::EngineError;
+use crate::journal::JournalError;
+use crate::rate_limit::RateLimited;
+use crate::registry::RegistryError;
+use crate::risk::RiskRejection;
+use crate::session::SessionError;
+use crate::settlement::SettlementError;
+use crate::types::{OrderID, UserID};
+use crate::withdrawal::WithdrawalError;
+
+// 统一错误类型: 撮合拒单、账户失败、风控与限流都归到这里，网关据此映射为对外错误码
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Account(#[from] AccountError),
+    #[error(transparent)]
+    Engine(#[from] EngineError),
+    #[error(transparent)]
+    Session(#[from] SessionError),
+    #[error(transparent)]
+    Risk(#[from] RiskRejection),
+    #[error(transparent)]
+    RateLimited(#[from] RateLimited),
+    #[error(transparent)]
+    Withdrawal(#[from] WithdrawalError),
+    #[error(transparent)]
+    Deposit(#[from] DepositError),
+    #[error(transparent)]
+    Registry(#[from] RegistryError),
+    #[error(transparent)]
+    Settlement(#[from] SettlementError),
+    #[error(transparent)]
+    Journal(#[from] JournalError),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    // 稳定的对外错误码，不随错误信息文案变化
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::Account(e) => account_code(e),
+            Error::Engine(e) => match e {
+                EngineError::OrderNotFound { .. } => "ORDER_NOT_FOUND",
+                EngineError::DuplicateOrderId { .. } => "DUPLICATE_ORDER_ID",
+                EngineError::InvalidQuantity { .. } => "INVALID_QUANTITY",
+                EngineError::InvalidPrice { .. } => "INVALID_PRICE",
+                EngineError::QuantityNotAboveFilled { .. } => "QUANTITY_NOT_ABOVE_FILLED",
+            },
+            Error::Session(e) => match e {
+                SessionError::SessionNotFound { .. } => "SESSION_NOT_FOUND",
+                SessionError::UserMismatch { .. } => "USER_MISMATCH",
+                SessionError::KillSwitchActive { .. } => "KILL_SWITCH_ACTIVE",
+            },
+            Error::Risk(e) => match e {
+                RiskRejection::MaxOpenOrders { .. } => "RISK_MAX_OPEN_ORDERS",
+                RiskRejection::MaxOrderNotional { .. } => "RISK_MAX_ORDER_NOTIONAL",
+                RiskRejection::MaxPosition { .. } => "RISK_MAX_POSITION",
+                RiskRejection::MaxDailyVolume { .. } => "RISK_MAX_DAILY_VOLUME",
+                RiskRejection::PriceDeviation { .. } => "RISK_PRICE_DEVIATION",
+            },
+            Error::RateLimited(_) => "RATE_LIMITED",
+            Error::Withdrawal(e) => match e {
+                WithdrawalError::NotFound { .. } => "WITHDRAWAL_NOT_FOUND",
+                WithdrawalError::InvalidState { .. } => "WITHDRAWAL_INVALID_STATE",
+                WithdrawalError::BelowMinimum { .. } => "WITHDRAWAL_BELOW_MINIMUM",
+                WithdrawalError::IdempotencyConflict { .. } => "IDEMPOTENCY_CONFLICT",
+                WithdrawalError::Account(e) => account_code(e),
+            },
+            Error::Deposit(e) => match e {
+                DepositError::NotFound { .. } => "DEPOSIT_NOT_FOUND",
+                DepositError::DuplicateMismatch { .. } => "DEPOSIT_DUPLICATE_MISMATCH",
+                DepositError::InvalidState { .. } => "DEPOSIT_INVALID_STATE",
+                DepositError::Account(e) => account_code(e),
+            },
+            Error::Registry(e) => match e {
+                RegistryError::InvalidSymbol { .. } => "INVALID_SYMBOL",
+                RegistryError::SymbolTooLong { .. } => "SYMBOL_TOO_LONG",
+                RegistryError::InvalidPrecision { .. } => "INVALID_PRECISION",
+                RegistryError::DuplicateAsset { .. } => "DUPLICATE_ASSET",
+                RegistryError::UnknownAsset { .. } => "UNKNOWN_ASSET",
+            },
+            Error::Settlement(e) => match e {
+                SettlementError::UnknownOrder { .. } => "SETTLEMENT_UNKNOWN_ORDER",
+                SettlementError::Account(e) => account_code(e),
+            },
+            Error::Journal(_) => "JOURNAL_FAILURE",
+        }
+    }
+
+    // 错误涉及的用户 (如果有)
+    pub fn user_id(&self) -> Option<UserID> {
+        match self {
+            Error::Account(e)
+            | Error::Withdrawal(WithdrawalError::Account(e))
+            | Error::Deposit(DepositError::Account(e))
+            | Error::Settlement(SettlementError::Account(e)) => Some(account_user(e)),
+            Error::Session(SessionError::UserMismatch { actual, .. }) => Some(*actual),
+            Error::Session(SessionError::KillSwitchActive { user_id }) => Some(*user_id),
+            _ => None,
+        }
+    }
+
+    // 错误涉及的订单 (如果有)
+    pub fn order_id(&self) -> Option<OrderID> {
+        match self {
+            Error::Engine(
+                EngineError::OrderNotFound { order_id }
+                | EngineError::DuplicateOrderId { order_id }
+                | EngineError::InvalidQuantity { order_id, .. }
+                | EngineError::InvalidPrice { order_id, .. }
+                | EngineError::QuantityNotAboveFilled { order_id, .. },
+            )
+            | Error::Settlement(SettlementError::UnknownOrder { order_id }) => Some(*order_id),
+            _ => None,
+        }
+    }
+}
+
+fn account_code(e: &AccountError) -> &'static str {
+    match e {
+        AccountError::UserNotFound { .. } => "USER_NOT_FOUND",
+        AccountError::AssetNotFound { .. } => "ASSET_NOT_FOUND",
+        AccountError::InsufficientAvailable { .. } => "INSUFFICIENT_BALANCE",
+        AccountError::InsufficientFrozen { .. } => "INSUFFICIENT_FROZEN",
+        AccountError::InsufficientHold { .. } => "INSUFFICIENT_HOLD",
+        AccountError::InsufficientPendingDeposit { .. } => "INSUFFICIENT_PENDING_DEPOSIT",
+        AccountError::InvalidAmount { .. } => "INVALID_AMOUNT",
+        AccountError::SelfTransfer { .. } => "SELF_TRANSFER",
+        AccountError::NotSubAccount { .. } => "NOT_SUB_ACCOUNT",
+        AccountError::InvalidSubAccount { .. } => "INVALID_SUB_ACCOUNT",
+        AccountError::Overflow { .. } => "BALANCE_OVERFLOW",
+        AccountError::UnknownAsset { .. } => "UNKNOWN_ASSET",
+        AccountError::AssetNotAllowed { .. } => "ASSET_NOT_ALLOWED",
+        AccountError::ExcessPrecision { .. } => "EXCESS_PRECISION",
+    }
+}
+
+fn account_user(e: &AccountError) -> UserID {
+    match e {
+        AccountError::UserNotFound { user_id }
+        | AccountError::AssetNotFound { user_id, .. }
+        | AccountError::InsufficientAvailable { user_id, .. }
+        | AccountError::InsufficientFrozen { user_id, .. }
+        | AccountError::InsufficientHold { user_id, .. }
+        | AccountError::InsufficientPendingDeposit { user_id, .. }
+        | AccountError::InvalidAmount { user_id, .. }
+        | AccountError::SelfTransfer { user_id }
+        | AccountError::Overflow { user_id, .. }
+        | AccountError::UnknownAsset { user_id, .. }
+        | AccountError::AssetNotAllowed { user_id, .. }
+        | AccountError::ExcessPrecision { user_id, .. } => *user_id,
+        AccountError::NotSubAccount { sub, .. } | AccountError::InvalidSubAccount { sub } => *sub,
+    }
+}
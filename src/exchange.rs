@@ -0,0 +1,147 @@
+use std::io::Write;
+use rust_decimal::Decimal;
+use crate::account::{AccountError, AccountManager};
+use crate::engine::OrderBook;
+use crate::error::Error;
+use crate::journal::Journal;
+use crate::observer::EventListener;
+use crate::settlement::Settlement;
+use crate::snapshot::{Decoder, Encoder};
+use crate::types::{Asset, Instrument, Order, OrderID, Price, Quantity, TradeEvent, UserID};
+
+// 输入指令: 所有状态变更都经由指令驱动，按相同顺序重放得到相同状态
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Deposit { user_id: UserID, asset: Asset, amount: Decimal },
+    Place(Order),
+    Cancel { order_id: OrderID },
+    // quantity 为改单后的剩余数量
+    Amend { order_id: OrderID, price: Price, quantity: Quantity },
+    // order_qty 为改单后的订单总量 (含已成交)，剩余数量按处理时的实际成交计算，不大于已成交时拒绝
+    Replace { order_id: OrderID, price: Price, order_qty: Quantity },
+}
+
+impl Command {
+    // 不依赖状态的校验，在分配序号、写日志之前执行: 被拒绝的指令不会进入日志
+    pub fn validate(&self) -> Result<(), Error> {
+        if let Command::Deposit { user_id, asset, amount } = self
+            && *amount <= Decimal::ZERO
+        {
+            return Err(AccountError::InvalidAmount { user_id: *user_id, asset: *asset, amount: *amount }.into());
+        }
+        Ok(())
+    }
+}
+
+// 单交易对的确定性状态机: 订单簿 + 账户 + 结算
+pub struct Exchange {
+    instrument: Instrument,
+    book: OrderBook,
+    account: AccountManager,
+    settlement: Settlement,
+    last_seq: u64,
+}
+
+impl Exchange {
+    pub fn new(instrument: Instrument, base_precision: u32, quote_precision: u32) -> Self {
+        Self {
+            instrument,
+            book: OrderBook::new(),
+            account: AccountManager::new(),
+            settlement: Settlement::new(instrument, base_precision, quote_precision),
+            last_seq: 0,
+        }
+    }
+
+    pub fn instrument(&self) -> Instrument {
+        self.instrument
+    }
+
+    pub fn book(&self) -> &OrderBook {
+        &self.book
+    }
+
+    pub fn account(&self) -> &AccountManager {
+        &self.account
+    }
+
+    pub fn settlement(&self) -> &Settlement {
+        &self.settlement
+    }
+
+    // 同时监听订单事件与余额事件
+    pub fn add_listener<L: EventListener + Clone + 'static>(&mut self, listener: L) {
+        self.book.add_listener(Box::new(listener.clone()));
+        self.account.add_listener(Box::new(listener));
+    }
+
+    // 最后一条已处理指令的序号，重放时从下一条开始
+    pub fn last_seq(&self) -> u64 {
+        self.last_seq
+    }
+
+    // 先写日志再处理: 日志写失败时指令不会被执行，校验不通过的指令不写日志
+    pub fn execute<W: Write>(&mut self, journal: &mut Journal<W>, command: &Command) -> Result<Vec<TradeEvent>, Error> {
+        command.validate()?;
+        let seq = journal.append(command)?;
+        self.apply(seq, command)
+    }
+
+    // 处理一条指令。被拒绝的指令同样推进序号，重放时会得到同样的拒绝
+    pub fn apply(&mut self, seq: u64, command: &Command) -> Result<Vec<TradeEvent>, Error> {
+        self.last_seq = seq;
+        match command {
+            Command::Deposit { user_id, asset, amount } => {
+                self.account.deposit(*user_id, *asset, *amount)?;
+                Ok(Vec::new())
+            }
+            // 下单与改单先预演成交并整体校验结算，全部通过后才改动订单簿，
+            // 之后的撮合与结算不会失败，订单簿与余额不会出现分歧
+            Command::Place(order) => {
+                self.book.validate_order(order)?;
+                let preview = self.book.preview_match(order);
+                self.settlement.check(&self.account, &preview, Some(order))?;
+                self.settlement.reserve(&mut self.account, order)?;
+                let trades = self.book.match_order(order.clone());
+                self.settlement.settle(&mut self.account, &trades)?;
+                Ok(trades)
+            }
+            Command::Cancel { order_id } => {
+                self.book.cancel_order(*order_id)?;
+                self.settlement.release(&mut self.account, *order_id)?;
+                Ok(Vec::new())
+            }
+            Command::Amend { order_id, price, quantity } => self.amend(*order_id, *price, *quantity),
+            Command::Replace { order_id, price, order_qty } => {
+                let quantity = self.book.replace_quantity(*order_id, *order_qty)?;
+                self.amend(*order_id, *price, quantity)
+            }
+        }
+    }
+
+    fn amend(&mut self, order_id: OrderID, price: Price, quantity: Quantity) -> Result<Vec<TradeEvent>, Error> {
+        let amended = self.book.validate_amend(order_id, price, quantity)?;
+        let preview = self.book.preview_match(&amended);
+        self.settlement.check(&self.account, &preview, Some(&amended))?;
+        self.settlement.amend(&mut self.account, &amended)?;
+        let trades = self.book.amend_order(order_id, price, quantity)?;
+        self.settlement.settle(&mut self.account, &trades)?;
+        Ok(trades)
+    }
+
+    pub(crate) fn encode(&self, e: &mut Encoder) {
+        e.asset(self.instrument.base);
+        e.asset(self.instrument.quote);
+        self.settlement.encode(e);
+        self.book.encode(e);
+        self.account.encode(e);
+    }
+
+    pub(crate) fn decode(d: &mut Decoder, last_seq: u64) -> Option<Self> {
+        let instrument = Instrument::new(d.asset()?, d.asset()?);
+        let settlement = Settlement::decode(d, instrument)?;
+        let book = OrderBook::decode(d)?;
+        let account = AccountManager::decode(d)?;
+        Some(Self { instrument, book, account, settlement, last_seq })
+    }
+}
@@ -0,0 +1,237 @@
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+// FIX 4.4 消息编解码: tag=value 以 SOH (0x01) 分隔，
+// 8 (BeginString) / 9 (BodyLength) 开头，10 (CheckSum) 结尾，编码时自动生成
+pub const SOH: u8 = 0x01;
+pub const BEGIN_STRING: &str = "FIX.4.4";
+// BodyLength 上限，超过即视为格式错误，避免为对方声明的长度无限缓冲
+pub const MAX_BODY_LEN: usize = 8192;
+// 一条完整消息的最大字节数: 8=FIX.4.4<SOH>9=<BodyLength><SOH> + 消息体 + 10=xxx<SOH>
+pub const MAX_MESSAGE_LEN: usize = BEGIN_STRING.len() + 6 + MAX_BODY_LEN_DIGITS + MAX_BODY_LEN + 7;
+const MAX_BODY_LEN_DIGITS: usize = 4;
+
+pub mod tag {
+    pub const AVG_PX: u32 = 6;
+    pub const BEGIN_SEQ_NO: u32 = 7;
+    pub const BEGIN_STRING: u32 = 8;
+    pub const BODY_LENGTH: u32 = 9;
+    pub const CHECK_SUM: u32 = 10;
+    pub const CL_ORD_ID: u32 = 11;
+    pub const CUM_QTY: u32 = 14;
+    pub const END_SEQ_NO: u32 = 16;
+    pub const EXEC_ID: u32 = 17;
+    pub const LAST_PX: u32 = 31;
+    pub const LAST_QTY: u32 = 32;
+    pub const MSG_SEQ_NUM: u32 = 34;
+    pub const MSG_TYPE: u32 = 35;
+    pub const NEW_SEQ_NO: u32 = 36;
+    pub const ORDER_ID: u32 = 37;
+    pub const ORDER_QTY: u32 = 38;
+    pub const ORD_STATUS: u32 = 39;
+    pub const ORD_TYPE: u32 = 40;
+    pub const ORIG_CL_ORD_ID: u32 = 41;
+    pub const POSS_DUP_FLAG: u32 = 43;
+    pub const PRICE: u32 = 44;
+    pub const REF_SEQ_NUM: u32 = 45;
+    pub const SENDER_COMP_ID: u32 = 49;
+    pub const SENDING_TIME: u32 = 52;
+    pub const SIDE: u32 = 54;
+    pub const SYMBOL: u32 = 55;
+    pub const TARGET_COMP_ID: u32 = 56;
+    pub const TEXT: u32 = 58;
+    pub const ENCRYPT_METHOD: u32 = 98;
+    pub const CXL_REJ_REASON: u32 = 102;
+    pub const ORD_REJ_REASON: u32 = 103;
+    pub const HEART_BT_INT: u32 = 108;
+    pub const TEST_REQ_ID: u32 = 112;
+    pub const ORIG_SENDING_TIME: u32 = 122;
+    pub const GAP_FILL_FLAG: u32 = 123;
+    pub const RESET_SEQ_NUM_FLAG: u32 = 141;
+    pub const EXEC_TYPE: u32 = 150;
+    pub const LEAVES_QTY: u32 = 151;
+    pub const REF_MSG_TYPE: u32 = 372;
+    pub const CXL_REJ_RESPONSE_TO: u32 = 434;
+    pub const PASSWORD: u32 = 554;
+}
+
+pub mod msg_type {
+    pub const HEARTBEAT: &str = "0";
+    pub const TEST_REQUEST: &str = "1";
+    pub const RESEND_REQUEST: &str = "2";
+    pub const REJECT: &str = "3";
+    pub const SEQUENCE_RESET: &str = "4";
+    pub const LOGOUT: &str = "5";
+    pub const EXECUTION_REPORT: &str = "8";
+    pub const ORDER_CANCEL_REJECT: &str = "9";
+    pub const LOGON: &str = "A";
+    pub const NEW_ORDER_SINGLE: &str = "D";
+    pub const ORDER_CANCEL_REQUEST: &str = "F";
+    pub const ORDER_CANCEL_REPLACE_REQUEST: &str = "G";
+}
+
+// 标准消息头中除 8 / 9 / 35 以外的字段，编码时排在消息体之前
+const HEADER_TAGS: [u32; 6] = [
+    tag::SENDER_COMP_ID,
+    tag::TARGET_COMP_ID,
+    tag::MSG_SEQ_NUM,
+    tag::SENDING_TIME,
+    tag::POSS_DUP_FLAG,
+    tag::ORIG_SENDING_TIME,
+];
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum FixError {
+    #[error("FIX 消息格式错误: {reason}")]
+    Malformed { reason: String },
+    #[error("FIX 消息校验和错误: 期望 {expected}, 实际 {actual}")]
+    CheckSum { expected: u8, actual: u8 },
+}
+
+fn malformed(reason: &str) -> FixError {
+    FixError::Malformed { reason: reason.to_string() }
+}
+
+// 一条 FIX 消息，保存 35 (MsgType) 及之后除 10 以外的全部字段
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixMessage {
+    fields: Vec<(u32, String)>,
+}
+
+impl FixMessage {
+    pub fn new(msg_type: &str) -> Self {
+        Self { fields: vec![(tag::MSG_TYPE, msg_type.to_string())] }
+    }
+
+    pub fn msg_type(&self) -> &str {
+        &self.fields[0].1
+    }
+
+    pub fn with(mut self, tag: u32, value: impl ToString) -> Self {
+        self.set(tag, value);
+        self
+    }
+
+    // 已有同名字段时覆盖
+    pub fn set(&mut self, tag: u32, value: impl ToString) {
+        let value = value.to_string();
+        match self.fields.iter_mut().find(|(t, _)| *t == tag) {
+            Some(field) => field.1 = value,
+            None => self.fields.push((tag, value)),
+        }
+    }
+
+    pub fn get(&self, tag: u32) -> Option<&str> {
+        self.fields.iter().find(|(t, _)| *t == tag).map(|(_, v)| v.as_str())
+    }
+
+    pub fn parse<T: FromStr>(&self, tag: u32) -> Option<T> {
+        self.get(tag)?.parse().ok()
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = (u32, &str)> {
+        self.fields.iter().map(|(t, v)| (*t, v.as_str()))
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::with_capacity(128);
+        let header = HEADER_TAGS.iter().filter_map(|&t| self.get(t).map(|v| (t, v)));
+        let rest = self.fields[1..].iter().filter(|(t, _)| !HEADER_TAGS.contains(t)).map(|(t, v)| (*t, v.as_str()));
+        for (t, v) in std::iter::once((tag::MSG_TYPE, self.msg_type())).chain(header).chain(rest) {
+            body.extend_from_slice(format!("{t}={v}").as_bytes());
+            body.push(SOH);
+        }
+
+        let mut buf = format!("8={BEGIN_STRING}\x019={}\x01", body.len()).into_bytes();
+        buf.extend_from_slice(&body);
+        let sum = checksum(&buf);
+        buf.extend_from_slice(format!("10={sum:03}\x01").as_bytes());
+        buf
+    }
+
+    // 从字节流头部解出一条消息，返回消息与占用的字节数；数据还不完整时返回 None
+    pub fn decode(buf: &[u8]) -> Result<Option<(FixMessage, usize)>, FixError> {
+        let begin = format!("8={BEGIN_STRING}\x019=").into_bytes();
+        let prefix = buf.len().min(begin.len());
+        if buf[..prefix] != begin[..prefix] {
+            return Err(malformed("BeginString 不是 FIX.4.4"));
+        }
+        if buf.len() < begin.len() {
+            return Ok(None);
+        }
+        let Some(soh) = buf[begin.len()..].iter().position(|&b| b == SOH) else {
+            if buf.len() - begin.len() > MAX_BODY_LEN_DIGITS {
+                return Err(malformed("BodyLength 无效"));
+            }
+            return Ok(None);
+        };
+        let body_start = begin.len() + soh + 1;
+        let body_len: usize = std::str::from_utf8(&buf[begin.len()..body_start - 1])
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| malformed("BodyLength 无效"))?;
+        if body_len > MAX_BODY_LEN {
+            return Err(malformed("BodyLength 超过上限"));
+        }
+        let body_end = body_start.checked_add(body_len).ok_or_else(|| malformed("BodyLength 无效"))?;
+        let total = body_end + 7; // 10=xxx<SOH>
+        if buf.len() < total {
+            return Ok(None);
+        }
+
+        let trailer = &buf[body_end..total];
+        if &trailer[..3] != b"10=" || trailer[6] != SOH {
+            return Err(malformed("CheckSum 缺失或位置不对"));
+        }
+        let expected = std::str::from_utf8(&trailer[3..6])
+            .ok()
+            .and_then(|s| s.parse::<u8>().ok())
+            .ok_or_else(|| malformed("CheckSum 无效"))?;
+        let actual = checksum(&buf[..body_end]);
+        if expected != actual {
+            return Err(FixError::CheckSum { expected, actual });
+        }
+
+        let body = std::str::from_utf8(&buf[body_start..body_end]).map_err(|_| malformed("消息不是 UTF-8"))?;
+        let mut fields = Vec::new();
+        for field in body.split('\x01').filter(|f| !f.is_empty()) {
+            let (t, v) = field.split_once('=').ok_or_else(|| malformed("字段缺少 ="))?;
+            let t = t.parse().map_err(|_| malformed("tag 不是数字"))?;
+            fields.push((t, v.to_string()));
+        }
+        if fields.first().is_none_or(|(t, _)| *t != tag::MSG_TYPE) {
+            return Err(malformed("第三个字段必须是 MsgType"));
+        }
+        Ok(Some((FixMessage { fields }, total)))
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |sum, &b| sum.wrapping_add(b))
+}
+
+// UTC 时间戳，格式 YYYYMMDD-HH:MM:SS.sss
+pub fn sending_time(now: SystemTime) -> String {
+    let elapsed = now.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let (days, secs) = (elapsed.as_secs() / 86_400, elapsed.as_secs() % 86_400);
+
+    // 公历日期换算 (Howard Hinnant 的 civil_from_days)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}:{:02}:{:02}.{:03}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        elapsed.subsec_millis()
+    )
+}
@@ -0,0 +1,959 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use rust_decimal::Decimal;
+use crate::actor::{EngineHandle, EngineOutput, Outcome};
+use crate::exchange::Command;
+use crate::fix::{self, msg_type, tag, FixMessage};
+use crate::types::{Instrument, Order, OrderID, OrderSide, OrderStatus, TradeEvent, UserID};
+
+const LOGON_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_HEART_BT_INT: u64 = 30;
+// 每个会话在内存中保留的最近业务消息条数，更早的重发请求从消息文件读取
+const RESEND_CACHE: usize = 4096;
+
+// 接入方: 以对方的 SenderCompID 识别，映射到撮合系统中的用户
+#[derive(Debug, Clone)]
+pub struct Counterparty {
+    pub user_id: UserID,
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FixConfig {
+    pub comp_id: String,    // 本方 CompID
+    pub store_dir: PathBuf, // 序号文件目录，每个接入方一个文件
+    pub counterparties: HashMap<String, Counterparty>,
+}
+
+impl FixConfig {
+    pub fn new(comp_id: impl Into<String>, store_dir: impl Into<PathBuf>) -> Self {
+        Self { comp_id: comp_id.into(), store_dir: store_dir.into(), counterparties: HashMap::new() }
+    }
+
+    pub fn counterparty(mut self, comp_id: impl Into<String>, user_id: UserID, password: Option<&str>) -> Self {
+        self.counterparties.insert(comp_id.into(), Counterparty { user_id, password: password.map(String::from) });
+        self
+    }
+}
+
+// 单个接入方的会话状态，跨连接保留: 断线期间产生的回报照常编号，重连后由对方 ResendRequest 补发
+struct SessionState {
+    our: String,
+    their: String,
+    next_out: u64,
+    next_in: u64,
+    sent: BTreeMap<u64, FixMessage>, // 最近发出的业务消息，最多 RESEND_CACHE 条
+    trimmed: u64,                    // 不大于它的消息已移出内存，只在消息文件里
+    path: PathBuf,
+    store: fs::File, // 业务消息文件，按发送顺序追加编码后的消息，用于重发
+    stream: Option<TcpStream>,
+    connection: u64, // 当前持有 stream 的连接
+    last_out: Instant,
+}
+
+impl SessionState {
+    // 序号文件内容: "<next_out> <next_in>"
+    fn load(config: &FixConfig, their: &str) -> io::Result<Self> {
+        let path = config.store_dir.join(format!("{}-{}.seqnums", config.comp_id, their));
+        let (next_out, next_in) = match fs::read_to_string(&path) {
+            Ok(text) => {
+                let mut nums = text.split_whitespace().map(|n| n.parse::<u64>());
+                match (nums.next(), nums.next()) {
+                    (Some(Ok(out)), Some(Ok(inb))) => (out, inb),
+                    _ => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("序号文件损坏: {}", path.display()))),
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (1, 1),
+            Err(e) => return Err(e),
+        };
+
+        // 业务消息文件与序号文件放在一起，截掉没写完的尾部，最近的消息载入内存
+        let store_path = path.with_extension("messages");
+        let mut store = fs::OpenOptions::new().read(true).append(true).create(true).open(&store_path)?;
+        let mut bytes = Vec::new();
+        store.read_to_end(&mut bytes)?;
+        let (messages, valid) = decode_messages(&bytes);
+        if valid < bytes.len() {
+            store.set_len(valid as u64)?;
+        }
+        let mut state = Self {
+            our: config.comp_id.clone(),
+            their: their.to_string(),
+            next_out,
+            next_in,
+            sent: BTreeMap::new(),
+            trimmed: 0,
+            path,
+            store,
+            stream: None,
+            connection: 0,
+            last_out: Instant::now(),
+        };
+        for (seq, msg) in messages {
+            state.cache(seq, msg);
+        }
+        Ok(state)
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, format!("{} {}\n", self.next_out, self.next_in))?;
+        fs::rename(&tmp, &self.path)
+    }
+
+    fn reset(&mut self) {
+        self.next_out = 1;
+        self.next_in = 1;
+        self.sent.clear();
+        self.trimmed = 0;
+        if self.store.set_len(0).is_err() {
+            self.disconnect();
+        }
+    }
+
+    fn cache(&mut self, seq: u64, msg: FixMessage) {
+        self.sent.insert(seq, msg);
+        while self.sent.len() > RESEND_CACHE {
+            if let Some((seq, _)) = self.sent.pop_first() {
+                self.trimmed = self.trimmed.max(seq);
+            }
+        }
+    }
+
+    // 重发范围内的业务消息: 都在内存中时直接取，否则读消息文件
+    fn stored(&self, begin: u64, end: u64) -> BTreeMap<u64, FixMessage> {
+        if begin > self.trimmed {
+            return self.sent.range(begin..=end).map(|(seq, msg)| (*seq, msg.clone())).collect();
+        }
+        let path = self.path.with_extension("messages");
+        match fs::read(&path) {
+            Ok(bytes) => decode_messages(&bytes).0.into_iter().filter(|(seq, _)| (begin..=end).contains(seq)).collect(),
+            Err(_) => self.sent.range(begin..=end).map(|(seq, msg)| (*seq, msg.clone())).collect(),
+        }
+    }
+
+    fn set_next_in(&mut self, next_in: u64) {
+        self.next_in = next_in;
+        self.persist();
+    }
+
+    // 序号无法落盘时断开连接，避免重启后序号回退
+    fn persist(&mut self) {
+        if self.save().is_err() {
+            self.disconnect();
+        }
+    }
+
+    fn disconnect(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+
+    fn header(&self, msg: FixMessage, seq: u64) -> FixMessage {
+        msg.with(tag::SENDER_COMP_ID, &self.our)
+            .with(tag::TARGET_COMP_ID, &self.their)
+            .with(tag::MSG_SEQ_NUM, seq)
+            .with(tag::SENDING_TIME, fix::sending_time(SystemTime::now()))
+    }
+
+    // 分配序号、保存并发送；未连接时只保存。会话消息不保存，重发时以 GapFill 跳过
+    fn send(&mut self, msg: FixMessage) {
+        let msg = self.header(msg, self.next_out);
+        if is_application(msg.msg_type()) {
+            if self.store.write_all(&msg.encode()).is_err() {
+                self.disconnect();
+            }
+            self.cache(self.next_out, msg.clone());
+        }
+        self.next_out += 1;
+        self.persist();
+        self.write(&msg);
+    }
+
+    fn write(&mut self, msg: &FixMessage) {
+        if let Some(stream) = self.stream.as_mut() {
+            if stream.write_all(&msg.encode()).is_err() {
+                self.disconnect();
+            }
+            self.last_out = Instant::now();
+        }
+    }
+
+    // 业务消息原样重发 (PossDupFlag=Y)，会话消息与找不到的消息用 SequenceReset-GapFill 跳过
+    fn resend(&mut self, begin: u64, end: u64) {
+        let last = self.next_out - 1;
+        let end = if end == 0 || end > last { last } else { end };
+        let mut stored = self.stored(begin.max(1), end);
+        let mut gap_start = None;
+        for seq in begin.max(1)..=end {
+            match stored.remove(&seq) {
+                Some(mut msg) => {
+                    if let Some(start) = gap_start.take() {
+                        self.write(&self.gap_fill(start, seq));
+                    }
+                    let orig = msg.get(tag::SENDING_TIME).unwrap_or_default().to_string();
+                    msg.set(tag::POSS_DUP_FLAG, "Y");
+                    msg.set(tag::ORIG_SENDING_TIME, orig);
+                    msg.set(tag::SENDING_TIME, fix::sending_time(SystemTime::now()));
+                    self.write(&msg);
+                }
+                None => {
+                    gap_start.get_or_insert(seq);
+                }
+            }
+        }
+        if let Some(start) = gap_start {
+            self.write(&self.gap_fill(start, end + 1));
+        }
+    }
+
+    fn gap_fill(&self, seq: u64, new_seq: u64) -> FixMessage {
+        let msg = FixMessage::new(msg_type::SEQUENCE_RESET)
+            .with(tag::POSS_DUP_FLAG, "Y")
+            .with(tag::GAP_FILL_FLAG, "Y")
+            .with(tag::NEW_SEQ_NO, new_seq);
+        self.header(msg, seq)
+    }
+}
+
+// 解码消息文件，返回 (序号, 消息) 与完整消息占用的字节数，之后的部分是没写完或损坏的尾部
+fn decode_messages(bytes: &[u8]) -> (Vec<(u64, FixMessage)>, usize) {
+    let mut messages = Vec::new();
+    let mut pos = 0;
+    while let Ok(Some((msg, len))) = FixMessage::decode(&bytes[pos..]) {
+        pos += len;
+        if let Some(seq) = msg.parse(tag::MSG_SEQ_NUM) {
+            messages.push((seq, msg));
+        }
+    }
+    (messages, pos)
+}
+
+fn is_application(msg_type: &str) -> bool {
+    !matches!(
+        msg_type,
+        msg_type::HEARTBEAT | msg_type::TEST_REQUEST | msg_type::RESEND_REQUEST | msg_type::REJECT
+            | msg_type::SEQUENCE_RESET | msg_type::LOGOUT | msg_type::LOGON
+    )
+}
+
+// 尚未处理完的撤单 / 改单请求，按提交顺序与引擎结果一一对应
+enum Pending {
+    Cancel { cl_ord_id: String },
+    Replace { cl_ord_id: String, price: Decimal, order_qty: Decimal },
+}
+
+// 通过 FIX 下的活动订单
+struct OrderInfo {
+    owner: String,
+    cl_ord_id: String,
+    side: OrderSide,
+    price: Decimal,
+    order_qty: Decimal,
+    cum_qty: Decimal,
+    notional: Decimal,
+    pending: VecDeque<Pending>,
+}
+
+impl OrderInfo {
+    fn ord_status(&self) -> &'static str {
+        if self.cum_qty.is_zero() {
+            "0"
+        } else if self.cum_qty < self.order_qty {
+            "1"
+        } else {
+            "2"
+        }
+    }
+}
+
+#[derive(Default)]
+struct Orders {
+    by_id: HashMap<OrderID, OrderInfo>,
+    by_cl_ord_id: HashMap<(String, String), OrderID>,
+    log: Option<fs::File>, // 订单表文件，见 recover
+}
+
+impl Orders {
+    // 订单表文件: 每条记录为 "<OrderID> SOH <CompID> SOH <ClOrdID> SOH LF" (FIX 字段值不含 SOH)，
+    // 同一订单以最后一条为准。重启时按引擎中仍活动的订单重建，成交量取自订单簿，随后重写文件
+    fn recover(engine: &EngineHandle, config: &FixConfig) -> io::Result<Self> {
+        let path = config.store_dir.join(format!("{}.orders", config.comp_id));
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        let text = String::from_utf8_lossy(&bytes);
+        let mut records: Vec<&str> = text.split("\x01\n").collect();
+        records.pop(); // 末尾是空串或没写完的记录
+        let mut known = HashMap::new();
+        for record in records {
+            let fields: Vec<&str> = record.split('\x01').collect();
+            if let [id, owner, cl_ord_id] = fields[..]
+                && let Ok(id) = id.parse::<OrderID>()
+            {
+                known.insert(id, (owner.to_string(), cl_ord_id.to_string()));
+            }
+        }
+
+        let ids: Vec<OrderID> = known.keys().copied().collect();
+        let reports = engine
+            .query(move |ex| ids.iter().filter_map(|id| ex.book().order_status(*id)).collect::<Vec<_>>())
+            .map_err(io::Error::other)?;
+        let mut orders = Orders::default();
+        let mut data = Vec::new();
+        for r in reports {
+            let Some((owner, cl_ord_id)) = known.remove(&r.order_id) else { continue };
+            let active = matches!(r.status, OrderStatus::Open | OrderStatus::PartiallyFilled);
+            if !active || config.counterparties.get(&owner).map(|c| c.user_id) != Some(r.user_id) {
+                continue;
+            }
+            let info = OrderInfo {
+                owner,
+                cl_ord_id,
+                side: r.side,
+                price: r.price,
+                order_qty: r.original_quantity,
+                cum_qty: r.filled_quantity,
+                notional: r.average_price.unwrap_or_default() * r.filled_quantity,
+                pending: VecDeque::new(),
+            };
+            data.extend_from_slice(&record(r.order_id, &info));
+            orders.insert(r.order_id, info);
+        }
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, data)?;
+        fs::rename(&tmp, &path)?;
+        orders.log = Some(fs::OpenOptions::new().append(true).open(&path)?);
+        Ok(orders)
+    }
+
+    fn insert(&mut self, order_id: OrderID, info: OrderInfo) {
+        self.by_cl_ord_id.insert((info.owner.clone(), info.cl_ord_id.clone()), order_id);
+        self.by_id.insert(order_id, info);
+    }
+
+    // 记下订单当前的 ClOrdID
+    fn record(&mut self, order_id: OrderID) -> io::Result<()> {
+        let (Some(info), Some(log)) = (self.by_id.get(&order_id), self.log.as_mut()) else { return Ok(()) };
+        log.write_all(&record(order_id, info))
+    }
+
+    fn remove(&mut self, order_id: OrderID) -> Option<OrderInfo> {
+        let info = self.by_id.remove(&order_id)?;
+        self.by_cl_ord_id.remove(&(info.owner.clone(), info.cl_ord_id.clone()));
+        Some(info)
+    }
+}
+
+struct Shared {
+    engine: EngineHandle,
+    symbol: String,
+    config: FixConfig,
+    sessions: Mutex<HashMap<String, Arc<Mutex<SessionState>>>>,
+    orders: Mutex<Orders>,
+    next_order_id: AtomicU64,
+    next_exec_id: AtomicU64,
+    next_connection: AtomicU64,
+}
+
+// FIX 4.4 接入: Logon / Logout / Heartbeat / TestRequest / ResendRequest / SequenceReset 会话层，
+// NewOrderSingle / OrderCancelRequest / OrderCancelReplaceRequest 映射为下单 / 撤单 / 改单，
+// 结果与成交以 ExecutionReport / OrderCancelReject 回报。只支持限价单，Symbol 为 "BASE/QUOTE"
+pub struct FixAcceptor(Arc<Shared>);
+
+impl FixAcceptor {
+    // outputs 为引擎的输出通道，所有回报都按引擎处理顺序从这里生成
+    // 订单号从引擎中已有的最大订单号之后开始分配 (如从日志恢复之后)，
+    // 仍活动的 FIX 订单按订单表文件恢复，重启后照常回报成交、接受撤单与改单
+    pub fn new(engine: EngineHandle, outputs: Receiver<EngineOutput>, instrument: Instrument, config: FixConfig) -> io::Result<Self> {
+        fs::create_dir_all(&config.store_dir)?;
+        let orders = Orders::recover(&engine, &config)?;
+        let last = engine.query(|ex| ex.book().last_order_id()).ok().flatten();
+        let shared = Arc::new(Shared {
+            engine,
+            symbol: instrument.to_string(),
+            config,
+            sessions: Mutex::default(),
+            orders: Mutex::new(orders),
+            next_order_id: AtomicU64::new(last.map_or(1, |id| id + 1)),
+            next_exec_id: AtomicU64::new(1),
+            next_connection: AtomicU64::new(1),
+        });
+        let dispatch = shared.clone();
+        thread::spawn(move || {
+            for output in outputs {
+                if let EngineOutput::Processed { command, outcome, .. } = output {
+                    dispatch.dispatch(command, outcome);
+                }
+            }
+        });
+        Ok(Self(shared))
+    }
+
+    pub fn serve(&self, listener: TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let shared = self.0.clone();
+            thread::spawn(move || shared.run_connection(stream));
+        }
+        Ok(())
+    }
+}
+
+impl Shared {
+    fn state(&self, their: &str) -> io::Result<Arc<Mutex<SessionState>>> {
+        let mut sessions = self.sessions.lock().unwrap();
+        if let Some(state) = sessions.get(their) {
+            return Ok(state.clone());
+        }
+        let state = Arc::new(Mutex::new(SessionState::load(&self.config, their)?));
+        sessions.insert(their.to_string(), state.clone());
+        Ok(state)
+    }
+
+    fn send_to(&self, their: &str, msg: FixMessage) {
+        if let Ok(state) = self.state(their) {
+            state.lock().unwrap().send(msg);
+        }
+    }
+
+    fn exec_id(&self) -> u64 {
+        self.next_exec_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn exec_report(&self, order_id: OrderID, info: &OrderInfo, exec_type: &str, ord_status: &str) -> FixMessage {
+        let leaves = match ord_status {
+            "2" | "4" | "8" => Decimal::ZERO,
+            _ => info.order_qty - info.cum_qty,
+        };
+        let avg_px = if info.cum_qty.is_zero() { Decimal::ZERO } else { info.notional / info.cum_qty };
+        FixMessage::new(msg_type::EXECUTION_REPORT)
+            .with(tag::ORDER_ID, order_id)
+            .with(tag::CL_ORD_ID, &info.cl_ord_id)
+            .with(tag::EXEC_ID, self.exec_id())
+            .with(tag::EXEC_TYPE, exec_type)
+            .with(tag::ORD_STATUS, ord_status)
+            .with(tag::SYMBOL, &self.symbol)
+            .with(tag::SIDE, side_code(&info.side))
+            .with(tag::ORD_TYPE, "2")
+            .with(tag::PRICE, info.price.normalize())
+            .with(tag::ORDER_QTY, info.order_qty.normalize())
+            .with(tag::CUM_QTY, info.cum_qty.normalize())
+            .with(tag::LEAVES_QTY, leaves.normalize())
+            .with(tag::AVG_PX, avg_px.normalize())
+    }
+
+    // 没有进入引擎的新单拒绝
+    fn reject_report(&self, request: &FixMessage, reason: u32, text: &str) -> FixMessage {
+        FixMessage::new(msg_type::EXECUTION_REPORT)
+            .with(tag::ORDER_ID, "NONE")
+            .with(tag::CL_ORD_ID, request.get(tag::CL_ORD_ID).unwrap_or_default())
+            .with(tag::EXEC_ID, self.exec_id())
+            .with(tag::EXEC_TYPE, "8")
+            .with(tag::ORD_STATUS, "8")
+            .with(tag::SYMBOL, request.get(tag::SYMBOL).unwrap_or_default())
+            .with(tag::SIDE, request.get(tag::SIDE).unwrap_or_default())
+            .with(tag::CUM_QTY, 0)
+            .with(tag::LEAVES_QTY, 0)
+            .with(tag::AVG_PX, 0)
+            .with(tag::ORD_REJ_REASON, reason)
+            .with(tag::TEXT, text)
+    }
+
+    fn cancel_reject(order_id: Option<OrderID>, ord_status: &str, cl_ord_id: &str, orig: &str, response_to: &str, reason: u32, text: &str) -> FixMessage {
+        FixMessage::new(msg_type::ORDER_CANCEL_REJECT)
+            .with(tag::ORDER_ID, order_id.map_or(String::from("NONE"), |id| id.to_string()))
+            .with(tag::CL_ORD_ID, cl_ord_id)
+            .with(tag::ORIG_CL_ORD_ID, orig)
+            .with(tag::ORD_STATUS, ord_status)
+            .with(tag::CXL_REJ_RESPONSE_TO, response_to)
+            .with(tag::CXL_REJ_REASON, reason)
+            .with(tag::TEXT, text)
+    }
+
+    // 按引擎处理顺序把结果翻译成回报
+    fn dispatch(&self, command: Command, outcome: Outcome) {
+        let mut out = Vec::new();
+        let mut orders = self.orders.lock().unwrap();
+        match command {
+            Command::Place(order) => match (orders.by_id.get(&order.id), outcome) {
+                (Some(info), Ok(trades)) => {
+                    out.push((info.owner.clone(), self.exec_report(order.id, info, "0", "0")));
+                    self.fills(&mut orders, &trades, &mut out);
+                }
+                (Some(info), Err(e)) => {
+                    let report = self.exec_report(order.id, info, "8", "8")
+                        .with(tag::ORD_REJ_REASON, 99)
+                        .with(tag::TEXT, format!("{}: {e}", e.code()));
+                    out.push((info.owner.clone(), report));
+                    self.finish(&mut orders, order.id, "8", &mut out);
+                }
+                // 其他入口下的单也可能与 FIX 挂单成交
+                (None, Ok(trades)) => self.fills(&mut orders, &trades, &mut out),
+                (None, Err(_)) => return,
+            },
+            Command::Cancel { order_id } => {
+                let Some(info) = orders.by_id.get_mut(&order_id) else { return };
+                let Some(Pending::Cancel { cl_ord_id }) = info.pending.pop_front() else { return };
+                match outcome {
+                    Ok(_) => {
+                        let report = self.exec_report(order_id, info, "4", "4")
+                            .with(tag::CL_ORD_ID, cl_ord_id)
+                            .with(tag::ORIG_CL_ORD_ID, &info.cl_ord_id);
+                        out.push((info.owner.clone(), report));
+                        self.finish(&mut orders, order_id, "4", &mut out);
+                    }
+                    Err(e) => {
+                        let text = format!("{}: {e}", e.code());
+                        let reject = Self::cancel_reject(Some(order_id), info.ord_status(), &cl_ord_id, &info.cl_ord_id, "1", 99, &text);
+                        out.push((info.owner.clone(), reject));
+                    }
+                }
+            }
+            Command::Amend { order_id, .. } | Command::Replace { order_id, .. } => {
+                let Some(info) = orders.by_id.get_mut(&order_id) else {
+                    // 其他入口的改单也可能与 FIX 挂单成交
+                    if let Ok(trades) = outcome {
+                        self.fills(&mut orders, &trades, &mut out);
+                    }
+                    return self.deliver(orders, out);
+                };
+                let Some(Pending::Replace { cl_ord_id, price, order_qty }) = info.pending.pop_front() else { return };
+                match outcome {
+                    Ok(trades) => {
+                        let orig = std::mem::replace(&mut info.cl_ord_id, cl_ord_id.clone());
+                        info.price = price;
+                        info.order_qty = order_qty;
+                        let report = self.exec_report(order_id, info, "5", info.ord_status()).with(tag::ORIG_CL_ORD_ID, &orig);
+                        out.push((info.owner.clone(), report));
+                        let owner = info.owner.clone();
+                        orders.by_cl_ord_id.remove(&(owner.clone(), orig));
+                        orders.by_cl_ord_id.insert((owner, cl_ord_id), order_id);
+                        // 写失败时重启后仍以原 ClOrdID 识别该订单
+                        let _ = orders.record(order_id);
+                        self.fills(&mut orders, &trades, &mut out);
+                    }
+                    Err(e) => {
+                        let text = format!("{}: {e}", e.code());
+                        let reject = Self::cancel_reject(Some(order_id), info.ord_status(), &cl_ord_id, &info.cl_ord_id, "2", 99, &text);
+                        out.push((info.owner.clone(), reject));
+                    }
+                }
+            }
+            Command::Deposit { .. } => {}
+        }
+        self.deliver(orders, out);
+    }
+
+    // 先释放订单表再发送，发送时会锁会话
+    fn deliver(&self, orders: MutexGuard<'_, Orders>, out: Vec<(String, FixMessage)>) {
+        drop(orders);
+        for (their, msg) in out {
+            self.send_to(&their, msg);
+        }
+    }
+
+    // 成交双方 (如果是 FIX 订单) 各收到一条 ExecType=F 的回报，完全成交后不再跟踪
+    fn fills(&self, orders: &mut Orders, trades: &[TradeEvent], out: &mut Vec<(String, FixMessage)>) {
+        for trade in trades {
+            for order_id in [trade.maker_order_id, trade.taker_order_id] {
+                let Some(info) = orders.by_id.get_mut(&order_id) else { continue };
+                info.cum_qty += trade.quantity;
+                info.notional += trade.quantity * trade.price;
+                let report = self.exec_report(order_id, info, "F", info.ord_status())
+                    .with(tag::LAST_QTY, trade.quantity.normalize())
+                    .with(tag::LAST_PX, trade.price.normalize());
+                out.push((info.owner.clone(), report));
+                if info.cum_qty >= info.order_qty {
+                    self.finish(orders, order_id, "2", out);
+                }
+            }
+        }
+    }
+
+    // 订单结束后不再跟踪；还在等待引擎处理的撤单 / 改单请求以"太晚"拒绝
+    fn finish(&self, orders: &mut Orders, order_id: OrderID, ord_status: &str, out: &mut Vec<(String, FixMessage)>) {
+        let Some(info) = orders.remove(order_id) else { return };
+        for pending in info.pending {
+            let (cl_ord_id, response_to) = match pending {
+                Pending::Cancel { cl_ord_id } => (cl_ord_id, "1"),
+                Pending::Replace { cl_ord_id, .. } => (cl_ord_id, "2"),
+            };
+            let reject = Self::cancel_reject(Some(order_id), ord_status, &cl_ord_id, &info.cl_ord_id, response_to, 0, "订单已结束");
+            out.push((info.owner.clone(), reject));
+        }
+    }
+
+    fn run_connection(&self, stream: TcpStream) {
+        let Ok(read_half) = stream.try_clone() else { return };
+        let mut reader = FixReader { stream: read_half, buf: Vec::new() };
+        if stream.set_read_timeout(Some(LOGON_TIMEOUT)).is_err() {
+            return;
+        }
+        let Ok(Some(logon)) = reader.next() else { return };
+        let connection = self.next_connection.fetch_add(1, Ordering::Relaxed);
+        let Some((their, user_id, heart_bt_int, state)) = self.logon(&logon, &stream, connection) else {
+            let _ = stream.shutdown(Shutdown::Both);
+            return;
+        };
+        let _guard = Disconnect { state: state.clone(), connection };
+
+        let mut session = Session { shared: self, their, user_id, state: state.clone(), resend_upto: 0 };
+        let interval = Duration::from_secs(heart_bt_int);
+        let _ = stream.set_read_timeout(Some(interval.min(Duration::from_secs(1))));
+        let mut last_in = Instant::now();
+        let mut test_sent = false;
+        loop {
+            match reader.next() {
+                Ok(Some(msg)) => {
+                    last_in = Instant::now();
+                    test_sent = false;
+                    if !session.handle(msg) {
+                        break;
+                    }
+                }
+                Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {}
+                _ => break,
+            }
+            let mut s = state.lock().unwrap();
+            if s.stream.is_none() {
+                break;
+            }
+            let silent = last_in.elapsed();
+            if silent > interval * 2 {
+                s.send(FixMessage::new(msg_type::LOGOUT).with(tag::TEXT, "心跳超时"));
+                break;
+            }
+            if silent > interval + interval / 5 && !test_sent {
+                s.send(FixMessage::new(msg_type::TEST_REQUEST).with(tag::TEST_REQ_ID, "TEST"));
+                test_sent = true;
+            } else if s.last_out.elapsed() >= interval {
+                s.send(FixMessage::new(msg_type::HEARTBEAT));
+            }
+        }
+    }
+
+    // 校验 Logon 并建立会话；一个接入方同时只能有一个连接
+    fn logon(&self, logon: &FixMessage, stream: &TcpStream, connection: u64) -> Option<(String, UserID, u64, Arc<Mutex<SessionState>>)> {
+        if logon.msg_type() != msg_type::LOGON || logon.get(tag::TARGET_COMP_ID) != Some(self.config.comp_id.as_str()) {
+            return None;
+        }
+        let their = logon.get(tag::SENDER_COMP_ID)?;
+        let counterparty = self.config.counterparties.get(their)?;
+        if counterparty.password.is_some() && counterparty.password.as_deref() != logon.get(tag::PASSWORD) {
+            return None;
+        }
+        let seq: u64 = logon.parse(tag::MSG_SEQ_NUM)?;
+        let heart_bt_int = logon.parse(tag::HEART_BT_INT).filter(|&h| h > 0).unwrap_or(DEFAULT_HEART_BT_INT);
+        let reset = logon.get(tag::RESET_SEQ_NUM_FLAG) == Some("Y");
+
+        let state = self.state(their).ok()?;
+        let mut s = state.lock().unwrap();
+        if s.stream.is_some() {
+            return None;
+        }
+        if reset {
+            s.reset();
+        }
+        s.stream = Some(stream.try_clone().ok()?);
+        s.connection = connection;
+        if seq < s.next_in {
+            let text = format!("MsgSeqNum 过小, 期望 {}", s.next_in);
+            s.send(FixMessage::new(msg_type::LOGOUT).with(tag::TEXT, text));
+            s.disconnect();
+            return None;
+        }
+        let mut reply = FixMessage::new(msg_type::LOGON).with(tag::ENCRYPT_METHOD, 0).with(tag::HEART_BT_INT, heart_bt_int);
+        if reset {
+            reply.set(tag::RESET_SEQ_NUM_FLAG, "Y");
+        }
+        s.send(reply);
+        if seq > s.next_in {
+            let begin = s.next_in;
+            s.send(FixMessage::new(msg_type::RESEND_REQUEST).with(tag::BEGIN_SEQ_NO, begin).with(tag::END_SEQ_NO, 0));
+        } else {
+            s.set_next_in(seq + 1);
+        }
+        drop(s);
+        Some((their.to_string(), counterparty.user_id, heart_bt_int, state))
+    }
+}
+
+// 一个已登录的连接
+struct Session<'a> {
+    shared: &'a Shared,
+    their: String,
+    user_id: UserID,
+    state: Arc<Mutex<SessionState>>,
+    resend_upto: u64, // 已请求对方重发到这个序号，期间不重复请求
+}
+
+impl Session<'_> {
+    // 返回 false 时断开连接
+    fn handle(&mut self, msg: FixMessage) -> bool {
+        let mut s = self.state.lock().unwrap();
+        if msg.get(tag::SENDER_COMP_ID) != Some(self.their.as_str()) || msg.get(tag::TARGET_COMP_ID) != Some(s.our.as_str()) {
+            s.send(FixMessage::new(msg_type::LOGOUT).with(tag::TEXT, "CompID 不匹配"));
+            return false;
+        }
+        let Some(seq) = msg.parse::<u64>(tag::MSG_SEQ_NUM) else {
+            s.send(FixMessage::new(msg_type::LOGOUT).with(tag::TEXT, "缺少 MsgSeqNum"));
+            return false;
+        };
+
+        match msg.msg_type() {
+            msg_type::SEQUENCE_RESET => {
+                let gap_fill = msg.get(tag::GAP_FILL_FLAG) == Some("Y");
+                if gap_fill && seq < s.next_in {
+                    return true;
+                }
+                // GapFill 本身也占序号，跳号说明前面还有缺口，按缺口处理先请求重发
+                if !gap_fill || seq == s.next_in {
+                    if let Some(new_seq) = msg.parse::<u64>(tag::NEW_SEQ_NO)
+                        && new_seq > s.next_in
+                    {
+                        s.set_next_in(new_seq);
+                    }
+                    return true;
+                }
+            }
+            // 重发请求不受本方序号缺口影响，先处理
+            msg_type::RESEND_REQUEST => {
+                let begin = msg.parse(tag::BEGIN_SEQ_NO).unwrap_or(1);
+                let end = msg.parse(tag::END_SEQ_NO).unwrap_or(0);
+                s.resend(begin, end);
+                if seq == s.next_in {
+                    s.set_next_in(seq + 1);
+                }
+                return true;
+            }
+            _ => {}
+        }
+
+        if seq > s.next_in {
+            if s.next_in > self.resend_upto {
+                let begin = s.next_in;
+                s.send(FixMessage::new(msg_type::RESEND_REQUEST).with(tag::BEGIN_SEQ_NO, begin).with(tag::END_SEQ_NO, 0));
+                self.resend_upto = seq;
+            }
+            return true;
+        }
+        if seq < s.next_in {
+            if msg.get(tag::POSS_DUP_FLAG) == Some("Y") {
+                return true;
+            }
+            let text = format!("MsgSeqNum 过小, 期望 {}", s.next_in);
+            s.send(FixMessage::new(msg_type::LOGOUT).with(tag::TEXT, text));
+            return false;
+        }
+        s.set_next_in(seq + 1);
+
+        match msg.msg_type() {
+            msg_type::HEARTBEAT => {}
+            msg_type::TEST_REQUEST => {
+                let id = msg.get(tag::TEST_REQ_ID).unwrap_or_default();
+                s.send(FixMessage::new(msg_type::HEARTBEAT).with(tag::TEST_REQ_ID, id));
+            }
+            msg_type::LOGOUT => {
+                s.send(FixMessage::new(msg_type::LOGOUT));
+                return false;
+            }
+            msg_type::NEW_ORDER_SINGLE => {
+                drop(s);
+                self.new_order(&msg);
+            }
+            msg_type::ORDER_CANCEL_REQUEST => {
+                drop(s);
+                self.cancel(&msg);
+            }
+            msg_type::ORDER_CANCEL_REPLACE_REQUEST => {
+                drop(s);
+                self.replace(&msg);
+            }
+            other => {
+                let reject = FixMessage::new(msg_type::REJECT)
+                    .with(tag::REF_SEQ_NUM, seq)
+                    .with(tag::REF_MSG_TYPE, other)
+                    .with(tag::TEXT, "不支持的消息类型");
+                s.send(reject);
+            }
+        }
+        true
+    }
+
+    fn reply(&self, msg: FixMessage) {
+        self.state.lock().unwrap().send(msg);
+    }
+
+    fn new_order(&self, msg: &FixMessage) {
+        let shared = self.shared;
+        let Some(cl_ord_id) = msg.get(tag::CL_ORD_ID) else {
+            return self.reply(shared.reject_report(msg, 99, "缺少 ClOrdID"));
+        };
+        if msg.get(tag::SYMBOL) != Some(shared.symbol.as_str()) {
+            return self.reply(shared.reject_report(msg, 1, "未知的 Symbol"));
+        }
+        if msg.get(tag::ORD_TYPE) != Some("2") {
+            return self.reply(shared.reject_report(msg, 99, "只支持限价单"));
+        }
+        let side = match msg.get(tag::SIDE) {
+            Some("1") => OrderSide::Bid,
+            Some("2") => OrderSide::Ask,
+            _ => return self.reply(shared.reject_report(msg, 99, "Side 只支持 1 (买) / 2 (卖)")),
+        };
+        let (Some(price), Some(quantity)) = (msg.parse::<Decimal>(tag::PRICE), msg.parse::<Decimal>(tag::ORDER_QTY)) else {
+            return self.reply(shared.reject_report(msg, 99, "Price / OrderQty 缺失或无效"));
+        };
+
+        let key = (self.their.clone(), cl_ord_id.to_string());
+        let order_id = {
+            let mut orders = shared.orders.lock().unwrap();
+            if orders.by_cl_ord_id.contains_key(&key) {
+                drop(orders);
+                return self.reply(shared.reject_report(msg, 6, "ClOrdID 重复"));
+            }
+            let order_id = shared.next_order_id.fetch_add(1, Ordering::Relaxed);
+            orders.insert(order_id, OrderInfo {
+                owner: self.their.clone(),
+                cl_ord_id: cl_ord_id.to_string(),
+                side: side.clone(),
+                price,
+                order_qty: quantity,
+                cum_qty: Decimal::ZERO,
+                notional: Decimal::ZERO,
+                pending: VecDeque::new(),
+            });
+            // 先记下订单再交给引擎，重启后才能认出它
+            if orders.record(order_id).is_err() {
+                orders.remove(order_id);
+                drop(orders);
+                return self.reply(shared.reject_report(msg, 99, "无法记录订单"));
+            }
+            order_id
+        };
+        let order = Order { id: order_id, price, quantity, side, user_id: self.user_id };
+        if shared.engine.submit(Command::Place(order)).is_err() {
+            shared.orders.lock().unwrap().remove(order_id);
+            self.reply(shared.reject_report(msg, 99, "撮合引擎已停止"));
+        }
+    }
+
+    // 找到本接入方 OrigClOrdID 对应的活动订单
+    fn lookup(&self, msg: &FixMessage, response_to: &str) -> Option<(OrderID, String, String)> {
+        let cl_ord_id = msg.get(tag::CL_ORD_ID).unwrap_or_default().to_string();
+        let orig = msg.get(tag::ORIG_CL_ORD_ID).unwrap_or_default().to_string();
+        let found = self.shared.orders.lock().unwrap().by_cl_ord_id.get(&(self.their.clone(), orig.clone())).copied();
+        if found.is_none() {
+            self.reply(Shared::cancel_reject(None, "8", &cl_ord_id, &orig, response_to, 1, "订单不存在"));
+        }
+        found.map(|id| (id, cl_ord_id, orig))
+    }
+
+    // lookup 之后订单可能已经结束，这时直接拒绝，不再提交给引擎
+    fn cancel(&self, msg: &FixMessage) {
+        let Some((order_id, cl_ord_id, orig)) = self.lookup(msg, "1") else { return };
+        let tracked = match self.shared.orders.lock().unwrap().by_id.get_mut(&order_id) {
+            Some(info) => {
+                info.pending.push_back(Pending::Cancel { cl_ord_id: cl_ord_id.clone() });
+                true
+            }
+            None => false,
+        };
+        if !tracked {
+            return self.reply(Shared::cancel_reject(None, "8", &cl_ord_id, &orig, "1", 1, "订单不存在"));
+        }
+        let _ = self.shared.engine.submit(Command::Cancel { order_id });
+    }
+
+    // OrderQty 为改单后的订单总量，引擎中的剩余数量 = OrderQty - 已成交
+    fn replace(&self, msg: &FixMessage) {
+        let Some((order_id, cl_ord_id, orig)) = self.lookup(msg, "2") else { return };
+        let (Some(price), Some(order_qty)) = (msg.parse::<Decimal>(tag::PRICE), msg.parse::<Decimal>(tag::ORDER_QTY)) else {
+            return self.reply(Shared::cancel_reject(Some(order_id), "0", &cl_ord_id, &orig, "2", 99, "Price / OrderQty 缺失或无效"));
+        };
+        {
+            let mut orders = self.shared.orders.lock().unwrap();
+            let Some(info) = orders.by_id.get_mut(&order_id) else {
+                drop(orders);
+                return self.reply(Shared::cancel_reject(None, "8", &cl_ord_id, &orig, "2", 1, "订单不存在"));
+            };
+            // 已知的成交量只增不减，这里不够时一定会被拒绝；够的话由引擎按实际成交再判断
+            if order_qty <= info.cum_qty {
+                let status = info.ord_status();
+                drop(orders);
+                return self.reply(Shared::cancel_reject(Some(order_id), status, &cl_ord_id, &orig, "2", 99, "OrderQty 不大于已成交数量"));
+            }
+            info.pending.push_back(Pending::Replace { cl_ord_id: cl_ord_id.clone(), price, order_qty });
+        }
+        let _ = self.shared.engine.submit(Command::Replace { order_id, price, order_qty });
+    }
+}
+
+fn record(order_id: OrderID, info: &OrderInfo) -> Vec<u8> {
+    format!("{order_id}\x01{}\x01{}\x01\n", info.owner, info.cl_ord_id).into_bytes()
+}
+
+fn side_code(side: &OrderSide) -> &'static str {
+    match side {
+        OrderSide::Bid => "1",
+        OrderSide::Ask => "2",
+    }
+}
+
+// 连接线程以任何方式退出 (包括 panic) 时释放会话，否则该接入方无法再次登录
+// 会话已被新连接接管时不动它
+struct Disconnect {
+    state: Arc<Mutex<SessionState>>,
+    connection: u64,
+}
+
+impl Drop for Disconnect {
+    fn drop(&mut self) {
+        let mut s = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if s.connection == self.connection {
+            s.disconnect();
+        }
+    }
+}
+
+// 从 TCP 流中按消息切分；读超时时已读到的部分保留在缓冲区，缓冲不超过一条最大消息
+struct FixReader {
+    stream: TcpStream,
+    buf: Vec<u8>,
+}
+
+impl FixReader {
+    // Ok(None) 表示对方关闭连接
+    fn next(&mut self) -> io::Result<Option<FixMessage>> {
+        loop {
+            match FixMessage::decode(&self.buf) {
+                Ok(Some((msg, len))) => {
+                    self.buf.drain(..len);
+                    return Ok(Some(msg));
+                }
+                Ok(None) if self.buf.len() >= fix::MAX_MESSAGE_LEN => {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "FIX 消息过长"));
+                }
+                Ok(None) => {}
+                Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+            }
+            let mut chunk = [0u8; 4096];
+            let n = self.stream.read(&mut chunk)?;
+            if n == 0 {
+                return Ok(None);
+            }
+            self.buf.extend_from_slice(&chunk[..n]);
+        }
+    }
+}
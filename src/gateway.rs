@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use crate::actor::{EngineHandle, EngineOutput, Outcome};
+use crate::engine::EngineError;
+use crate::error::Error;
+use crate::exchange::Command;
+use crate::types::{Asset, Order, OrderID, OrderReport, OrderSide, OrderStatus, Quantity, TradeEvent, UserID};
+
+// 文本行协议，字段以空格分隔:
+//   请求: LOGIN <user_id> <token> | PLACE <order_id> <BUY|SELL> <price> <qty> | CANCEL <order_id>
+//         AMEND <order_id> <price> <qty> | ORDER <order_id> | ORDERS | BALANCE <asset>
+//   响应: LOGGED_IN <user_id> | ACK <order_id> | CANCELLED <order_id> | REJECT <order_id|-> <code> <message>
+//         FILL <order_id> <trade_id> <price> <qty> | ORDER <order_id> <side> <price> <status> <filled> <remaining>
+//         BALANCE <asset> <available> <frozen> | END
+// 数值输出去掉末尾的 0。被动成交 (挂单被其他用户吃掉) 的 FILL 会异步推送到该用户的所有连接
+// 订单号由客户端自选，只需在本用户的活动订单中唯一，订单结束后可以重用 (ORDER 也只能查询活动订单)；
+// 引擎中的订单号由网关分配，应答中只出现客户端订单号
+
+// 单行请求的最大字节数 (不含换行)，超过即断开
+pub const MAX_LINE_LEN: usize = 1024;
+// 每个连接待写出的行数上限，客户端读得太慢写满时断开
+pub const OUTBOX_CAPACITY: usize = 1024;
+
+// 连接的待写出队列
+struct Outbox {
+    tx: SyncSender<String>,
+    stream: TcpStream,
+}
+
+impl Outbox {
+    // 队列已满或连接已关闭时断开连接，返回 false
+    fn send(&self, line: String) -> bool {
+        if self.tx.try_send(line).is_ok() {
+            return true;
+        }
+        let _ = self.stream.shutdown(Shutdown::Both);
+        false
+    }
+}
+
+type Subscribers = Arc<Mutex<HashMap<UserID, Vec<(u64, Arc<Outbox>)>>>>;
+
+struct ClientOrder {
+    user_id: UserID,
+    client_id: OrderID,
+    remaining: Quantity,
+}
+
+// 活动订单的客户端订单号与引擎订单号的对应关系，订单结束后移除，客户端订单号可以重用
+#[derive(Default)]
+struct ClientOrders {
+    by_client: HashMap<(UserID, OrderID), OrderID>,
+    by_id: HashMap<OrderID, ClientOrder>,
+}
+
+impl ClientOrders {
+    fn remove(&mut self, order_id: OrderID) {
+        if let Some(order) = self.by_id.remove(&order_id) {
+            self.by_client.remove(&(order.user_id, order.client_id));
+        }
+    }
+
+    // 全部成交的订单移除
+    fn fill(&mut self, order_id: OrderID, quantity: Quantity) {
+        if let Some(order) = self.by_id.get_mut(&order_id) {
+            order.remaining -= quantity;
+            if order.remaining <= Quantity::ZERO {
+                self.remove(order_id);
+            }
+        }
+    }
+}
+
+type Orders = Arc<Mutex<ClientOrders>>;
+
+// TCP 下单网关，背后是单线程撮合 Actor
+pub struct Gateway {
+    engine: EngineHandle,
+    credentials: Arc<HashMap<UserID, String>>,
+    subscribers: Subscribers,
+    orders: Orders,
+    next_order_id: Arc<AtomicU64>,
+    next_connection: Arc<AtomicU64>,
+}
+
+impl Gateway {
+    // outputs 为引擎的输出通道，用于跟踪订单状态并把挂单方的成交推送给对应用户
+    // 引擎订单号从引擎中已有的最大订单号之后开始分配
+    pub fn new(engine: EngineHandle, outputs: Receiver<EngineOutput>, credentials: HashMap<UserID, String>) -> Self {
+        let subscribers: Subscribers = Arc::default();
+        let orders: Orders = Arc::default();
+        let (dispatch, dispatch_orders) = (subscribers.clone(), orders.clone());
+        thread::spawn(move || {
+            for output in outputs {
+                if let EngineOutput::Processed { command, outcome, .. } = output {
+                    dispatch_fills(&dispatch, &mut dispatch_orders.lock().unwrap(), command, outcome);
+                }
+            }
+        });
+        let last = engine.query(|ex| ex.book().last_order_id()).ok().flatten();
+        Self {
+            engine,
+            credentials: Arc::new(credentials),
+            subscribers,
+            orders,
+            next_order_id: Arc::new(AtomicU64::new(last.map_or(1, |id| id + 1))),
+            next_connection: Arc::default(),
+        }
+    }
+
+    // 接受连接，每个连接一个线程。监听出错时返回
+    pub fn serve(&self, listener: TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            let connection = Connection {
+                id: self.next_connection.fetch_add(1, Ordering::Relaxed),
+                engine: self.engine.clone(),
+                credentials: self.credentials.clone(),
+                subscribers: self.subscribers.clone(),
+                orders: self.orders.clone(),
+                next_order_id: self.next_order_id.clone(),
+                user_id: None,
+            };
+            let stream = stream?;
+            thread::spawn(move || connection.run(stream));
+        }
+        Ok(())
+    }
+}
+
+// 处理一条指令的结果: 更新订单的剩余数量，把成交推送给挂单方
+// 下单失败与撤单成功时连接线程也会移除订单，使应答之后立即可以重用客户端订单号
+fn dispatch_fills(subscribers: &Subscribers, orders: &mut ClientOrders, command: Command, outcome: Outcome) {
+    let trades = match (command, outcome) {
+        (Command::Place(_), Ok(trades)) => trades,
+        (Command::Place(order), Err(_)) => return orders.remove(order.id),
+        (Command::Amend { order_id, quantity, .. }, Ok(trades)) => {
+            if let Some(order) = orders.by_id.get_mut(&order_id) {
+                order.remaining = quantity;
+            }
+            trades
+        }
+        (Command::Cancel { order_id }, Ok(_)) => return orders.remove(order_id),
+        _ => return,
+    };
+    for trade in &trades {
+        if let Some(maker) = orders.by_id.get(&trade.maker_order_id) {
+            let line = fill(maker.client_id, trade);
+            if let Some(outboxes) = subscribers.lock().unwrap().get_mut(&maker.user_id) {
+                outboxes.retain(|(_, outbox)| outbox.send(line.clone()));
+            }
+        }
+        orders.fill(trade.maker_order_id, trade.quantity);
+        orders.fill(trade.taker_order_id, trade.quantity);
+    }
+}
+
+struct Connection {
+    id: u64,
+    engine: EngineHandle,
+    credentials: Arc<HashMap<UserID, String>>,
+    subscribers: Subscribers,
+    orders: Orders,
+    next_order_id: Arc<AtomicU64>,
+    user_id: Option<UserID>,
+}
+
+impl Connection {
+    fn run(mut self, stream: TcpStream) {
+        let (Ok(mut writer), Ok(control)) = (stream.try_clone(), stream.try_clone()) else { return };
+        // 写线程: 本连接的应答与推送的成交都经由同一个队列，保证整行输出
+        let (tx, rx) = mpsc::sync_channel::<String>(OUTBOX_CAPACITY);
+        let outbox = Arc::new(Outbox { tx, stream: control });
+        thread::spawn(move || {
+            for line in rx {
+                if writer.write_all(line.as_bytes()).and_then(|_| writer.write_all(b"\n")).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut reader = BufReader::new(stream);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            let limit = MAX_LINE_LEN as u64 + 1;
+            match reader.by_ref().take(limit).read_until(b'\n', &mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
+            }
+            if buf.last() == Some(&b'\n') {
+                buf.pop();
+            } else if buf.len() > MAX_LINE_LEN {
+                outbox.send(reject(None, "LINE_TOO_LONG", "请求行过长"));
+                break;
+            }
+            let Ok(line) = std::str::from_utf8(&buf) else { break };
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() {
+                continue;
+            }
+            if !self.handle(line, &outbox).into_iter().all(|reply| outbox.send(reply)) {
+                break;
+            }
+        }
+        if let Some(user_id) = self.user_id
+            && let Some(senders) = self.subscribers.lock().unwrap().get_mut(&user_id)
+        {
+            senders.retain(|(id, _)| *id != self.id);
+        }
+    }
+
+    fn handle(&mut self, line: &str, outbox: &Arc<Outbox>) -> Vec<String> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if let ["LOGIN", user_id, token] = fields[..] {
+            return vec![self.login(user_id, token, outbox)];
+        }
+        let Some(user_id) = self.user_id else {
+            return vec![reject(None, "NOT_LOGGED_IN", "请先登录")];
+        };
+        match self.request(user_id, &fields) {
+            Ok(replies) => replies,
+            Err(bad) => vec![bad],
+        }
+    }
+
+    fn login(&mut self, user_id: &str, token: &str, outbox: &Arc<Outbox>) -> String {
+        if self.user_id.is_some() {
+            return reject(None, "ALREADY_LOGGED_IN", "连接已登录");
+        }
+        match user_id.parse::<UserID>() {
+            Ok(user_id) if self.credentials.get(&user_id).is_some_and(|t| t == token) => {
+                self.user_id = Some(user_id);
+                self.subscribers.lock().unwrap().entry(user_id).or_default().push((self.id, outbox.clone()));
+                format!("LOGGED_IN {user_id}")
+            }
+            _ => reject(None, "AUTH_FAILED", "用户或令牌错误"),
+        }
+    }
+
+    // Err 为格式错误等单行拒绝
+    fn request(&self, user_id: UserID, fields: &[&str]) -> Result<Vec<String>, String> {
+        let replies = match *fields {
+            ["PLACE", id, side, price, quantity] => {
+                let side = match side {
+                    "BUY" => OrderSide::Bid,
+                    "SELL" => OrderSide::Ask,
+                    _ => return Err(bad_request("side 应为 BUY 或 SELL")),
+                };
+                let (client_id, price, quantity) = (parse(id)?, parse(price)?, parse(quantity)?);
+                let order_id = self.assign(user_id, client_id, quantity)?;
+                let order = Order { id: order_id, price, quantity, side, user_id };
+                let outcome = self.call(Command::Place(order))?;
+                if outcome.is_err() {
+                    self.orders.lock().unwrap().remove(order_id);
+                }
+                self.trade_replies(client_id, outcome)
+            }
+            ["CANCEL", id] => {
+                let client_id = parse(id)?;
+                let order_id = self.own_order(user_id, client_id)?;
+                match self.call(Command::Cancel { order_id })? {
+                    Ok(_) => {
+                        self.orders.lock().unwrap().remove(order_id);
+                        vec![format!("CANCELLED {client_id}")]
+                    }
+                    Err(e) => vec![reject(Some(client_id), e.code(), &e.to_string())],
+                }
+            }
+            ["AMEND", id, price, quantity] => {
+                let client_id = parse(id)?;
+                let order_id = self.own_order(user_id, client_id)?;
+                let command = Command::Amend { order_id, price: parse(price)?, quantity: parse(quantity)? };
+                self.trade_replies(client_id, self.call(command)?)
+            }
+            ["ORDER", id] => {
+                let client_id = parse(id)?;
+                let order_id = self.own_order(user_id, client_id)?;
+                let report = self.engine.query(move |ex| ex.book().order_status(order_id)).map_err(|_| stopped())?;
+                match report {
+                    Some(report) => vec![order_line(client_id, &report)],
+                    None => vec![not_found(client_id)],
+                }
+            }
+            ["ORDERS"] => {
+                let reports = self.engine.query(move |ex| ex.book().open_orders(user_id)).map_err(|_| stopped())?;
+                let orders = self.orders.lock().unwrap();
+                reports.iter()
+                    .filter_map(|r| orders.by_id.get(&r.order_id).map(|o| order_line(o.client_id, r)))
+                    .chain([String::from("END")])
+                    .collect()
+            }
+            ["BALANCE", asset] => {
+                let asset = Asset::try_new(asset).map_err(|e| {
+                    let e = Error::from(e);
+                    reject(None, e.code(), &e.to_string())
+                })?;
+                let (available, frozen) = self.engine.query(move |ex| ex.account().get_balance(user_id, asset)).map_err(|_| stopped())?;
+                vec![format!("BALANCE {asset} {} {}", available.normalize(), frozen.normalize())]
+            }
+            _ => return Err(bad_request("无法识别的请求")),
+        };
+        Ok(replies)
+    }
+
+    fn call(&self, command: Command) -> Result<Outcome, String> {
+        self.engine.call(command).map_err(|_| stopped())
+    }
+
+    // 为新单分配引擎订单号；客户端订单号与本用户的活动订单重复时拒绝
+    fn assign(&self, user_id: UserID, client_id: OrderID, quantity: Quantity) -> Result<OrderID, String> {
+        let mut orders = self.orders.lock().unwrap();
+        if orders.by_client.contains_key(&(user_id, client_id)) {
+            let e = Error::from(EngineError::DuplicateOrderId { order_id: client_id });
+            return Err(reject(Some(client_id), e.code(), &e.to_string()));
+        }
+        let order_id = self.next_order_id.fetch_add(1, Ordering::Relaxed);
+        orders.by_client.insert((user_id, client_id), order_id);
+        orders.by_id.insert(order_id, ClientOrder { user_id, client_id, remaining: quantity });
+        Ok(order_id)
+    }
+
+    // 只能查询与操作自己的活动订单，返回引擎订单号
+    fn own_order(&self, user_id: UserID, client_id: OrderID) -> Result<OrderID, String> {
+        let found = self.orders.lock().unwrap().by_client.get(&(user_id, client_id)).copied();
+        found.ok_or_else(|| not_found(client_id))
+    }
+
+    // 主动成交方的应答: ACK 之后紧跟本单的成交
+    fn trade_replies(&self, client_id: OrderID, outcome: Outcome) -> Vec<String> {
+        match outcome {
+            Ok(trades) => [format!("ACK {client_id}")].into_iter().chain(trades.iter().map(|t| fill(client_id, t))).collect(),
+            Err(e) => vec![reject(Some(client_id), e.code(), &e.to_string())],
+        }
+    }
+}
+
+fn parse<T: FromStr>(field: &str) -> Result<T, String> {
+    field.parse().map_err(|_| bad_request(&format!("无法解析字段 {field}")))
+}
+
+fn fill(order_id: OrderID, trade: &TradeEvent) -> String {
+    format!("FILL {order_id} {} {} {}", trade.trade_id, trade.price.normalize(), trade.quantity.normalize())
+}
+
+fn order_line(client_id: OrderID, r: &OrderReport) -> String {
+    let side = match r.side {
+        OrderSide::Bid => "BUY",
+        OrderSide::Ask => "SELL",
+    };
+    let status = match r.status {
+        OrderStatus::Open => "OPEN",
+        OrderStatus::PartiallyFilled => "PARTIALLY_FILLED",
+        OrderStatus::Filled => "FILLED",
+        OrderStatus::Cancelled => "CANCELLED",
+    };
+    format!(
+        "ORDER {client_id} {side} {} {status} {} {}",
+        r.price.normalize(), r.filled_quantity.normalize(), r.remaining_quantity.normalize()
+    )
+}
+
+fn reject(order_id: Option<OrderID>, code: &str, message: &str) -> String {
+    match order_id {
+        Some(id) => format!("REJECT {id} {code} {message}"),
+        None => format!("REJECT - {code} {message}"),
+    }
+}
+
+fn not_found(client_id: OrderID) -> String {
+    reject(Some(client_id), "ORDER_NOT_FOUND", "订单不存在")
+}
+
+fn bad_request(message: &str) -> String {
+    reject(None, "BAD_REQUEST", message)
+}
+
+fn stopped() -> String {
+    reject(None, "ENGINE_STOPPED", "撮合引擎已停止")
+}
@@ -0,0 +1,264 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use rust_decimal::Decimal;
+use thiserror::Error;
+use crate::exchange::{Command, Exchange};
+use crate::types::{Asset, Order, OrderSide};
+
+// 记录头: 负载长度 u32 + 校验和 u32 + 序号 u64，均为小端；校验和覆盖长度、序号与负载
+const HEADER_LEN: usize = 16;
+// 单条负载的上限，远大于现有指令，超出说明长度字段已损坏
+const MAX_PAYLOAD_LEN: usize = 1024;
+
+const TAG_DEPOSIT: u8 = 1;
+const TAG_PLACE: u8 = 2;
+const TAG_CANCEL: u8 = 3;
+const TAG_AMEND: u8 = 4;
+const TAG_REPLACE: u8 = 5;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum JournalError {
+    #[error("日志读写失败: {message}")]
+    Io { kind: io::ErrorKind, message: String },
+    #[error("偏移 {offset} 处的日志记录校验和不匹配")]
+    ChecksumMismatch { offset: u64 },
+    #[error("偏移 {offset} 处的日志记录无法解析")]
+    Malformed { offset: u64 },
+    #[error("偏移 {offset} 处的日志记录长度 {len} 无效")]
+    InvalidLength { offset: u64, len: usize },
+    #[error("日志序号不连续: 期望 {expected}, 实际 {found}")]
+    SequenceGap { expected: u64, found: u64 },
+}
+
+impl From<io::Error> for JournalError {
+    fn from(e: io::Error) -> Self {
+        JournalError::Io { kind: e.kind(), message: e.to_string() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalRecord {
+    pub seq: u64,
+    pub command: Command,
+}
+
+// 只追加的指令日志: 指令先落日志，再交给状态机处理
+pub struct Journal<W: Write> {
+    writer: W,
+    next_seq: u64,
+}
+
+impl<W: Write> Journal<W> {
+    pub fn new(writer: W) -> Self {
+        Self::resume(writer, 0)
+    }
+
+    // 接在已有日志之后继续写，last_seq 为日志中最后一条记录的序号
+    pub fn resume(writer: W, last_seq: u64) -> Self {
+        Self { writer, next_seq: last_seq + 1 }
+    }
+
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    // 写入一条指令，返回分配的序号。整条记录一次写出，崩溃时最多留下一条残缺的尾部记录
+    pub fn append(&mut self, command: &Command) -> Result<u64, JournalError> {
+        let seq = self.next_seq;
+        let payload = encode_command(command);
+
+        let mut record = Vec::with_capacity(HEADER_LEN + payload.len());
+        record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        record.extend_from_slice(&checksum(payload.len(), seq, &payload).to_le_bytes());
+        record.extend_from_slice(&seq.to_le_bytes());
+        record.extend_from_slice(&payload);
+
+        self.writer.write_all(&record)?;
+        self.writer.flush()?;
+        self.next_seq += 1;
+        Ok(seq)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl Journal<File> {
+    // 打开 (或创建) 日志文件，截掉崩溃时写了一半的尾部记录，返回日志与已有记录；
+    // 中间记录损坏时报错，不会截掉其后已提交的记录
+    pub fn open(path: impl AsRef<Path>) -> Result<(Self, Vec<JournalRecord>), JournalError> {
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+
+        let (records, valid_len) = scan(&bytes)?;
+        file.set_len(valid_len as u64)?;
+        file.seek(SeekFrom::End(0))?;
+
+        let last_seq = records.last().map_or(0, |r| r.seq);
+        Ok((Self::resume(file, last_seq), records))
+    }
+
+    // append 只保证写入操作系统，需要抵御断电时调用 sync 落盘
+    pub fn sync(&mut self) -> Result<(), JournalError> {
+        self.writer.sync_data()?;
+        Ok(())
+    }
+}
+
+// 读取全部记录。尾部不完整的记录视为崩溃时未写完，直接忽略；中间记录损坏则报错
+pub fn read_journal<R: Read>(mut reader: R) -> Result<Vec<JournalRecord>, JournalError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    Ok(scan(&bytes)?.0)
+}
+
+// 把序号大于 exchange.last_seq() 的记录依次重放，返回重放的条数
+// 指令本身被拒绝 (如余额不足) 属于正常结果，与首次处理时一致，不算重放失败
+pub fn replay(records: &[JournalRecord], exchange: &mut Exchange) -> Result<usize, JournalError> {
+    let start = exchange.last_seq();
+    let mut applied = 0;
+    for record in records.iter().filter(|r| r.seq > start) {
+        let expected = exchange.last_seq() + 1;
+        if record.seq != expected {
+            return Err(JournalError::SequenceGap { expected, found: record.seq });
+        }
+        let _ = exchange.apply(record.seq, &record.command);
+        applied += 1;
+    }
+    Ok(applied)
+}
+
+// 返回完整记录与其占用的字节数
+fn scan(bytes: &[u8]) -> Result<(Vec<JournalRecord>, usize), JournalError> {
+    let mut records = Vec::new();
+    let mut offset = 0;
+    while bytes.len() - offset >= HEADER_LEN {
+        let header = &bytes[offset..offset + HEADER_LEN];
+        let len = u32::from_le_bytes(header[0..4].try_into().unwrap()) as usize;
+        let crc = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        let seq = u64::from_le_bytes(header[8..16].try_into().unwrap());
+        if len > MAX_PAYLOAD_LEN {
+            return Err(JournalError::InvalidLength { offset: offset as u64, len });
+        }
+
+        let start = offset + HEADER_LEN;
+        let rest = &bytes[start..];
+        if rest.len() < len {
+            // 负载不完整: 只有它确实是最后一条记录时才是崩溃残留。
+            // 若按某个更短的长度能通过校验，说明是中间记录的长度字段被改坏
+            if (0..=rest.len()).any(|n| checksum(n, seq, &rest[..n]) == crc) {
+                return Err(JournalError::ChecksumMismatch { offset: offset as u64 });
+            }
+            break;
+        }
+        let payload = &rest[..len];
+        if checksum(len, seq, payload) != crc {
+            return Err(JournalError::ChecksumMismatch { offset: offset as u64 });
+        }
+        let command = decode_command(payload).ok_or(JournalError::Malformed { offset: offset as u64 })?;
+        records.push(JournalRecord { seq, command });
+        offset = start + len;
+    }
+    Ok((records, offset))
+}
+
+fn checksum(len: usize, seq: u64, payload: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&(len as u32).to_le_bytes());
+    hasher.update(&seq.to_le_bytes());
+    hasher.update(payload);
+    hasher.finalize()
+}
+
+fn encode_command(command: &Command) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(64);
+    match command {
+        Command::Deposit { user_id, asset, amount } => {
+            buf.push(TAG_DEPOSIT);
+            buf.extend_from_slice(&user_id.to_le_bytes());
+            buf.extend_from_slice(asset.as_bytes());
+            buf.extend_from_slice(&amount.serialize());
+        }
+        Command::Place(order) => {
+            buf.push(TAG_PLACE);
+            buf.extend_from_slice(&order.id.to_le_bytes());
+            buf.extend_from_slice(&order.user_id.to_le_bytes());
+            buf.push(match order.side {
+                OrderSide::Bid => 0,
+                OrderSide::Ask => 1,
+            });
+            buf.extend_from_slice(&order.price.serialize());
+            buf.extend_from_slice(&order.quantity.serialize());
+        }
+        Command::Cancel { order_id } => {
+            buf.push(TAG_CANCEL);
+            buf.extend_from_slice(&order_id.to_le_bytes());
+        }
+        Command::Amend { order_id, price, quantity } => {
+            buf.push(TAG_AMEND);
+            buf.extend_from_slice(&order_id.to_le_bytes());
+            buf.extend_from_slice(&price.serialize());
+            buf.extend_from_slice(&quantity.serialize());
+        }
+        Command::Replace { order_id, price, order_qty } => {
+            buf.push(TAG_REPLACE);
+            buf.extend_from_slice(&order_id.to_le_bytes());
+            buf.extend_from_slice(&price.serialize());
+            buf.extend_from_slice(&order_qty.serialize());
+        }
+    }
+    buf
+}
+
+fn decode_command(payload: &[u8]) -> Option<Command> {
+    let (tag, mut rest) = payload.split_first()?;
+    let command = match *tag {
+        TAG_DEPOSIT => Command::Deposit {
+            user_id: take_u64(&mut rest)?,
+            asset: Asset::from_bytes(take(&mut rest)?),
+            amount: take_decimal(&mut rest)?,
+        },
+        TAG_PLACE => {
+            let id = take_u64(&mut rest)?;
+            let user_id = take_u64(&mut rest)?;
+            let side = match take::<1>(&mut rest)?[0] {
+                0 => OrderSide::Bid,
+                1 => OrderSide::Ask,
+                _ => return None,
+            };
+            let price = take_decimal(&mut rest)?;
+            let quantity = take_decimal(&mut rest)?;
+            Command::Place(Order { id, price, quantity, side, user_id })
+        }
+        TAG_CANCEL => Command::Cancel { order_id: take_u64(&mut rest)? },
+        TAG_AMEND => Command::Amend {
+            order_id: take_u64(&mut rest)?,
+            price: take_decimal(&mut rest)?,
+            quantity: take_decimal(&mut rest)?,
+        },
+        TAG_REPLACE => Command::Replace {
+            order_id: take_u64(&mut rest)?,
+            price: take_decimal(&mut rest)?,
+            order_qty: take_decimal(&mut rest)?,
+        },
+        _ => return None,
+    };
+    rest.is_empty().then_some(command)
+}
+
+fn take<const N: usize>(buf: &mut &[u8]) -> Option<[u8; N]> {
+    let (head, tail) = buf.split_first_chunk::<N>()?;
+    *buf = tail;
+    Some(*head)
+}
+
+fn take_u64(buf: &mut &[u8]) -> Option<u64> {
+    take(buf).map(u64::from_le_bytes)
+}
+
+fn take_decimal(buf: &mut &[u8]) -> Option<Decimal> {
+    take(buf).map(Decimal::deserialize)
+}
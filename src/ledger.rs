@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use thiserror::Error;
+use crate::types::{Asset, DepositID, OrderID, TradeID, TransferID, UserID, WithdrawalID};
+
+pub type EntryID = u64;
+
+// 记账科目: 用户的可用 / 冻结余额，以及系统侧科目
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LedgerAccount {
+    Available(UserID),
+    Frozen(UserID),
+    WithdrawHold(UserID),
+    PendingDeposit(UserID),
+    External, // 系统外部: 充值流入 / 提现流出的对手方
+    Clearing, // 成交清算中转: 卖方扣减与买方入账在这里对冲
+    Fees,     // 手续费收入
+}
+
+// 分录原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EntryReason {
+    Deposit,
+    Freeze,
+    Unlock,
+    TradeDebit,  // 成交扣除冻结
+    TradeCredit, // 成交入账
+    Fee,
+    WithdrawalHold,    // 提现申请冻结
+    WithdrawalRelease, // 提现被拒绝退回
+    Withdrawal,        // 提现完成流出
+    DepositPending,    // 充值确认中
+    DepositReversal,   // 充值回滚
+    Transfer,          // 用户间 / 母子账户划转
+    DustSweep,         // 清算零头转入平台账户
+    Opening,           // 从快照恢复的期初余额
+}
+
+// 分录关联的业务单据
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EntryRef {
+    #[default]
+    None,
+    Order(OrderID),
+    Trade(TradeID),
+    Withdrawal(WithdrawalID),
+    Deposit(DepositID),
+    Transfer(TransferID),
+}
+
+// 单条记账: amount 为正表示该科目增加
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Posting {
+    pub account: LedgerAccount,
+    pub asset: Asset,
+    pub amount: Decimal,
+}
+
+// 一笔复式分录，所有 posting 之和必须为 0
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JournalEntry {
+    pub id: EntryID,
+    pub reason: EntryReason,
+    pub reference: EntryRef,
+    pub postings: Vec<Posting>,
+}
+
+impl JournalEntry {
+    pub fn is_balanced(&self) -> bool {
+        let mut sums: HashMap<Asset, Decimal> = HashMap::new();
+        for p in &self.postings {
+            *sums.entry(p.asset).or_default() += p.amount;
+        }
+        sums.values().all(|s| *s == dec!(0))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum LedgerError {
+    #[error("{account:?} 的 {asset} 账实不符: 账本 {ledger}, 余额 {balance}")]
+    BalanceMismatch { account: LedgerAccount, asset: Asset, ledger: Decimal, balance: Decimal },
+}
+
+// 复式记账日志: 每一次余额变动都对应一笔平衡的分录
+// 各科目余额随记账累加，分录明细可以按需裁剪而不影响余额
+#[derive(Default)]
+pub struct Ledger {
+    entries: Vec<JournalEntry>,
+    next_id: EntryID,
+    // (用户, 资产) -> 相关分录号
+    by_user_asset: HashMap<(UserID, Asset), Vec<EntryID>>,
+    totals: HashMap<(LedgerAccount, Asset), Decimal>,
+}
+
+impl Ledger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 从 from 科目转 amount 到 to 科目
+    pub(crate) fn record(
+        &mut self,
+        reason: EntryReason,
+        reference: EntryRef,
+        asset: Asset,
+        amount: Decimal,
+        from: LedgerAccount,
+        to: LedgerAccount,
+    ) -> EntryID {
+        self.next_id += 1;
+        let id = self.next_id;
+        for account in [from, to] {
+            if let Some(user_id) = account.user_id() {
+                let list = self.by_user_asset.entry((user_id, asset)).or_default();
+                if list.last() != Some(&id) {
+                    list.push(id);
+                }
+            }
+        }
+        *self.totals.entry((from, asset)).or_default() -= amount;
+        *self.totals.entry((to, asset)).or_default() += amount;
+        self.entries.push(JournalEntry {
+            id,
+            reason,
+            reference,
+            postings: vec![
+                Posting { account: from, asset, amount: -amount },
+                Posting { account: to, asset, amount },
+            ],
+        });
+        id
+    }
+
+    // 仍保留的分录明细
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    // 某用户某资产仍保留的分录，按时间顺序
+    pub fn entries_for(&self, user_id: UserID, asset: Asset) -> Vec<&JournalEntry> {
+        let first = match self.entries.first() {
+            Some(e) => e.id,
+            None => return Vec::new(),
+        };
+        self.by_user_asset
+            .get(&(user_id, asset))
+            .map(|ids| ids.iter().map(|id| &self.entries[(id - first) as usize]).collect())
+            .unwrap_or_default()
+    }
+
+    // 只保留最近 keep 笔分录明细，科目余额不受影响
+    pub fn prune(&mut self, keep: usize) {
+        let drop = self.entries.len().saturating_sub(keep);
+        if drop == 0 {
+            return;
+        }
+        self.entries.drain(..drop);
+        let first = self.entries.first().map_or(self.next_id + 1, |e| e.id);
+        self.by_user_asset.retain(|_, ids| {
+            ids.retain(|id| *id >= first);
+            !ids.is_empty()
+        });
+    }
+
+    // 各科目的累计余额
+    pub fn totals(&self) -> &HashMap<(LedgerAccount, Asset), Decimal> {
+        &self.totals
+    }
+
+    pub fn balance_of(&self, account: LedgerAccount, asset: Asset) -> Decimal {
+        self.totals.get(&(account, asset)).copied().unwrap_or_default()
+    }
+}
+
+impl LedgerAccount {
+    pub fn user_id(&self) -> Option<UserID> {
+        match self {
+            LedgerAccount::Available(u)
+            | LedgerAccount::Frozen(u)
+            | LedgerAccount::WithdrawHold(u)
+            | LedgerAccount::PendingDeposit(u) => Some(*u),
+            _ => None,
+        }
+    }
+}
@@ -1,7 +1,58 @@
 pub mod types;
+pub mod error;
 pub mod account;
+pub mod registry;
+pub mod ledger;
+pub mod audit;
+pub mod withdrawal;
+pub mod deposit;
+pub mod settlement;
+pub mod exchange;
+pub mod actor;
+pub mod ring;
+pub mod pipeline;
+pub mod shard;
+pub mod gateway;
+pub mod fix;
+pub mod fix_acceptor;
+pub mod websocket;
+pub mod market_data;
+pub mod journal;
+pub mod snapshot;
+pub mod codec;
 pub mod engine;
+pub mod observer;
+pub mod session;
+pub mod risk;
+pub mod rate_limit;
 
-pub use types::{Order, OrderSide, Asset,Price,TradeEvent};
-pub use engine::OrderBook;
-pub use account::{AccountManager,AccountError};
\ No newline at end of file
+pub use error::Error;
+pub use types::{Order, OrderSide, Asset, Price, TradeEvent, Instrument, OrderStatus, OrderReport};
+pub use engine::{OrderBook, EngineError};
+pub use account::{AccountManager, AccountError, Balance, Page};
+pub use registry::{AssetRegistry, AssetInfo, AssetStatus, AssetOperation, RegistryError};
+pub use audit::{Auditor, AuditError};
+pub use ledger::{Ledger, LedgerAccount, LedgerError, JournalEntry, EntryReason, EntryRef};
+pub use session::{SessionManager, SessionError, SessionID};
+pub use risk::{RiskEngine, RiskLimits, RiskRejection};
+pub use rate_limit::{
+    RateLimiter, RateLimit, RateLimitConfig, RateLimited, RateLimitScope, RequestKind, Clock,
+    SystemClock, ManualClock,
+};
+pub use observer::{EventListener, BalanceEvent, BalanceEventKind, OrderEvent};
+pub use withdrawal::{
+    WithdrawalManager, Withdrawal, WithdrawalStatus, WithdrawalPolicy, WithdrawalError,
+};
+pub use deposit::{DepositManager, Deposit, DepositStatus, DepositError};
+pub use settlement::{Settlement, SettlementError, Rounding};
+pub use exchange::{Exchange, Command};
+pub use actor::{Engine, EngineHandle, EngineOutput, EngineStopped};
+pub use pipeline::{Pipeline, PipelineBuilder, Processed};
+pub use shard::{ShardedEngine, ShardedEngineBuilder, ShardHandle, ShardError};
+pub use gateway::Gateway;
+pub use fix::{FixMessage, FixError};
+pub use fix_acceptor::{FixAcceptor, FixConfig, Counterparty};
+pub use market_data::{MarketDataServer, MarketDataFeed, Channel};
+pub use journal::{Journal, JournalRecord, JournalError};
+pub use snapshot::{SnapshotError, SnapshotInfo};
+pub use codec::{CodecError, Message};
@@ -1,10 +1,36 @@
 use rust_decimal_macros::dec;
 use mach_rs::{Asset, AccountManager, AccountError, OrderBook, Order, OrderSide};
+use mach_rs::{EventListener, BalanceEvent, BalanceEventKind};
+
+// 演示用: 把余额变动打印到终端
+struct PrintListener;
+
+impl EventListener for PrintListener {
+    fn on_balance(&mut self, e: &BalanceEvent) {
+        match e.kind {
+            BalanceEventKind::Deposit => println!("用户 {} 充值 {} {}, 当前可用: {}", e.user_id, e.amount, e.asset, e.available),
+            BalanceEventKind::Freeze => println!("用户 {} 冻结 {} {} 成功", e.user_id, e.amount, e.asset),
+            BalanceEventKind::Unlock => println!("用户 {} 解冻 {} {}, 资金回退", e.user_id, e.amount, e.asset),
+            BalanceEventKind::ConfirmTrade => println!("用户 {} 支出 {} {}, 交易完成", e.user_id, e.amount, e.asset),
+            BalanceEventKind::TradeCredit => println!("用户 {} 成交入账 {} {}", e.user_id, e.amount, e.asset),
+            BalanceEventKind::Fee => println!("用户 {} 支付手续费 {} {}", e.user_id, e.amount, e.asset),
+            BalanceEventKind::WithdrawalHold => println!("用户 {} 申请提现 {} {}", e.user_id, e.amount, e.asset),
+            BalanceEventKind::WithdrawalRelease => println!("用户 {} 提现被拒绝, 退回 {} {}", e.user_id, e.amount, e.asset),
+            BalanceEventKind::Withdrawal => println!("用户 {} 提现完成 {} {}", e.user_id, e.amount, e.asset),
+            BalanceEventKind::DepositPending => println!("用户 {} 充值 {} {} 等待确认", e.user_id, e.amount, e.asset),
+            BalanceEventKind::DepositReversal => println!("用户 {} 充值 {} {} 被回滚", e.user_id, e.amount, e.asset),
+            BalanceEventKind::TransferOut => println!("用户 {} 转出 {} {}", e.user_id, e.amount, e.asset),
+            BalanceEventKind::TransferIn => println!("用户 {} 转入 {} {}", e.user_id, e.amount, e.asset),
+            BalanceEventKind::DustSweep => println!("平台账户 {} 归集零头 {} {}", e.user_id, e.amount, e.asset),
+        }
+    }
+}
 
 fn main() {
     // 1. 初始化两个模块
     let mut account = AccountManager::new();
     let mut book = OrderBook::new();
+    account.add_listener(Box::new(PrintListener));
 
     // 2. 准备资产
     let usdt = Asset::from("USDT");
@@ -36,18 +62,18 @@ fn main() {
             book.match_order(order);
             println!("User 1 挂单成功");
         },
-        Err(AccountError::InsufficientAvailable) => {
-            println!("拒单：User 1 可用余额不足，请充值！");
+        Err(AccountError::InsufficientAvailable { requested, available, .. }) => {
+            println!("拒单：User 1 可用余额不足 (需要 {}, 可用 {})，请充值！", requested, available);
         },
         // 3. 失败 - 用户不存在 (可能是前端传错了 ID)
-        Err(AccountError::UserNotFound) => {
-            println!("拒单：用户 ID 1 不存在");
+        Err(AccountError::UserNotFound { user_id }) => {
+            println!("拒单：用户 ID {} 不存在", user_id);
         },
 
         // 4. 失败 - 其他严重错误 (比如 Overflow, AssetNotFound)
         Err(e) => {
-            // {:?} 会打印错误的调试信息
-            println!("系统严重错误: {:?}", e);
+            // Display 输出带上下文的错误信息
+            println!("系统严重错误: {}", e);
             // 在实际系统中，这里可能需要 panic! 或者发报警邮件
         }
     }
@@ -90,15 +116,19 @@ fn main() {
 
                 // 1. 结算 Maker (卖方 User 1)
                 // 逻辑：BTC 真正卖掉了(扣除冻结)，收到了 USDT(增加可用)
-                let _ = account.confirm_trade(trade.maker_user_id, btc, trade.quantity);
-                let _ = account.deposit(trade.maker_user_id, usdt, total_usdt);
+                let _ = account.settle_debit(trade.maker_user_id, btc, trade.quantity, trade.trade_id);
+                let _ = account.settle_credit(trade.maker_user_id, usdt, total_usdt, trade.trade_id);
 
                 // 2. 结算 Taker (买方 User 2)
                 // 逻辑：USDT 真正花掉了(扣除冻结)，收到了 BTC(增加可用)
-                let _ = account.confirm_trade(trade.taker_user_id, usdt, total_usdt);
-                let _ = account.deposit(trade.taker_user_id, btc, trade.quantity);
+                let _ = account.settle_debit(trade.taker_user_id, usdt, total_usdt, trade.trade_id);
+                let _ = account.settle_credit(trade.taker_user_id, btc, trade.quantity, trade.trade_id);
             }
             println!("结算完成！");
+            match account.verify_ledger() {
+                Ok(_) => println!("对账通过，共 {} 笔分录", account.ledger().entries().len()),
+                Err(e) => println!("对账失败: {}", e),
+            }
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,468 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io;
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender, SyncSender};
+use std::sync::Arc;
+use std::thread;
+use rust_decimal::Decimal;
+use crate::engine::OrderBook;
+use crate::observer::{EventListener, OrderEvent};
+use crate::types::{Instrument, Order, OrderID, OrderSide, Price, Quantity, TradeEvent};
+use crate::websocket::{self, Frame, Opcode};
+
+// WebSocket 行情协议，消息为 JSON 文本:
+//   请求: {"op":"subscribe"|"unsubscribe","channel":"trades"|"ticker"|"depth","instrument":"BTC/USDT"}
+//   depth:  订阅后先推送全量快照 {"type":"snapshot",...,"seq":N,"bids":[["价格","数量"],...],"asks":[...]}，
+//           之后逐条推送价格档位变化 {"type":"update",...,"seq":N+1,"side":"bid","price":"100","quantity":"1.5"}，数量为 0 表示该档删除
+//   ticker: 快照与更新格式相同 {"type":"snapshot"|"update",...,"bid":["价格","数量"]|null,"ask":...}，只在最优价或其数量变化时推送
+//   trades: 订阅应答 {"type":"subscribed",...,"seq":N}，成交 {"type":"trade",...,"trade_id":1,"price":"100","quantity":"0.5","side":"buy"} (side 为主动方)
+// seq 为交易对的档位变化序号，每次变化加一；快照的 seq 表示已包含到该序号为止的全部变化，客户端据此检测丢失
+// 慢消费者保护: 每个连接的发送队列有上限，队列满 (客户端读得太慢) 时直接断开，客户端重连后从快照重新开始
+
+const MAX_MESSAGE: usize = 4096;
+
+type ClientID = u64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Channel {
+    Trades,
+    Ticker,
+    Depth,
+}
+
+impl Channel {
+    fn name(self) -> &'static str {
+        match self {
+            Channel::Trades => "trades",
+            Channel::Ticker => "ticker",
+            Channel::Depth => "depth",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "trades" => Some(Channel::Trades),
+            "ticker" => Some(Channel::Ticker),
+            "depth" => Some(Channel::Depth),
+            _ => None,
+        }
+    }
+}
+
+enum Input {
+    Register { instrument: Instrument, orders: Vec<Order> },
+    Event { instrument: Instrument, event: OrderEvent },
+    Connect { client: ClientID, queue: SyncSender<Frame>, stream: TcpStream },
+    Subscribe { client: ClientID, instrument: String, channel: Channel },
+    Unsubscribe { client: ClientID, instrument: String, channel: Channel },
+    Disconnect(ClientID),
+}
+
+// 注册到撮合引擎上的监听器，把订单簿事件转给行情服务
+#[derive(Clone)]
+pub struct MarketDataFeed {
+    instrument: Instrument,
+    inputs: Sender<Input>,
+}
+
+impl EventListener for MarketDataFeed {
+    fn on_order(&mut self, event: &OrderEvent) {
+        let _ = self.inputs.send(Input::Event { instrument: self.instrument, event: event.clone() });
+    }
+}
+
+// WebSocket 行情服务: 一个分发线程维护各交易对的聚合深度并推送给订阅者，每个连接一个读线程和一个写线程
+pub struct MarketDataServer {
+    inputs: Sender<Input>,
+    queue_capacity: usize,
+    next_client: Arc<AtomicU64>,
+}
+
+impl MarketDataServer {
+    // queue_capacity 为每个连接最多积压的消息数，超过即断开
+    pub fn new(queue_capacity: usize) -> Self {
+        let (inputs, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut dispatcher = Dispatcher::default();
+            for input in rx {
+                dispatcher.handle(input);
+            }
+        });
+        Self { inputs, queue_capacity, next_client: Arc::default() }
+    }
+
+    // 以 book 当前的挂单为初始深度注册交易对，返回的监听器需通过 Exchange::add_listener 注册，
+    // 之后 book 的变化都要经过该监听器。每个交易对只注册一次
+    pub fn feed(&self, instrument: Instrument, book: &OrderBook) -> MarketDataFeed {
+        let orders = book.bids.values().chain(book.asks.values()).flatten().cloned().collect();
+        let _ = self.inputs.send(Input::Register { instrument, orders });
+        MarketDataFeed { instrument, inputs: self.inputs.clone() }
+    }
+
+    // 接受连接，每个连接一个线程。监听出错时返回
+    pub fn serve(&self, listener: TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            let connection = Connection {
+                id: self.next_client.fetch_add(1, Ordering::Relaxed),
+                inputs: self.inputs.clone(),
+                queue_capacity: self.queue_capacity,
+            };
+            let stream = stream?;
+            thread::spawn(move || connection.run(stream));
+        }
+        Ok(())
+    }
+}
+
+// 由订单簿事件维护的聚合深度
+struct Book {
+    instrument: Instrument,
+    seq: u64,
+    // 挂单的方向、价格与剩余数量，用于确定成交与撤单影响的档位
+    orders: HashMap<OrderID, (OrderSide, Price, Quantity)>,
+    bids: BTreeMap<Price, Quantity>,
+    asks: BTreeMap<Price, Quantity>,
+    subscribers: HashMap<Channel, BTreeSet<ClientID>>,
+}
+
+type Top = (Option<(Price, Quantity)>, Option<(Price, Quantity)>);
+
+impl Book {
+    fn new(instrument: Instrument, orders: Vec<Order>) -> Self {
+        let mut book = Self {
+            instrument,
+            seq: 0,
+            orders: HashMap::new(),
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            subscribers: HashMap::new(),
+        };
+        for order in orders {
+            book.rest(&order);
+        }
+        book
+    }
+
+    fn levels(&mut self, side: &OrderSide) -> &mut BTreeMap<Price, Quantity> {
+        match side {
+            OrderSide::Bid => &mut self.bids,
+            OrderSide::Ask => &mut self.asks,
+        }
+    }
+
+    fn rest(&mut self, order: &Order) {
+        self.orders.insert(order.id, (order.side.clone(), order.price, order.quantity));
+        *self.levels(&order.side).entry(order.price).or_default() += order.quantity;
+    }
+
+    fn top(&self) -> Top {
+        let best = |level: Option<(&Price, &Quantity)>| level.map(|(p, q)| (*p, *q));
+        (best(self.bids.iter().next_back()), best(self.asks.iter().next()))
+    }
+
+    // 应用一个订单簿事件，返回要推送的 (频道, 消息)。事件涉及未知订单时忽略
+    fn apply(&mut self, event: OrderEvent) -> Vec<(Channel, String)> {
+        let top = self.top();
+        let (side, price, delta, trade) = match event {
+            OrderEvent::Rested(order) => {
+                self.orders.insert(order.id, (order.side.clone(), order.price, order.quantity));
+                (order.side, order.price, order.quantity, None)
+            }
+            OrderEvent::Trade(trade) => {
+                let Some((side, price, remaining)) = self.orders.get_mut(&trade.maker_order_id) else { return Vec::new() };
+                *remaining -= trade.quantity;
+                let (side, price) = (side.clone(), *price);
+                if *remaining <= Decimal::ZERO {
+                    self.orders.remove(&trade.maker_order_id);
+                }
+                (side, price, -trade.quantity, Some(trade))
+            }
+            OrderEvent::Cancelled(order) | OrderEvent::Replaced(order) => {
+                let Some((side, price, remaining)) = self.orders.remove(&order.id) else { return Vec::new() };
+                (side, price, -remaining, None)
+            }
+            OrderEvent::Reduced(order) => {
+                let Some((side, price, remaining)) = self.orders.get_mut(&order.id) else { return Vec::new() };
+                let delta = order.quantity - *remaining;
+                *remaining = order.quantity;
+                (side.clone(), *price, delta, None)
+            }
+        };
+
+        self.seq += 1;
+        let levels = self.levels(&side);
+        let level = levels.entry(price).or_default();
+        *level += delta;
+        let quantity = (*level).max(Decimal::ZERO);
+        if quantity.is_zero() {
+            levels.remove(&price);
+        }
+
+        let mut messages = Vec::new();
+        if let Some(trade) = trade {
+            // 主动方与挂单方方向相反
+            let taker = match side {
+                OrderSide::Bid => "sell",
+                OrderSide::Ask => "buy",
+            };
+            messages.push((Channel::Trades, self.trade(&trade, taker)));
+        }
+        messages.push((
+            Channel::Depth,
+            format!(
+                r#"{},"side":"{}","price":"{}","quantity":"{}"}}"#,
+                self.header("update", Channel::Depth),
+                side_name(&side),
+                price.normalize(),
+                quantity.normalize()
+            ),
+        ));
+        if self.top() != top {
+            messages.push((Channel::Ticker, self.ticker("update")));
+        }
+        messages
+    }
+
+    fn header(&self, kind: &str, channel: Channel) -> String {
+        format!(r#"{{"type":"{kind}","channel":"{}","instrument":"{}","seq":{}"#, channel.name(), self.instrument, self.seq)
+    }
+
+    fn trade(&self, trade: &TradeEvent, taker: &str) -> String {
+        format!(
+            r#"{},"trade_id":{},"price":"{}","quantity":"{}","side":"{taker}"}}"#,
+            self.header("trade", Channel::Trades),
+            trade.trade_id,
+            trade.price.normalize(),
+            trade.quantity.normalize()
+        )
+    }
+
+    fn ticker(&self, kind: &str) -> String {
+        let level = |l: Option<(Price, Quantity)>| match l {
+            Some((p, q)) => format!(r#"["{}","{}"]"#, p.normalize(), q.normalize()),
+            None => String::from("null"),
+        };
+        let (bid, ask) = self.top();
+        format!(r#"{},"bid":{},"ask":{}}}"#, self.header(kind, Channel::Ticker), level(bid), level(ask))
+    }
+
+    // 订阅时的首条消息
+    fn snapshot(&self, channel: Channel) -> String {
+        match channel {
+            Channel::Trades => format!("{}}}", self.header("subscribed", channel)),
+            Channel::Ticker => self.ticker("snapshot"),
+            Channel::Depth => {
+                let levels = |levels: &mut dyn Iterator<Item = (&Price, &Quantity)>| {
+                    let levels: Vec<String> = levels.map(|(p, q)| format!(r#"["{}","{}"]"#, p.normalize(), q.normalize())).collect();
+                    levels.join(",")
+                };
+                format!(
+                    r#"{},"bids":[{}],"asks":[{}]}}"#,
+                    self.header("snapshot", channel),
+                    levels(&mut self.bids.iter().rev()),
+                    levels(&mut self.asks.iter())
+                )
+            }
+        }
+    }
+}
+
+fn side_name(side: &OrderSide) -> &'static str {
+    match side {
+        OrderSide::Bid => "bid",
+        OrderSide::Ask => "ask",
+    }
+}
+
+fn error(message: &str) -> String {
+    format!(r#"{{"type":"error","message":"{message}"}}"#)
+}
+
+struct Client {
+    queue: SyncSender<Frame>,
+    // 用于断开慢消费者
+    stream: TcpStream,
+}
+
+#[derive(Default)]
+struct Dispatcher {
+    books: HashMap<Instrument, Book>,
+    clients: HashMap<ClientID, Client>,
+}
+
+impl Dispatcher {
+    fn handle(&mut self, input: Input) {
+        match input {
+            Input::Register { instrument, orders } => {
+                self.books.entry(instrument).or_insert_with(|| Book::new(instrument, orders));
+            }
+            Input::Event { instrument, event } => {
+                let Some(book) = self.books.get_mut(&instrument) else { return };
+                let mut lagging = BTreeSet::new();
+                for (channel, message) in book.apply(event) {
+                    let frame = Frame::text(message);
+                    for &id in book.subscribers.get(&channel).into_iter().flatten() {
+                        if !lagging.contains(&id) && !send(&self.clients, id, frame.clone()) {
+                            lagging.insert(id);
+                        }
+                    }
+                }
+                for id in lagging {
+                    self.disconnect(id);
+                }
+            }
+            Input::Connect { client, queue, stream } => {
+                self.clients.insert(client, Client { queue, stream });
+            }
+            Input::Subscribe { client, instrument, channel } => {
+                let reply = match self.books.values_mut().find(|b| b.instrument.to_string() == instrument) {
+                    Some(book) => {
+                        book.subscribers.entry(channel).or_default().insert(client);
+                        book.snapshot(channel)
+                    }
+                    None => error("交易对不存在"),
+                };
+                self.reply(client, reply);
+            }
+            Input::Unsubscribe { client, instrument, channel } => {
+                let reply = match self.books.values_mut().find(|b| b.instrument.to_string() == instrument) {
+                    Some(book) => {
+                        book.subscribers.entry(channel).or_default().remove(&client);
+                        format!("{}}}", book.header("unsubscribed", channel))
+                    }
+                    None => error("交易对不存在"),
+                };
+                self.reply(client, reply);
+            }
+            Input::Disconnect(client) => self.disconnect(client),
+        }
+    }
+
+    fn reply(&mut self, client: ClientID, message: String) {
+        if !send(&self.clients, client, Frame::text(message)) {
+            self.disconnect(client);
+        }
+    }
+
+    fn disconnect(&mut self, client: ClientID) {
+        if let Some(c) = self.clients.remove(&client) {
+            let _ = c.stream.shutdown(Shutdown::Both);
+        }
+        for book in self.books.values_mut() {
+            for subscribers in book.subscribers.values_mut() {
+                subscribers.remove(&client);
+            }
+        }
+    }
+}
+
+// 队列满或连接已关闭时返回 false，不会阻塞分发线程
+fn send(clients: &HashMap<ClientID, Client>, client: ClientID, frame: Frame) -> bool {
+    clients.get(&client).is_some_and(|c| c.queue.try_send(frame).is_ok())
+}
+
+struct Connection {
+    id: ClientID,
+    inputs: Sender<Input>,
+    queue_capacity: usize,
+}
+
+impl Connection {
+    fn run(self, mut stream: TcpStream) {
+        if websocket::accept(&mut stream).is_err() {
+            return;
+        }
+        let (Ok(mut writer), Ok(control)) = (stream.try_clone(), stream.try_clone()) else { return };
+        // 写线程: 行情推送与本连接的应答都经由同一个有界队列
+        let (queue, outgoing) = mpsc::sync_channel::<Frame>(self.queue_capacity);
+        thread::spawn(move || {
+            for frame in outgoing {
+                if websocket::write_frame(&mut writer, &frame, None).is_err() {
+                    break;
+                }
+                // Close 发出后关闭连接，读循环随之结束
+                if frame.opcode == Opcode::Close {
+                    let _ = writer.shutdown(Shutdown::Both);
+                    break;
+                }
+            }
+        });
+        if self.inputs.send(Input::Connect { client: self.id, queue: queue.clone(), stream: control }).is_err() {
+            return;
+        }
+
+        // 正在拼接的分片消息及其首帧的 opcode
+        let mut message: Option<(Opcode, Vec<u8>)> = None;
+        let mut closing = false;
+        while let Ok(frame) = websocket::read_frame(&mut stream, MAX_MESSAGE, true) {
+            // 已发出 Close，丢弃之后收到的帧
+            if closing {
+                continue;
+            }
+            let close = match frame.opcode {
+                Opcode::Ping => {
+                    let _ = queue.try_send(Frame { fin: true, opcode: Opcode::Pong, payload: frame.payload });
+                    None
+                }
+                Opcode::Pong => None,
+                Opcode::Close => Some(1000),
+                // 没有进行中的消息却收到续帧，或分片消息中途开始新消息: 协议错误
+                Opcode::Continuation if message.is_none() => Some(1002),
+                Opcode::Text | Opcode::Binary if message.is_some() => Some(1002),
+                Opcode::Text | Opcode::Binary | Opcode::Continuation => {
+                    let (_, payload) = message.get_or_insert_with(|| (frame.opcode, Vec::new()));
+                    payload.extend_from_slice(&frame.payload);
+                    if payload.len() > MAX_MESSAGE {
+                        break;
+                    }
+                    match message.take_if(|_| frame.fin) {
+                        Some((opcode, payload)) => self.message(&queue, opcode, payload),
+                        None => None,
+                    }
+                }
+            };
+            if let Some(code) = close {
+                if queue.try_send(Frame::close(Some(code))).is_err() {
+                    break;
+                }
+                closing = true;
+            }
+        }
+        let _ = self.inputs.send(Input::Disconnect(self.id));
+    }
+
+    // 处理一条完整消息，返回 Some 时以该状态码关闭连接
+    fn message(&self, queue: &SyncSender<Frame>, opcode: Opcode, payload: Vec<u8>) -> Option<u16> {
+        let reply = match String::from_utf8(payload) {
+            Ok(text) => self.request(&text).err(),
+            // 文本消息必须是合法的 UTF-8
+            Err(_) if opcode == Opcode::Text => return Some(1007),
+            Err(_) => Some(error("请求应为 UTF-8 编码的 JSON")),
+        };
+        if let Some(reply) = reply {
+            let _ = queue.try_send(Frame::text(reply));
+        }
+        None
+    }
+
+    // Err 为格式错误的应答
+    fn request(&self, text: &str) -> Result<(), String> {
+        let channel = json_field(text, "channel").and_then(Channel::parse).ok_or_else(|| error("channel 应为 trades、ticker 或 depth"))?;
+        let instrument = json_field(text, "instrument").ok_or_else(|| error("缺少 instrument"))?.to_string();
+        let input = match json_field(text, "op") {
+            Some("subscribe") => Input::Subscribe { client: self.id, instrument, channel },
+            Some("unsubscribe") => Input::Unsubscribe { client: self.id, instrument, channel },
+            _ => return Err(error("op 应为 subscribe 或 unsubscribe")),
+        };
+        self.inputs.send(input).map_err(|_| error("行情服务已停止"))
+    }
+}
+
+// 只取扁平 JSON 对象中的字符串字段，足够解析订阅请求
+fn json_field<'a>(text: &'a str, key: &str) -> Option<&'a str> {
+    let pattern = format!("\"{key}\"");
+    let rest = &text[text.find(&pattern)? + pattern.len()..];
+    let rest = rest.trim_start().strip_prefix(':')?.trim_start().strip_prefix('"')?;
+    rest.split('"').next()
+}
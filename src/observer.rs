@@ -0,0 +1,76 @@
+use rust_decimal::Decimal;
+use crate::types::{Asset, Order, TradeEvent, UserID};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BalanceEventKind {
+    Deposit,      // 充值
+    Freeze,       // 下单冻结
+    Unlock,       // 撤单解冻
+    ConfirmTrade, // 成交扣除冻结
+    TradeCredit,  // 成交入账
+    Fee,          // 扣手续费
+    WithdrawalHold,    // 提现申请冻结
+    WithdrawalRelease, // 提现被拒绝退回
+    Withdrawal,        // 提现完成
+    DepositPending,    // 充值确认中
+    DepositReversal,   // 充值回滚
+    TransferOut,       // 划转转出
+    TransferIn,        // 划转转入
+    DustSweep,         // 收到清算零头 (平台账户)
+}
+
+// 一次余额变动，available / frozen 为变动后的余额
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BalanceEvent {
+    pub kind: BalanceEventKind,
+    pub user_id: UserID,
+    pub asset: Asset,
+    pub amount: Decimal,
+    pub available: Decimal,
+    pub frozen: Decimal,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OrderEvent {
+    Rested(Order),    // 未成交部分进入订单簿
+    Trade(TradeEvent),
+    Cancelled(Order), // quantity 为撤销时的剩余数量
+    Reduced(Order),   // 改单原地减量，保留时间优先级；quantity 为新的剩余数量
+    Replaced(Order),  // 改单从原位置取出 (quantity 为取出时的剩余数量)，随后按新价格重新撮合
+}
+
+// 事件监听器: 替代直接打印日志，由调用方决定如何处理 (打印、统计、推送行情...)
+// 没有注册监听器时不产生任何事件对象
+pub trait EventListener: Send {
+    fn on_balance(&mut self, _event: &BalanceEvent) {}
+    fn on_order(&mut self, _event: &OrderEvent) {}
+}
+
+// 监听器列表
+#[derive(Default)]
+pub(crate) struct Listeners(Vec<Box<dyn EventListener>>);
+
+impl Listeners {
+    pub(crate) fn add(&mut self, listener: Box<dyn EventListener>) {
+        self.0.push(listener);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn balance(&mut self, event: BalanceEvent) {
+        for l in self.0.iter_mut() {
+            l.on_balance(&event);
+        }
+    }
+
+    pub(crate) fn order(&mut self, event: OrderEvent) {
+        for l in self.0.iter_mut() {
+            l.on_order(&event);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread::{self, JoinHandle};
+use crate::actor::Outcome;
+use crate::error::Error;
+use crate::exchange::{Command, Exchange};
+use crate::journal::{Journal, JournalError};
+use crate::ring::{self, Producer};
+
+// 撮合线程处理完一条指令后发布到输出环的结果
+#[derive(Debug, Clone)]
+pub struct Processed {
+    pub seq: u64,
+    pub command: Command,
+    pub outcome: Outcome,
+}
+
+type JournalStage = Box<dyn FnMut(&Command) -> Result<u64, JournalError> + Send>;
+type OutputStage = Box<dyn FnMut(&Processed) + Send>;
+
+// 环形缓冲流水线:
+// 定序器 -> 输入环 -> [日志线程] -> 撮合线程 -> 输出环 -> 各下游消费者 (行情、清算通知...)
+// 撮合线程只处理日志线程已写入的序号，下游消费者各自维护游标、互不阻塞
+pub struct PipelineBuilder {
+    exchange: Exchange,
+    capacity: usize,
+    journal: Option<JournalStage>,
+    consumers: Vec<OutputStage>,
+}
+
+impl PipelineBuilder {
+    // capacity 为输入环与输出环的容量
+    pub fn new(exchange: Exchange, capacity: usize) -> Self {
+        Self { exchange, capacity, journal: None, consumers: Vec::new() }
+    }
+
+    // 日志必须紧接在 exchange 的状态之后
+    pub fn journal<W: Write + Send + 'static>(mut self, mut journal: Journal<W>) -> Result<Self, JournalError> {
+        let expected = self.exchange.last_seq() + 1;
+        if journal.next_seq() != expected {
+            return Err(JournalError::SequenceGap { expected, found: journal.next_seq() });
+        }
+        self.journal = Some(Box::new(move |command| journal.append(command)));
+        Ok(self)
+    }
+
+    pub fn consumer<F: FnMut(&Processed) + Send + 'static>(mut self, consumer: F) -> Self {
+        self.consumers.push(Box::new(consumer));
+        self
+    }
+
+    pub fn spawn(self) -> Pipeline {
+        let PipelineBuilder { mut exchange, capacity, journal, consumers } = self;
+        let mut input = ring::ring_buffer::<Option<Command>>(capacity);
+        let mut output = ring::ring_buffer::<Option<Processed>>(capacity);
+        let mut threads = Vec::new();
+
+        // 日志写失败后，从失败的序号起不再撮合
+        let failed_at = Arc::new(AtomicU64::new(u64::MAX));
+        let journal_error = Arc::new(Mutex::new(None));
+        let base = exchange.last_seq();
+
+        let matcher_input = match journal {
+            Some(mut append) => {
+                let journal_input = input.subscribe(&[]);
+                let matcher_input = input.subscribe(&[&journal_input]);
+                let (failed_at, journal_error) = (failed_at.clone(), journal_error.clone());
+                threads.push(thread::spawn(move || {
+                    journal_input.run(|seq, command: &Option<Command>| {
+                        let Some(command) = command else { return };
+                        if failed_at.load(Ordering::Relaxed) != u64::MAX {
+                            return;
+                        }
+                        if let Err(e) = append(command) {
+                            *journal_error.lock().unwrap() = Some(e);
+                            failed_at.store(base + seq + 1, Ordering::Release);
+                        }
+                    });
+                }));
+                matcher_input
+            }
+            None => input.subscribe(&[]),
+        };
+        for mut consumer in consumers {
+            let output_consumer = output.subscribe(&[]);
+            threads.push(thread::spawn(move || {
+                output_consumer.run(|_, processed: &Option<Processed>| {
+                    if let Some(processed) = processed {
+                        consumer(processed);
+                    }
+                });
+            }));
+        }
+
+        let matcher = thread::spawn(move || {
+            matcher_input.run(|seq, command| {
+                let Some(command) = command else { return };
+                let seq = base + seq + 1;
+                if seq >= failed_at.load(Ordering::Acquire) {
+                    return;
+                }
+                let outcome = exchange.apply(seq, command);
+                output.publish(Some(Processed { seq, command: command.clone(), outcome }));
+            });
+            exchange
+        });
+
+        Pipeline { sequencer: input, matcher, threads, journal_error }
+    }
+}
+
+pub struct Pipeline {
+    sequencer: Producer<Option<Command>>,
+    matcher: JoinHandle<Exchange>,
+    threads: Vec<JoinHandle<()>>,
+    journal_error: Arc<Mutex<Option<JournalError>>>,
+}
+
+impl Pipeline {
+    // 定序: 指令按发布顺序获得序号。输入环满时等待
+    // 校验不通过的指令 (见 Command::validate) 不分配序号，也不写日志
+    pub fn publish(&mut self, command: Command) -> Result<(), Error> {
+        command.validate()?;
+        self.sequencer.publish(Some(command));
+        Ok(())
+    }
+
+    // 日志写入失败的原因 (如果有)，失败之后的指令都不会被撮合
+    pub fn journal_error(&self) -> Option<JournalError> {
+        self.journal_error.lock().unwrap().clone()
+    }
+
+    // 关闭输入，等各阶段处理完已发布的指令后交回状态
+    // 某个阶段异常退出不会卡住其他阶段，在这里报告
+    pub fn shutdown(self) -> Exchange {
+        let Pipeline { sequencer, matcher, threads, .. } = self;
+        drop(sequencer);
+        let exchange = matcher.join().expect("撮合线程异常退出");
+        for t in threads {
+            t.join().expect("流水线线程异常退出");
+        }
+        exchange
+    }
+}
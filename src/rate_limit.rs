@@ -0,0 +1,210 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Instant;
+use thiserror::Error;
+use crate::types::{Instrument, UserID};
+
+// 时钟抽象，单位为毫秒；测试中可注入手动推进的时钟
+pub trait Clock {
+    fn now_millis(&self) -> u64;
+}
+
+// 系统单调时钟
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        SystemClock { start: Instant::now() }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+}
+
+// 手动推进的时钟，用于测试与回放
+#[derive(Default)]
+pub struct ManualClock {
+    now: Cell<u64>,
+}
+
+impl ManualClock {
+    pub fn new(now: u64) -> Self {
+        ManualClock { now: Cell::new(now) }
+    }
+
+    pub fn advance(&self, millis: u64) {
+        self.now.set(self.now.get() + millis);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_millis(&self) -> u64 {
+        self.now.get()
+    }
+}
+
+// 令牌桶参数: 容量 capacity，每 refill_millis 毫秒补充 1 个令牌
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub capacity: u32,
+    pub refill_millis: u64,
+}
+
+impl RateLimit {
+    // 每秒 per_second 次，允许 burst 的突发
+    pub fn per_second(per_second: u32, burst: u32) -> Self {
+        RateLimit {
+            capacity: burst.max(1),
+            refill_millis: (1000 / per_second.max(1) as u64).max(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitScope {
+    User,
+    Instrument,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestKind {
+    NewOrder,
+    Cancel,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{kind:?} 请求触发 {scope:?} 维度限流, {retry_after_millis}ms 后重试")]
+pub struct RateLimited {
+    pub scope: RateLimitScope,
+    pub kind: RequestKind,
+    pub retry_after_millis: u64, // 多久之后重试可以拿到令牌
+}
+
+struct TokenBucket {
+    tokens: u32,
+    last_refill: u64,
+}
+
+impl TokenBucket {
+    fn new(limit: &RateLimit, now: u64) -> Self {
+        TokenBucket { tokens: limit.capacity, last_refill: now }
+    }
+
+    fn refill(&mut self, limit: &RateLimit, now: u64) {
+        let elapsed = now.saturating_sub(self.last_refill);
+        let new_tokens = elapsed / limit.refill_millis;
+        if new_tokens > 0 {
+            self.tokens = (self.tokens as u64 + new_tokens).min(limit.capacity as u64) as u32;
+            self.last_refill += new_tokens * limit.refill_millis;
+        }
+        if self.tokens == limit.capacity {
+            self.last_refill = now;
+        }
+    }
+
+    fn retry_after(&self, limit: &RateLimit, now: u64) -> u64 {
+        (self.last_refill + limit.refill_millis).saturating_sub(now).max(1)
+    }
+}
+
+// 一组按 key 区分的令牌桶
+struct Buckets<K> {
+    limit: RateLimit,
+    buckets: HashMap<K, TokenBucket>,
+}
+
+impl<K: Eq + Hash> Buckets<K> {
+    fn new(limit: RateLimit) -> Self {
+        Buckets { limit, buckets: HashMap::new() }
+    }
+
+    // 刷新令牌并返回是否有余量；不足时返回需要等待的毫秒数
+    fn peek(&mut self, key: K, now: u64) -> Result<&mut TokenBucket, u64> {
+        let limit = self.limit;
+        let bucket = self.buckets.entry(key).or_insert_with(|| TokenBucket::new(&limit, now));
+        bucket.refill(&limit, now);
+        if bucket.tokens == 0 {
+            return Err(bucket.retry_after(&limit, now));
+        }
+        Ok(bucket)
+    }
+}
+
+// 下单 / 撤单限流: 用户维度与交易对维度各自独立计数，新单与撤单使用不同额度
+pub struct RateLimiter<C: Clock> {
+    clock: C,
+    user_orders: Buckets<UserID>,
+    user_cancels: Buckets<UserID>,
+    instrument_orders: Buckets<Instrument>,
+    instrument_cancels: Buckets<Instrument>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitConfig {
+    pub user_orders: RateLimit,
+    pub user_cancels: RateLimit,
+    pub instrument_orders: RateLimit,
+    pub instrument_cancels: RateLimit,
+}
+
+impl<C: Clock> RateLimiter<C> {
+    pub fn new(config: RateLimitConfig, clock: C) -> Self {
+        RateLimiter {
+            clock,
+            user_orders: Buckets::new(config.user_orders),
+            user_cancels: Buckets::new(config.user_cancels),
+            instrument_orders: Buckets::new(config.instrument_orders),
+            instrument_cancels: Buckets::new(config.instrument_cancels),
+        }
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    // 在 match_order 之前调用
+    pub fn check_new_order(&mut self, user_id: UserID, instrument: Instrument) -> Result<(), RateLimited> {
+        self.acquire(RequestKind::NewOrder, user_id, instrument)
+    }
+
+    // 在 cancel_order 之前调用
+    pub fn check_cancel(&mut self, user_id: UserID, instrument: Instrument) -> Result<(), RateLimited> {
+        self.acquire(RequestKind::Cancel, user_id, instrument)
+    }
+
+    fn acquire(&mut self, kind: RequestKind, user_id: UserID, instrument: Instrument) -> Result<(), RateLimited> {
+        let now = self.clock.now_millis();
+        let (users, instruments) = match kind {
+            RequestKind::NewOrder => (&mut self.user_orders, &mut self.instrument_orders),
+            RequestKind::Cancel => (&mut self.user_cancels, &mut self.instrument_cancels),
+        };
+
+        // 两个维度都有余量才扣减，避免被拒绝的请求消耗另一维度的额度
+        let user_bucket = users.peek(user_id, now).map_err(|retry_after_millis| RateLimited {
+            scope: RateLimitScope::User,
+            kind,
+            retry_after_millis,
+        })?;
+        let instrument_bucket = instruments.peek(instrument, now).map_err(|retry_after_millis| RateLimited {
+            scope: RateLimitScope::Instrument,
+            kind,
+            retry_after_millis,
+        })?;
+
+        user_bucket.tokens -= 1;
+        instrument_bucket.tokens -= 1;
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use rust_decimal::Decimal;
+use thiserror::Error;
+use crate::snapshot::{Decoder, Encoder};
+use crate::types::Asset;
+
+// 资产符号最长 8 字节，与 Asset 的定长布局一致
+pub const MAX_SYMBOL_LEN: usize = 8;
+// rust_decimal 最多支持 28 位小数
+pub const MAX_PRECISION: u32 = 28;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AssetStatus {
+    Enabled,          // 全部业务可用
+    DepositOnly,      // 只允许充值，不能交易、划转或提现
+    WithdrawDisabled, // 暂停提现，其余业务正常
+    Disabled,         // 下架: 不能发起任何新业务
+}
+
+// 需要按资产状态放行的业务
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetOperation {
+    Deposit,
+    Trade,
+    Transfer,
+    Withdraw,
+}
+
+impl AssetStatus {
+    pub fn allows(&self, operation: AssetOperation) -> bool {
+        match self {
+            AssetStatus::Enabled => true,
+            AssetStatus::DepositOnly => operation == AssetOperation::Deposit,
+            AssetStatus::WithdrawDisabled => operation != AssetOperation::Withdraw,
+            AssetStatus::Disabled => false,
+        }
+    }
+}
+
+impl fmt::Display for AssetOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AssetOperation::Deposit => "充值",
+            AssetOperation::Trade => "交易",
+            AssetOperation::Transfer => "划转",
+            AssetOperation::Withdraw => "提现",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AssetInfo {
+    pub asset: Asset,
+    pub name: String,   // 展示名称，例如 "Tether USD"
+    pub precision: u32, // 允许的最大小数位数
+    pub status: AssetStatus,
+}
+
+impl AssetInfo {
+    // 金额的小数位数是否在精度以内 (末尾的 0 不计)
+    pub fn fits_precision(&self, amount: Decimal) -> bool {
+        amount.normalize().scale() <= self.precision
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum RegistryError {
+    #[error("资产符号 {symbol:?} 无效")]
+    InvalidSymbol { symbol: String },
+    #[error("资产符号 {symbol:?} 超过 {MAX_SYMBOL_LEN} 字节")]
+    SymbolTooLong { symbol: String },
+    #[error("资产 {asset} 的精度 {precision} 超过上限 {MAX_PRECISION}")]
+    InvalidPrecision { asset: Asset, precision: u32 },
+    #[error("资产 {asset} 已注册")]
+    DuplicateAsset { asset: Asset },
+    #[error("资产 {asset} 未注册")]
+    UnknownAsset { asset: Asset },
+}
+
+// 校验符号并转换为 Asset，拒绝任何会被截断的输入
+pub fn parse_symbol(symbol: &str) -> Result<Asset, RegistryError> {
+    let asset = Asset::try_new(symbol)?;
+    let valid = symbol.bytes().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'_');
+    if !valid {
+        return Err(RegistryError::InvalidSymbol { symbol: symbol.to_string() });
+    }
+    Ok(asset)
+}
+
+// 资产注册表: 只有注册过的资产才能进入账户系统
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AssetRegistry {
+    assets: BTreeMap<Asset, AssetInfo>,
+}
+
+impl AssetRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 注册新资产，初始状态为 Enabled
+    pub fn register(&mut self, symbol: &str, name: &str, precision: u32) -> Result<Asset, RegistryError> {
+        let asset = parse_symbol(symbol)?;
+        if precision > MAX_PRECISION {
+            return Err(RegistryError::InvalidPrecision { asset, precision });
+        }
+        if self.assets.contains_key(&asset) {
+            return Err(RegistryError::DuplicateAsset { asset });
+        }
+
+        self.assets.insert(asset, AssetInfo {
+            asset,
+            name: name.to_string(),
+            precision,
+            status: AssetStatus::Enabled,
+        });
+        Ok(asset)
+    }
+
+    pub fn set_status(&mut self, asset: Asset, status: AssetStatus) -> Result<(), RegistryError> {
+        let info = self.assets.get_mut(&asset).ok_or(RegistryError::UnknownAsset { asset })?;
+        info.status = status;
+        Ok(())
+    }
+
+    pub fn get(&self, asset: Asset) -> Option<&AssetInfo> {
+        self.assets.get(&asset)
+    }
+
+    // 按符号查找，非法或会被截断的符号直接返回 None
+    pub fn lookup(&self, symbol: &str) -> Option<&AssetInfo> {
+        self.assets.get(&parse_symbol(symbol).ok()?)
+    }
+
+    // 全部已注册资产，按符号排序
+    pub fn assets(&self) -> impl Iterator<Item = &AssetInfo> {
+        self.assets.values()
+    }
+
+    pub(crate) fn encode(&self, e: &mut Encoder) {
+        e.len(self.assets.len());
+        for info in self.assets.values() {
+            e.asset(info.asset);
+            e.str(&info.name);
+            e.u32(info.precision);
+            e.u8(match info.status {
+                AssetStatus::Enabled => 0,
+                AssetStatus::DepositOnly => 1,
+                AssetStatus::WithdrawDisabled => 2,
+                AssetStatus::Disabled => 3,
+            });
+        }
+    }
+
+    pub(crate) fn decode(d: &mut Decoder) -> Option<Self> {
+        let mut registry = AssetRegistry::new();
+        for _ in 0..d.len()? {
+            let asset = d.asset()?;
+            let name = d.string()?;
+            let precision = d.u32()?;
+            let status = match d.u8()? {
+                0 => AssetStatus::Enabled,
+                1 => AssetStatus::DepositOnly,
+                2 => AssetStatus::WithdrawDisabled,
+                3 => AssetStatus::Disabled,
+                _ => return None,
+            };
+            registry.assets.insert(asset, AssetInfo { asset, name, precision, status });
+        }
+        Some(registry)
+    }
+}
@@ -0,0 +1,196 @@
+use std::cell::UnsafeCell;
+use std::hint;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+
+// 预分配的单生产者 / 多消费者环形缓冲 (Disruptor 风格):
+// 生产者按序号写槽位，每个消费者维护自己的游标，生产者不会覆盖最慢的消费者还没处理的槽位。
+// 消费者可以依赖上游消费者，只处理上游已经处理过的序号 (如先落日志再撮合)。
+struct Shared<T> {
+    slots: Box<[UnsafeCell<T>]>,
+    mask: u64,
+    published: AtomicU64, // 已发布的条数，即下一个写入的序号
+    closed: AtomicBool,
+}
+
+// SAFETY: 槽位的读写由序号协议隔开: 生产者只写所有消费者都已处理过的槽位，
+// 消费者只读已发布 (published 以 Release 写入) 且尚未被回收的槽位
+unsafe impl<T: Send + Sync> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+    fn slot(&self, seq: u64) -> *mut T {
+        self.slots[(seq & self.mask) as usize].get()
+    }
+}
+
+// 消费者游标。消费者释放 (包括 panic 展开) 后标记为已退出:
+// 生产者不再等它，依赖它的下游消费者处理完它处理过的部分后也随之退出
+struct Cursor {
+    seq: AtomicU64, // 已处理的条数
+    detached: AtomicBool,
+}
+
+impl Cursor {
+    // 已退出的游标返回 None
+    fn live(&self) -> Option<u64> {
+        if self.detached.load(Ordering::Acquire) { None } else { Some(self.seq.load(Ordering::Acquire)) }
+    }
+}
+
+pub struct Producer<T> {
+    shared: Arc<Shared<T>>,
+    next: u64,
+    gating: Vec<Arc<Cursor>>,
+    cached_min: u64, // 上次看到的最慢消费者游标，减少读取原子变量的次数
+}
+
+pub struct Consumer<T> {
+    shared: Arc<Shared<T>>,
+    cursor: Arc<Cursor>,
+    upstream: Vec<Arc<Cursor>>,
+}
+
+// 容量向上取整为 2 的幂，槽位用 T::default() 预先填满
+pub fn ring_buffer<T: Default>(capacity: usize) -> Producer<T> {
+    let capacity = capacity.max(1).next_power_of_two();
+    let slots = (0..capacity).map(|_| UnsafeCell::new(T::default())).collect();
+    Producer {
+        shared: Arc::new(Shared {
+            slots,
+            mask: capacity as u64 - 1,
+            published: AtomicU64::new(0),
+            closed: AtomicBool::new(false),
+        }),
+        next: 0,
+        gating: Vec::new(),
+        cached_min: 0,
+    }
+}
+
+impl<T> Producer<T> {
+    pub fn capacity(&self) -> usize {
+        self.shared.slots.len()
+    }
+
+    // 已发布的条数
+    pub fn published(&self) -> u64 {
+        self.next
+    }
+
+    // 新增消费者，从下一条发布的数据开始处理；after 中的消费者处理过的序号才对它可见
+    pub fn subscribe(&mut self, after: &[&Consumer<T>]) -> Consumer<T> {
+        let cursor = Arc::new(Cursor { seq: AtomicU64::new(self.next), detached: AtomicBool::new(false) });
+        self.gating.push(cursor.clone());
+        Consumer {
+            shared: self.shared.clone(),
+            cursor,
+            upstream: after.iter().map(|c| c.cursor.clone()).collect(),
+        }
+    }
+
+    // 发布一条数据，返回它的序号 (从 0 开始)。缓冲区满时等待最慢的消费者
+    pub fn publish(&mut self, value: T) -> u64 {
+        let mut spins = 0;
+        while self.is_full() {
+            backoff(&mut spins);
+        }
+        self.write(value)
+    }
+
+    // 缓冲区满时不等待，原样退回
+    pub fn try_publish(&mut self, value: T) -> Result<u64, T> {
+        if self.is_full() { Err(value) } else { Ok(self.write(value)) }
+    }
+
+    fn is_full(&mut self) -> bool {
+        let capacity = self.capacity() as u64;
+        if self.next - self.cached_min < capacity {
+            return false;
+        }
+        self.cached_min = self.gating.iter().filter_map(|c| c.live()).min().unwrap_or(self.next);
+        self.next - self.cached_min >= capacity
+    }
+
+    fn write(&mut self, value: T) -> u64 {
+        let seq = self.next;
+        // SAFETY: is_full 已确认所有消费者都处理过这个槽位上一轮的数据
+        unsafe { *self.shared.slot(seq) = value };
+        self.next += 1;
+        self.shared.published.store(self.next, Ordering::Release);
+        seq
+    }
+}
+
+// 生产者释放即关闭，消费者处理完剩余数据后退出 run
+impl<T> Drop for Producer<T> {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Release);
+    }
+}
+
+impl<T> Consumer<T> {
+    // 已处理的条数
+    pub fn cursor(&self) -> u64 {
+        self.cursor.seq.load(Ordering::Acquire)
+    }
+
+    fn available(&self) -> u64 {
+        let published = self.shared.published.load(Ordering::Acquire);
+        self.upstream.iter().map(|c| c.seq.load(Ordering::Acquire)).fold(published, u64::min)
+    }
+
+    // 有上游已退出，且它处理过的部分本消费者都已处理完
+    fn upstream_gone(&self) -> bool {
+        let cursor = self.cursor.seq.load(Ordering::Relaxed);
+        self.upstream.iter().any(|c| c.live().is_none() && c.seq.load(Ordering::Acquire) == cursor)
+    }
+
+    // 批量处理当前可见的全部数据，返回处理的条数；批处理结束才推进游标
+    pub fn poll<F: FnMut(u64, &T)>(&mut self, mut handler: F) -> usize {
+        let start = self.cursor.seq.load(Ordering::Relaxed);
+        let end = self.available();
+        if end <= start {
+            return 0;
+        }
+        for seq in start..end {
+            // SAFETY: seq 已发布，且本消费者游标未越过它，生产者不会改写这个槽位
+            handler(seq, unsafe { &*self.shared.slot(seq) });
+        }
+        self.cursor.seq.store(end, Ordering::Release);
+        (end - start) as usize
+    }
+
+    // 持续处理，直到生产者关闭且全部数据处理完，或上游消费者已退出
+    pub fn run<F: FnMut(u64, &T)>(mut self, mut handler: F) {
+        let mut spins = 0;
+        loop {
+            let closed = self.shared.closed.load(Ordering::Acquire);
+            if self.poll(&mut handler) > 0 {
+                spins = 0;
+            } else if (closed && self.cursor.seq.load(Ordering::Relaxed) == self.shared.published.load(Ordering::Acquire))
+                || self.upstream_gone()
+            {
+                return;
+            } else {
+                backoff(&mut spins);
+            }
+        }
+    }
+}
+
+impl<T> Drop for Consumer<T> {
+    fn drop(&mut self) {
+        self.cursor.detached.store(true, Ordering::Release);
+    }
+}
+
+// 先自旋，等久了让出 CPU
+fn backoff(spins: &mut u32) {
+    if *spins < 64 {
+        hint::spin_loop();
+    } else {
+        thread::yield_now();
+    }
+    *spins = spins.saturating_add(1);
+}
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use thiserror::Error;
+use crate::types::{Asset, Instrument, Order, OrderID, OrderSide, Price, Quantity, TradeEvent, UserID};
+
+// 风控参数，None 表示不限制
+#[derive(Debug, Clone, Default)]
+pub struct RiskLimits {
+    pub max_open_orders: Option<usize>,
+    pub max_order_notional: Option<Decimal>,
+    // 每个资产的最大净持仓 (绝对值)，包含未成交挂单可能带来的持仓
+    pub max_position: HashMap<Asset, Decimal>,
+    // 每日最大成交额 (计价资产)，包含未成交挂单
+    pub max_daily_volume: Option<Decimal>,
+    // 相对参考价的最大偏离比例，例如 0.1 表示 10%
+    pub max_price_deviation: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum RiskRejection {
+    #[error("挂单数超限: 上限 {limit}, 当前 {current}")]
+    MaxOpenOrders { limit: usize, current: usize },
+    #[error("单笔金额超限: 上限 {limit}, 订单 {notional}")]
+    MaxOrderNotional { limit: Decimal, notional: Decimal },
+    #[error("{asset} 持仓超限: 上限 {limit}, 预估 {projected}")]
+    MaxPosition { asset: Asset, limit: Decimal, projected: Decimal },
+    #[error("日成交额超限: 上限 {limit}, 预估 {projected}")]
+    MaxDailyVolume { limit: Decimal, projected: Decimal },
+    #[error("价格 {price} 偏离参考价 {reference} 超过 {max_deviation}")]
+    PriceDeviation { reference: Price, price: Price, max_deviation: Decimal },
+}
+
+// 风控引擎记录的挂单信息
+struct RiskOrder {
+    user_id: UserID,
+    side: OrderSide,
+    price: Price,
+    remaining: Quantity,
+}
+
+#[derive(Default)]
+struct UserRisk {
+    open_orders: usize,
+    daily_volume: Decimal,
+    pending_volume: Decimal, // 挂单剩余部分的成交额
+    positions: HashMap<Asset, Decimal>,
+    pending_long: HashMap<Asset, Decimal>,
+    pending_short: HashMap<Asset, Decimal>,
+}
+
+impl UserRisk {
+    fn add_pending(&mut self, deltas: [(Asset, Decimal); 2], sign: Decimal) {
+        for (asset, delta) in deltas {
+            let bucket = if delta > dec!(0) { &mut self.pending_long } else { &mut self.pending_short };
+            *bucket.entry(asset).or_default() += delta.abs() * sign;
+        }
+    }
+}
+
+// 下单前风控: 位于 OrderBook::match_order (以及 try_freeze) 之前
+// 使用方式: check -> try_freeze -> match_order -> on_order_matched，撤单后调用 on_order_cancelled
+pub struct RiskEngine {
+    instrument: Instrument,
+    limits: RiskLimits,
+    reference_price: Option<Price>,
+    users: HashMap<UserID, UserRisk>,
+    orders: HashMap<OrderID, RiskOrder>,
+}
+
+impl RiskEngine {
+    pub fn new(instrument: Instrument, limits: RiskLimits) -> Self {
+        Self {
+            instrument,
+            limits,
+            reference_price: None,
+            users: HashMap::new(),
+            orders: HashMap::new(),
+        }
+    }
+
+    pub fn set_reference_price(&mut self, price: Price) {
+        self.reference_price = Some(price);
+    }
+
+    // 日切: 清空当日成交额
+    pub fn start_new_day(&mut self) {
+        for user in self.users.values_mut() {
+            user.daily_volume = dec!(0);
+        }
+    }
+
+    // 订单成交后各资产的持仓变化: 买单 +base -quote, 卖单 -base +quote
+    fn deltas(&self, side: &OrderSide, price: Price, quantity: Quantity) -> [(Asset, Decimal); 2] {
+        let notional = price * quantity;
+        match side {
+            OrderSide::Bid => [(self.instrument.base, quantity), (self.instrument.quote, -notional)],
+            OrderSide::Ask => [(self.instrument.base, -quantity), (self.instrument.quote, notional)],
+        }
+    }
+
+    pub fn check(&self, order: &Order) -> Result<(), RiskRejection> {
+        let empty = UserRisk::default();
+        let user = self.users.get(&order.user_id).unwrap_or(&empty);
+        let notional = order.price * order.quantity;
+
+        if let Some(limit) = self.limits.max_open_orders
+            && user.open_orders >= limit
+        {
+            return Err(RiskRejection::MaxOpenOrders { limit, current: user.open_orders });
+        }
+
+        if let Some(limit) = self.limits.max_order_notional
+            && notional > limit
+        {
+            return Err(RiskRejection::MaxOrderNotional { limit, notional });
+        }
+
+        if let (Some(max_deviation), Some(reference)) = (self.limits.max_price_deviation, self.reference_price)
+            && reference > dec!(0)
+            && (order.price - reference).abs() / reference > max_deviation
+        {
+            return Err(RiskRejection::PriceDeviation { reference, price: order.price, max_deviation });
+        }
+
+        for (asset, delta) in self.deltas(&order.side, order.price, order.quantity) {
+            let Some(&limit) = self.limits.max_position.get(&asset) else {
+                continue;
+            };
+            let position = user.positions.get(&asset).copied().unwrap_or_default();
+            // 假设同方向的挂单全部成交后的持仓
+            let projected = if delta > dec!(0) {
+                position + user.pending_long.get(&asset).copied().unwrap_or_default() + delta
+            } else {
+                position - user.pending_short.get(&asset).copied().unwrap_or_default() + delta
+            };
+            if projected.abs() > limit {
+                return Err(RiskRejection::MaxPosition { asset, limit, projected });
+            }
+        }
+
+        if let Some(limit) = self.limits.max_daily_volume {
+            let projected = user.daily_volume + user.pending_volume + notional;
+            if projected > limit {
+                return Err(RiskRejection::MaxDailyVolume { limit, projected });
+            }
+        }
+
+        Ok(())
+    }
+
+    // 撮合完成后更新计数器: order 为撮合前的原始订单
+    pub fn on_order_matched(&mut self, order: &Order, trades: &[TradeEvent]) {
+        let mut remaining = order.quantity;
+
+        for trade in trades.iter().filter(|t| t.taker_order_id == order.id) {
+            remaining -= trade.quantity;
+            self.apply_fill(order.user_id, &order.side, trade.price, trade.quantity);
+
+            let Some(maker) = self.orders.get_mut(&trade.maker_order_id) else {
+                continue;
+            };
+            maker.remaining -= trade.quantity;
+            let maker_done = maker.remaining <= dec!(0);
+            let (maker_user, maker_side, maker_price) = (maker.user_id, maker.side.clone(), maker.price);
+
+            // 挂单按挂单价预估，成交后从预估中移除
+            let deltas = self.deltas(&maker_side, maker_price, trade.quantity);
+            let user = self.users.entry(maker_user).or_default();
+            user.add_pending(deltas, dec!(-1));
+            user.pending_volume -= maker_price * trade.quantity;
+            self.apply_fill(maker_user, &maker_side, trade.price, trade.quantity);
+
+            if maker_done {
+                self.orders.remove(&trade.maker_order_id);
+                self.users.entry(maker_user).or_default().open_orders -= 1;
+            }
+        }
+
+        if remaining > dec!(0) {
+            let deltas = self.deltas(&order.side, order.price, remaining);
+            let user = self.users.entry(order.user_id).or_default();
+            user.open_orders += 1;
+            user.pending_volume += order.price * remaining;
+            user.add_pending(deltas, dec!(1));
+            self.orders.insert(order.id, RiskOrder {
+                user_id: order.user_id,
+                side: order.side.clone(),
+                price: order.price,
+                remaining,
+            });
+        }
+    }
+
+    // 撤单后释放挂单占用的额度，order 为 cancel_order 返回的订单
+    pub fn on_order_cancelled(&mut self, order: &Order) {
+        let Some(risk_order) = self.orders.remove(&order.id) else {
+            return;
+        };
+        let deltas = self.deltas(&risk_order.side, risk_order.price, risk_order.remaining);
+        let user = self.users.entry(risk_order.user_id).or_default();
+        user.open_orders -= 1;
+        user.pending_volume -= risk_order.price * risk_order.remaining;
+        user.add_pending(deltas, dec!(-1));
+    }
+
+    fn apply_fill(&mut self, user_id: UserID, side: &OrderSide, price: Price, quantity: Quantity) {
+        let deltas = self.deltas(side, price, quantity);
+        let user = self.users.entry(user_id).or_default();
+        user.daily_volume += price * quantity;
+        for (asset, delta) in deltas {
+            *user.positions.entry(asset).or_default() += delta;
+        }
+    }
+
+    pub fn open_order_count(&self, user_id: UserID) -> usize {
+        self.users.get(&user_id).map_or(0, |u| u.open_orders)
+    }
+
+    pub fn position(&self, user_id: UserID, asset: Asset) -> Decimal {
+        self.users
+            .get(&user_id)
+            .and_then(|u| u.positions.get(&asset).copied())
+            .unwrap_or_default()
+    }
+
+    pub fn daily_volume(&self, user_id: UserID) -> Decimal {
+        self.users.get(&user_id).map_or(dec!(0), |u| u.daily_volume)
+    }
+}
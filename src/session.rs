@@ -0,0 +1,256 @@
+use std::collections::{HashMap, HashSet};
+use crate::account::AccountManager;
+use crate::engine::{EngineError, OrderBook};
+use crate::error::Error;
+use crate::settlement::Settlement;
+use crate::types::{Order, OrderID, Price, Quantity, TradeEvent, UserID};
+
+pub type SessionID = u64;
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum SessionError {
+    #[error("会话 {session_id} 不存在")]
+    SessionNotFound { session_id: SessionID },
+    // 订单的 user_id 与会话登录用户不一致
+    #[error("会话 {session_id} 属于用户 {expected}, 订单用户为 {actual}")]
+    UserMismatch { session_id: SessionID, expected: UserID, actual: UserID },
+    // 用户被一键停止交易
+    #[error("用户 {user_id} 已触发 Kill Switch")]
+    KillSwitchActive { user_id: UserID },
+}
+
+pub struct Session {
+    pub id: SessionID,
+    pub user_id: UserID,
+    pub cancel_on_disconnect: bool,
+    last_heartbeat: u64,
+    // 通过本会话进入订单簿、仍有资金占用的挂单
+    orders: HashSet<OrderID>,
+}
+
+impl Session {
+    // 仍在跟踪的挂单数量
+    pub fn order_count(&self) -> usize {
+        self.orders.len()
+    }
+}
+
+// 会话层: 位于撮合引擎之上，负责心跳、断线撤单 (Cancel-on-Disconnect) 与用户级 Kill Switch
+// 时间使用逻辑时钟，由调用方通过 advance_clock 推进
+pub struct SessionManager {
+    sessions: HashMap<SessionID, Session>,
+    // 挂单所属会话，用于成交后修剪 Session::orders
+    order_sessions: HashMap<OrderID, SessionID>,
+    killed_users: HashSet<UserID>,
+    heartbeat_timeout: u64,
+    now: u64,
+    next_session_id: SessionID,
+}
+
+impl SessionManager {
+    pub fn new(heartbeat_timeout: u64) -> Self {
+        Self {
+            sessions: HashMap::new(),
+            order_sessions: HashMap::new(),
+            killed_users: HashSet::new(),
+            heartbeat_timeout,
+            now: 0,
+            next_session_id: 1,
+        }
+    }
+
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    pub fn logon(&mut self, user_id: UserID, cancel_on_disconnect: bool) -> SessionID {
+        let id = self.next_session_id;
+        self.next_session_id += 1;
+        self.sessions.insert(id, Session {
+            id,
+            user_id,
+            cancel_on_disconnect,
+            last_heartbeat: self.now,
+            orders: HashSet::new(),
+        });
+        id
+    }
+
+    pub fn session(&self, session_id: SessionID) -> Option<&Session> {
+        self.sessions.get(&session_id)
+    }
+
+    pub fn heartbeat(&mut self, session_id: SessionID) -> Result<(), SessionError> {
+        let session = self.sessions.get_mut(&session_id).ok_or(SessionError::SessionNotFound { session_id })?;
+        session.last_heartbeat = self.now;
+        Ok(())
+    }
+
+    // 检查会话归属与 Kill Switch
+    fn check_user(&self, session_id: SessionID, user_id: UserID) -> Result<(), Error> {
+        let session = self.sessions.get(&session_id).ok_or(SessionError::SessionNotFound { session_id })?;
+        if session.user_id != user_id {
+            return Err(SessionError::UserMismatch { session_id, expected: session.user_id, actual: user_id }.into());
+        }
+        if self.killed_users.contains(&user_id) {
+            return Err(SessionError::KillSwitchActive { user_id }.into());
+        }
+        Ok(())
+    }
+
+    // 通过会话下单: 检查 Kill Switch -> 预演成交并校验结算 -> 冻结资金 -> 撮合 -> 结算
+    // 资金经 Settlement 冻结与结算，价格改善多冻结的部分逐笔退回
+    pub fn place_order(
+        &mut self,
+        session_id: SessionID,
+        order: Order,
+        book: &mut OrderBook,
+        account: &mut AccountManager,
+        settlement: &mut Settlement,
+    ) -> Result<Vec<TradeEvent>, Error> {
+        self.check_user(session_id, order.user_id)?;
+        book.validate_order(&order)?;
+        let preview = book.preview_match(&order);
+        settlement.check(account, &preview, Some(&order))?;
+        settlement.reserve(account, &order)?;
+
+        let order_id = order.id;
+        if let Some(session) = self.sessions.get_mut(&session_id) {
+            session.orders.insert(order_id);
+        }
+        self.order_sessions.insert(order_id, session_id);
+
+        let trades = book.match_order(order);
+        settlement.settle(account, &trades)?;
+        self.on_trades(&trades, settlement);
+        Ok(trades)
+    }
+
+    // 通过会话改单，只能改本会话跟踪的挂单；冻结按新价格与数量调整
+    #[allow(clippy::too_many_arguments)]
+    pub fn amend_order(
+        &mut self,
+        session_id: SessionID,
+        order_id: OrderID,
+        price: Price,
+        quantity: Quantity,
+        book: &mut OrderBook,
+        account: &mut AccountManager,
+        settlement: &mut Settlement,
+    ) -> Result<Vec<TradeEvent>, Error> {
+        if self.order_sessions.get(&order_id) != Some(&session_id) {
+            return Err(EngineError::OrderNotFound { order_id }.into());
+        }
+        let amended = book.validate_amend(order_id, price, quantity)?;
+        self.check_user(session_id, amended.user_id)?;
+        let preview = book.preview_match(&amended);
+        settlement.check(account, &preview, Some(&amended))?;
+        settlement.amend(account, &amended)?;
+
+        let trades = book.amend_order(order_id, price, quantity)?;
+        settlement.settle(account, &trades)?;
+        self.on_trades(&trades, settlement);
+        Ok(trades)
+    }
+
+    // 同步成交: 已在 settlement 中结算完毕 (冻结全部释放) 的订单不再跟踪
+    // 不经过本会话层的撮合需要由调用方结算后转发成交
+    pub fn on_trades(&mut self, trades: &[TradeEvent], settlement: &Settlement) {
+        for trade in trades {
+            for order_id in [trade.maker_order_id, trade.taker_order_id] {
+                if settlement.reserved(order_id).is_none() {
+                    self.untrack(order_id);
+                }
+            }
+        }
+    }
+
+    fn untrack(&mut self, order_id: OrderID) {
+        let Some(session_id) = self.order_sessions.remove(&order_id) else { return };
+        if let Some(session) = self.sessions.get_mut(&session_id) {
+            session.orders.remove(&order_id);
+        }
+    }
+
+    // 主动登出
+    pub fn logout(
+        &mut self,
+        session_id: SessionID,
+        book: &mut OrderBook,
+        account: &mut AccountManager,
+        settlement: &mut Settlement,
+    ) -> Result<Vec<Order>, Error> {
+        self.close_session(session_id, book, account, settlement)
+    }
+
+    // 推进逻辑时钟，心跳超时的会话视为断线
+    pub fn advance_clock(
+        &mut self,
+        now: u64,
+        book: &mut OrderBook,
+        account: &mut AccountManager,
+        settlement: &mut Settlement,
+    ) -> Result<Vec<Order>, Error> {
+        self.now = self.now.max(now);
+
+        let mut expired: Vec<SessionID> = self.sessions
+            .values()
+            .filter(|s| self.now.saturating_sub(s.last_heartbeat) > self.heartbeat_timeout)
+            .map(|s| s.id)
+            .collect();
+        expired.sort_unstable();
+
+        let mut cancelled = Vec::new();
+        for id in expired {
+            cancelled.extend(self.close_session(id, book, account, settlement)?);
+        }
+        Ok(cancelled)
+    }
+
+    // 先撤单解冻，全部成功后才移除会话；解冻失败时会话保留，已处理的挂单不再跟踪
+    fn close_session(
+        &mut self,
+        session_id: SessionID,
+        book: &mut OrderBook,
+        account: &mut AccountManager,
+        settlement: &mut Settlement,
+    ) -> Result<Vec<Order>, Error> {
+        let session = self.sessions.get_mut(&session_id).ok_or(SessionError::SessionNotFound { session_id })?;
+
+        let mut cancelled = Vec::new();
+        if session.cancel_on_disconnect {
+            let mut ids: Vec<OrderID> = session.orders.iter().copied().collect();
+            ids.sort_unstable();
+
+            for id in ids {
+                session.orders.remove(&id);
+                self.order_sessions.remove(&id);
+                // 订单可能已成交或被其他途径撤销，此时撤单返回错误，直接跳过
+                if let Ok(order) = book.cancel_order(id) {
+                    settlement.release(account, id)?;
+                    cancelled.push(order);
+                }
+            }
+        }
+
+        if let Some(session) = self.sessions.remove(&session_id) {
+            for id in &session.orders {
+                self.order_sessions.remove(id);
+            }
+        }
+        Ok(cancelled)
+    }
+
+    // Kill Switch: 阻止用户提交新订单，直到手动复位
+    pub fn activate_kill_switch(&mut self, user_id: UserID) {
+        self.killed_users.insert(user_id);
+    }
+
+    pub fn reset_kill_switch(&mut self, user_id: UserID) {
+        self.killed_users.remove(&user_id);
+    }
+
+    pub fn is_kill_switch_active(&self, user_id: UserID) -> bool {
+        self.killed_users.contains(&user_id)
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use rust_decimal::{Decimal, RoundingStrategy};
+use thiserror::Error;
+use crate::account::{AccountManager, AccountError};
+use crate::registry::{AssetRegistry, RegistryError};
+use crate::snapshot::{Decoder, Encoder};
+use crate::types::{Asset, Instrument, Order, OrderID, OrderSide, Price, Quantity, TradeEvent, TradeID, UserID};
+
+// 扣款向上取整、入账向下取整: 平台永远不会多付，差额作为零头留在清算科目
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    Up,
+    Down,
+}
+
+pub fn round_amount(amount: Decimal, precision: u32, rounding: Rounding) -> Decimal {
+    let strategy = match rounding {
+        Rounding::Up => RoundingStrategy::AwayFromZero,
+        Rounding::Down => RoundingStrategy::ToZero,
+    };
+    amount.round_dp_with_strategy(precision, strategy)
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum SettlementError {
+    #[error("订单 {order_id} 没有通过结算模块冻结资金")]
+    UnknownOrder { order_id: OrderID },
+    #[error(transparent)]
+    Account(#[from] AccountError),
+}
+
+// 单个挂单的资金占用
+#[derive(Debug, Clone)]
+struct Reservation {
+    user_id: UserID,
+    side: OrderSide,
+    price: Price, // 限价，用于计算剩余数量仍需的冻结
+    remaining: Quantity,
+    reserved: Decimal, // 下单时冻结的金额 (已按精度向上取整)
+    spent: Decimal,    // 累计应付的精确金额
+    debited: Decimal,  // 累计实际扣除的金额 = spent 向上取整
+}
+
+impl Reservation {
+    // 按累计金额向上取整计算本次扣款，多次成交的总扣款不会超过下单时的冻结
+    fn debit(&mut self, amount: Decimal, precision: u32) -> Decimal {
+        self.spent += amount;
+        let debit = round_amount(self.spent, precision, Rounding::Up) - self.debited;
+        self.debited += debit;
+        debit
+    }
+
+    // 剩余数量全部按限价成交时仍需保留的冻结，超出部分 (价格改善 + 取整零头) 可以退回
+    fn needed(&self, precision: u32) -> Decimal {
+        let unit = match self.side {
+            OrderSide::Bid => self.price,
+            OrderSide::Ask => Decimal::ONE,
+        };
+        round_amount(self.spent + unit * self.remaining, precision, Rounding::Up) - self.debited
+    }
+}
+
+// 结算对账户的一次操作
+enum Movement {
+    Debit { user_id: UserID, asset: Asset, amount: Decimal, trade_id: TradeID },
+    Credit { user_id: UserID, asset: Asset, amount: Decimal, trade_id: TradeID },
+    Unlock { user_id: UserID, asset: Asset, amount: Decimal, order_id: OrderID },
+}
+
+impl Movement {
+    fn amount(&self) -> Decimal {
+        match *self {
+            Movement::Debit { amount, .. } | Movement::Credit { amount, .. } | Movement::Unlock { amount, .. } => amount,
+        }
+    }
+}
+
+// 推演得到的结算结果: 按顺序执行的账户操作，以及涉及订单的最终占用
+struct Plan {
+    movements: Vec<Movement>,
+    reservations: HashMap<OrderID, Reservation>,
+}
+
+// 单个交易对的结算: 按资产精度冻结、扣款、入账，每笔成交后退回多冻结的部分
+pub struct Settlement {
+    instrument: Instrument,
+    base_precision: u32,
+    quote_precision: u32,
+    reservations: HashMap<OrderID, Reservation>,
+}
+
+impl Settlement {
+    pub fn new(instrument: Instrument, base_precision: u32, quote_precision: u32) -> Self {
+        Self { instrument, base_precision, quote_precision, reservations: HashMap::new() }
+    }
+
+    // 精度取自资产注册表
+    pub fn from_registry(instrument: Instrument, registry: &AssetRegistry) -> Result<Self, RegistryError> {
+        let precision = |asset| registry.get(asset).map(|i| i.precision).ok_or(RegistryError::UnknownAsset { asset });
+        Ok(Self::new(instrument, precision(instrument.base)?, precision(instrument.quote)?))
+    }
+
+    fn precision(&self, side: &OrderSide) -> u32 {
+        match side {
+            OrderSide::Bid => self.quote_precision,
+            OrderSide::Ask => self.base_precision,
+        }
+    }
+
+    // 下单需要冻结的金额，向上取整到冻结资产的精度
+    pub fn required_funds(&self, order: &Order) -> Decimal {
+        round_amount(order.frozen_amount(), self.precision(&order.side), Rounding::Up)
+    }
+
+    // 下单前冻结资金并登记占用
+    pub fn reserve(&mut self, account: &mut AccountManager, order: &Order) -> Result<Decimal, AccountError> {
+        let amount = self.required_funds(order);
+        account.freeze_for_order(order.user_id, self.instrument.frozen_asset(&order.side), amount, order.id)?;
+        self.reservations.insert(order.id, Reservation {
+            user_id: order.user_id,
+            side: order.side.clone(),
+            price: order.price,
+            remaining: order.quantity,
+            reserved: amount,
+            spent: Decimal::ZERO,
+            debited: Decimal::ZERO,
+        });
+        Ok(amount)
+    }
+
+    // 订单剩余的冻结金额
+    pub fn reserved(&self, order_id: OrderID) -> Option<Decimal> {
+        self.reservations.get(&order_id).map(|r| r.reserved - r.debited)
+    }
+
+    // 全部挂单的剩余冻结: (用户, 冻结资产, 金额)，供审计核对
+    pub fn outstanding(&self) -> impl Iterator<Item = (UserID, Asset, Decimal)> + '_ {
+        self.reservations
+            .values()
+            .map(|r| (r.user_id, self.instrument.frozen_asset(&r.side), r.reserved - r.debited))
+    }
+
+    // 结算撮合结果。每笔成交后退回剩余数量用不到的冻结 (价格改善 + 取整零头)，订单完全成交后不再占用。
+    // 先推演全部成交并整体校验账户，任何一笔不通过都不修改状态
+    pub fn settle(&mut self, account: &mut AccountManager, trades: &[TradeEvent]) -> Result<(), SettlementError> {
+        let plan = self.plan(account, trades, None)?;
+        for movement in plan.movements {
+            match movement {
+                Movement::Debit { user_id, asset, amount, trade_id } => account.settle_debit(user_id, asset, amount, trade_id)?,
+                Movement::Credit { user_id, asset, amount, trade_id } => account.settle_credit(user_id, asset, amount, trade_id)?,
+                Movement::Unlock { user_id, asset, amount, order_id } => account.unlock_for_order(user_id, asset, amount, order_id)?,
+            }
+        }
+        for (order_id, r) in plan.reservations {
+            if r.remaining.is_zero() {
+                self.reservations.remove(&order_id);
+            } else {
+                self.reservations.insert(order_id, r);
+            }
+        }
+        Ok(())
+    }
+
+    // 撮合前的预检: trades 为预演出的成交，pending 为即将下单或改单的订单 (按其新的冻结计算)。
+    // 通过后先冻结再撮合，随后的 settle 不会失败
+    pub fn check(&self, account: &AccountManager, trades: &[TradeEvent], pending: Option<&Order>) -> Result<(), SettlementError> {
+        self.plan(account, trades, pending).map(|_| ())
+    }
+
+    // 在占用的副本上推演成交，不修改任何状态
+    fn plan(&self, account: &AccountManager, trades: &[TradeEvent], pending: Option<&Order>) -> Result<Plan, SettlementError> {
+        let mut reservations: HashMap<OrderID, Reservation> = HashMap::new();
+        let mut movements = Vec::new();
+        let mut frozen_out: HashMap<(UserID, Asset), Decimal> = HashMap::new();
+        let mut to_available: HashMap<(UserID, Asset), Decimal> = HashMap::new();
+        if let Some(order) = pending {
+            // 下单 / 改单后的占用；delta 为需要补冻 (正) 或退回 (负) 的金额
+            let required = self.required_funds(order);
+            let delta = required - self.reserved(order.id).unwrap_or_default();
+            let key = (order.user_id, self.instrument.frozen_asset(&order.side));
+            *frozen_out.entry(key).or_default() -= delta;
+            if delta < Decimal::ZERO {
+                *to_available.entry(key).or_default() -= delta;
+            }
+            reservations.insert(order.id, Reservation {
+                user_id: order.user_id,
+                side: order.side.clone(),
+                price: order.price,
+                remaining: order.quantity,
+                reserved: required,
+                spent: Decimal::ZERO,
+                debited: Decimal::ZERO,
+            });
+        }
+        for trade in trades {
+            for order_id in [trade.maker_order_id, trade.taker_order_id] {
+                if let Entry::Vacant(slot) = reservations.entry(order_id) {
+                    let r = self.reservations.get(&order_id).ok_or(SettlementError::UnknownOrder { order_id })?;
+                    slot.insert(r.clone());
+                }
+            }
+            let (buyer_id, seller_id) = match reservations[&trade.maker_order_id].side {
+                OrderSide::Bid => (trade.maker_order_id, trade.taker_order_id),
+                OrderSide::Ask => (trade.taker_order_id, trade.maker_order_id),
+            };
+            let (base, quote) = (self.instrument.base, self.instrument.quote);
+            let notional = trade.price * trade.quantity;
+            let trade_id = trade.trade_id;
+
+            let seller = reservations.get_mut(&seller_id).expect("inserted above");
+            let seller_user = seller.user_id;
+            let base_debit = seller.debit(trade.quantity, self.base_precision);
+            seller.remaining -= trade.quantity;
+
+            let buyer = reservations.get_mut(&buyer_id).expect("inserted above");
+            let buyer_user = buyer.user_id;
+            let quote_debit = buyer.debit(notional, self.quote_precision);
+            buyer.remaining -= trade.quantity;
+
+            let quote_credit = round_amount(notional, self.quote_precision, Rounding::Down);
+            let base_credit = round_amount(trade.quantity, self.base_precision, Rounding::Down);
+            // 卖方: 扣基础资产，收计价资产；买方: 扣计价资产，收基础资产
+            for movement in [
+                Movement::Debit { user_id: seller_user, asset: base, amount: base_debit, trade_id },
+                Movement::Credit { user_id: seller_user, asset: quote, amount: quote_credit, trade_id },
+                Movement::Debit { user_id: buyer_user, asset: quote, amount: quote_debit, trade_id },
+                Movement::Credit { user_id: buyer_user, asset: base, amount: base_credit, trade_id },
+            ] {
+                if !movement.amount().is_zero() {
+                    movements.push(movement);
+                }
+            }
+
+            for order_id in [seller_id, buyer_id] {
+                let r = reservations.get_mut(&order_id).expect("inserted above");
+                let excess = r.reserved - r.debited - r.needed(self.precision(&r.side));
+                if excess > Decimal::ZERO {
+                    r.reserved -= excess;
+                    let asset = self.instrument.frozen_asset(&r.side);
+                    movements.push(Movement::Unlock { user_id: r.user_id, asset, amount: excess, order_id });
+                }
+            }
+        }
+
+        for movement in &movements {
+            match *movement {
+                Movement::Debit { user_id, asset, amount, .. } => *frozen_out.entry((user_id, asset)).or_default() += amount,
+                Movement::Credit { user_id, asset, amount, .. } => *to_available.entry((user_id, asset)).or_default() += amount,
+                Movement::Unlock { user_id, asset, amount, .. } => {
+                    *frozen_out.entry((user_id, asset)).or_default() += amount;
+                    *to_available.entry((user_id, asset)).or_default() += amount;
+                }
+            }
+        }
+        account.check_settlement(&frozen_out, &to_available)?;
+        Ok(Plan { movements, reservations })
+    }
+
+    // 改单: 按新价格与剩余数量重新计算冻结，只补冻或退回差额；资金不足时不做任何修改
+    pub fn amend(&mut self, account: &mut AccountManager, amended: &Order) -> Result<(), SettlementError> {
+        let required = self.required_funds(amended);
+        let asset = self.instrument.frozen_asset(&amended.side);
+        let r = self.reservations.get_mut(&amended.id).ok_or(SettlementError::UnknownOrder { order_id: amended.id })?;
+
+        let leftover = r.reserved - r.debited;
+        if required > leftover {
+            account.freeze_for_order(r.user_id, asset, required - leftover, amended.id)?;
+        } else if required < leftover {
+            account.unlock_for_order(r.user_id, asset, leftover - required, amended.id)?;
+        }
+        r.price = amended.price;
+        r.remaining = amended.quantity;
+        r.reserved = required;
+        r.spent = Decimal::ZERO;
+        r.debited = Decimal::ZERO;
+        Ok(())
+    }
+
+    // 撤单或订单结束: 退回剩余冻结，返回退回的金额
+    pub fn release(&mut self, account: &mut AccountManager, order_id: OrderID) -> Result<Decimal, SettlementError> {
+        let r = self.reservations.remove(&order_id).ok_or(SettlementError::UnknownOrder { order_id })?;
+        let leftover = r.reserved - r.debited;
+        if !leftover.is_zero() {
+            account.unlock_for_order(r.user_id, self.instrument.frozen_asset(&r.side), leftover, order_id)?;
+        }
+        Ok(leftover)
+    }
+
+    pub(crate) fn encode(&self, e: &mut Encoder) {
+        e.u32(self.base_precision);
+        e.u32(self.quote_precision);
+        let mut ids: Vec<&OrderID> = self.reservations.keys().collect();
+        ids.sort_unstable();
+        e.len(ids.len());
+        for id in ids {
+            let r = &self.reservations[id];
+            e.u64(*id);
+            e.u64(r.user_id);
+            e.side(&r.side);
+            for amount in [r.price, r.remaining, r.reserved, r.spent, r.debited] {
+                e.decimal(amount);
+            }
+        }
+    }
+
+    pub(crate) fn decode(d: &mut Decoder, instrument: Instrument) -> Option<Self> {
+        let mut settlement = Settlement::new(instrument, d.u32()?, d.u32()?);
+        for _ in 0..d.len()? {
+            let id = d.u64()?;
+            settlement.reservations.insert(id, Reservation {
+                user_id: d.u64()?,
+                side: d.side()?,
+                price: d.decimal()?,
+                remaining: d.decimal()?,
+                reserved: d.decimal()?,
+                spent: d.decimal()?,
+                debited: d.decimal()?,
+            });
+        }
+        Some(settlement)
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::thread::{self, JoinHandle};
+use thiserror::Error;
+use crate::account::AccountManager;
+use crate::actor::Outcome;
+use crate::engine::OrderBook;
+use crate::error::Error;
+use crate::exchange::Command;
+use crate::settlement::Settlement;
+use crate::types::{Asset, Instrument, Order, OrderID, Price, Quantity, TradeEvent, UserID};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum ShardError {
+    #[error("交易对 {instrument} 不存在")]
+    UnknownInstrument { instrument: Instrument },
+    #[error("撮合引擎已停止")]
+    Stopped,
+}
+
+// 账户服务独占的状态: 全部用户余额 + 各交易对的资金占用
+struct Accounts {
+    manager: AccountManager,
+    settlements: HashMap<Instrument, Settlement>,
+}
+
+impl Accounts {
+    fn settlement(&mut self, instrument: Instrument) -> (&mut Settlement, &mut AccountManager) {
+        let settlement = self.settlements.get_mut(&instrument).expect("交易对在启动时注册");
+        (settlement, &mut self.manager)
+    }
+}
+
+type Job = Box<dyn FnOnce(&mut Accounts) + Send>;
+
+enum AccountRequest {
+    Job(Job),
+    Stop,
+}
+
+// 单线程账户服务: 所有分片的冻结请求在这里串行处理，同一用户的资金不会被多个分片重复占用
+#[derive(Clone)]
+struct AccountService(Sender<AccountRequest>);
+
+impl AccountService {
+    fn call<R, F>(&self, f: F) -> Result<R, ShardError>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut Accounts) -> R + Send + 'static,
+    {
+        let (reply, rx) = mpsc::channel();
+        self.0.send(AccountRequest::Job(Box::new(move |accounts| {
+            let _ = reply.send(f(accounts));
+        }))).map_err(|_| ShardError::Stopped)?;
+        rx.recv().map_err(|_| ShardError::Stopped)
+    }
+}
+
+fn deposit(accounts: &AccountService, user_id: UserID, asset: Asset, amount: Decimal) -> Result<Outcome, ShardError> {
+    accounts.call(move |a| {
+        a.manager.deposit(user_id, asset, amount)?;
+        Ok(Vec::new())
+    })
+}
+
+enum ShardRequest {
+    Command { command: Command, reply: Sender<Outcome> },
+    Stop,
+}
+
+// 分片工作线程: 独占一个交易对的订单簿，资金操作同步请求账户服务
+struct Shard {
+    instrument: Instrument,
+    book: OrderBook,
+    accounts: AccountService,
+}
+
+impl Shard {
+    fn run(mut self, requests: Receiver<ShardRequest>) -> OrderBook {
+        for request in requests {
+            let ShardRequest::Command { command, reply } = request else { break };
+            let Ok(outcome) = self.apply(command) else { break };
+            let _ = reply.send(outcome);
+        }
+        self.book
+    }
+
+    // 外层错误表示账户服务已停止
+    fn apply(&mut self, command: Command) -> Result<Outcome, ShardError> {
+        let instrument = self.instrument;
+        match command {
+            Command::Deposit { user_id, asset, amount } => deposit(&self.accounts, user_id, asset, amount),
+            Command::Place(order) => {
+                if let Err(e) = self.book.validate_order(&order) {
+                    return Ok(Err(e.into()));
+                }
+                // 与 Exchange::apply 相同: 在账户服务里先按预演的成交整体校验结算，通过后才冻结并改动订单簿
+                let preview = self.book.preview_match(&order);
+                let reserve = order.clone();
+                let reserved = self.accounts.call(move |a| -> Result<(), Error> {
+                    let (settlement, manager) = a.settlement(instrument);
+                    settlement.check(manager, &preview, Some(&reserve))?;
+                    settlement.reserve(manager, &reserve)?;
+                    Ok(())
+                })?;
+                if let Err(e) = reserved {
+                    return Ok(Err(e));
+                }
+                let trades = self.book.match_order(order);
+                self.settle(trades)
+            }
+            Command::Cancel { order_id } => {
+                if let Err(e) = self.book.cancel_order(order_id) {
+                    return Ok(Err(e.into()));
+                }
+                self.accounts.call(move |a| {
+                    let (settlement, manager) = a.settlement(instrument);
+                    settlement.release(manager, order_id)?;
+                    Ok(Vec::new())
+                })
+            }
+            Command::Amend { order_id, price, quantity } => self.amend(order_id, price, quantity),
+            Command::Replace { order_id, price, order_qty } => match self.book.replace_quantity(order_id, order_qty) {
+                Ok(quantity) => self.amend(order_id, price, quantity),
+                Err(e) => Ok(Err(e.into())),
+            },
+        }
+    }
+
+    fn amend(&mut self, order_id: OrderID, price: Price, quantity: Quantity) -> Result<Outcome, ShardError> {
+        let instrument = self.instrument;
+        let amended: Order = match self.book.validate_amend(order_id, price, quantity) {
+            Ok(order) => order,
+            Err(e) => return Ok(Err(e.into())),
+        };
+        let preview = self.book.preview_match(&amended);
+        let frozen = self.accounts.call(move |a| -> Result<(), Error> {
+            let (settlement, manager) = a.settlement(instrument);
+            settlement.check(manager, &preview, Some(&amended))?;
+            settlement.amend(manager, &amended)?;
+            Ok(())
+        })?;
+        if let Err(e) = frozen {
+            return Ok(Err(e));
+        }
+        match self.book.amend_order(order_id, price, quantity) {
+            Ok(trades) => self.settle(trades),
+            Err(e) => Ok(Err(e.into())),
+        }
+    }
+
+    fn settle(&self, trades: Vec<TradeEvent>) -> Result<Outcome, ShardError> {
+        let instrument = self.instrument;
+        self.accounts.call(move |a| {
+            let (settlement, manager) = a.settlement(instrument);
+            settlement.settle(manager, &trades).map_err(Error::from)?;
+            Ok(trades)
+        })
+    }
+}
+
+pub struct ShardedEngineBuilder {
+    manager: AccountManager,
+    instruments: Vec<(Instrument, u32, u32)>,
+}
+
+impl ShardedEngineBuilder {
+    pub fn new(manager: AccountManager) -> Self {
+        Self { manager, instruments: Vec::new() }
+    }
+
+    // 每个交易对一个分片
+    pub fn instrument(mut self, instrument: Instrument, base_precision: u32, quote_precision: u32) -> Self {
+        self.instruments.push((instrument, base_precision, quote_precision));
+        self
+    }
+
+    // capacity 为每个分片输入通道的容量
+    pub fn spawn(self, capacity: usize) -> ShardedEngine {
+        let settlements = self.instruments.iter()
+            .map(|&(instrument, base, quote)| (instrument, Settlement::new(instrument, base, quote)))
+            .collect();
+        let mut state = Accounts { manager: self.manager, settlements };
+        let (sender, requests) = mpsc::channel();
+        let account_thread = thread::spawn(move || {
+            for request in requests {
+                let AccountRequest::Job(job) = request else { break };
+                job(&mut state);
+            }
+            state.manager
+        });
+        let accounts = AccountService(sender);
+
+        let mut routes = HashMap::new();
+        let mut shards = Vec::new();
+        for (instrument, _, _) in self.instruments {
+            let (sender, requests) = mpsc::sync_channel(capacity);
+            let shard = Shard { instrument, book: OrderBook::new(), accounts: accounts.clone() };
+            shards.push((instrument, thread::spawn(move || shard.run(requests))));
+            routes.insert(instrument, sender);
+        }
+
+        ShardedEngine {
+            handle: ShardHandle { routes: Arc::new(routes), accounts },
+            shards,
+            account_thread,
+        }
+    }
+}
+
+// 按交易对路由指令的句柄，可以克隆给多个网关线程
+#[derive(Clone)]
+pub struct ShardHandle {
+    routes: Arc<HashMap<Instrument, SyncSender<ShardRequest>>>,
+    accounts: AccountService,
+}
+
+impl ShardHandle {
+    // 把指令交给交易对所在的分片，等待处理结果。充值不经过分片，直接交给账户服务
+    pub fn call(&self, instrument: Instrument, command: Command) -> Result<Outcome, ShardError> {
+        if let Command::Deposit { user_id, asset, amount } = command {
+            return deposit(&self.accounts, user_id, asset, amount);
+        }
+        let route = self.routes.get(&instrument).ok_or(ShardError::UnknownInstrument { instrument })?;
+        let (reply, rx) = mpsc::channel();
+        route.send(ShardRequest::Command { command, reply }).map_err(|_| ShardError::Stopped)?;
+        rx.recv().map_err(|_| ShardError::Stopped)
+    }
+
+    // 在账户服务线程上只读查询余额
+    pub fn query_accounts<R, F>(&self, f: F) -> Result<R, ShardError>
+    where
+        R: Send + 'static,
+        F: FnOnce(&AccountManager) -> R + Send + 'static,
+    {
+        self.accounts.call(move |a| f(&a.manager))
+    }
+
+    pub fn instruments(&self) -> impl Iterator<Item = &Instrument> {
+        self.routes.keys()
+    }
+}
+
+// 按交易对分片的多线程引擎: 每个订单簿固定在一个线程上，余额由单独的账户服务统一管理
+pub struct ShardedEngine {
+    handle: ShardHandle,
+    shards: Vec<(Instrument, JoinHandle<OrderBook>)>,
+    account_thread: JoinHandle<AccountManager>,
+}
+
+impl ShardedEngine {
+    pub fn builder(manager: AccountManager) -> ShardedEngineBuilder {
+        ShardedEngineBuilder::new(manager)
+    }
+
+    pub fn handle(&self) -> ShardHandle {
+        self.handle.clone()
+    }
+
+    // 各分片处理完已提交的指令后停止，再停止账户服务，交回订单簿与账户
+    // 不等待句柄释放: 之后通过仍存活的句柄提交的指令返回 Stopped
+    pub fn shutdown(self) -> (AccountManager, HashMap<Instrument, OrderBook>) {
+        let ShardedEngine { handle, shards, account_thread } = self;
+        for route in handle.routes.values() {
+            let _ = route.send(ShardRequest::Stop);
+        }
+        let books = shards.into_iter()
+            .map(|(instrument, t)| (instrument, t.join().expect("分片线程异常退出")))
+            .collect();
+        let _ = handle.accounts.0.send(AccountRequest::Stop);
+        (account_thread.join().expect("账户服务线程异常退出"), books)
+    }
+}
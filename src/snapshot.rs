@@ -0,0 +1,246 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use rust_decimal::Decimal;
+use thiserror::Error;
+use crate::exchange::Exchange;
+use crate::journal::{self, JournalError, JournalRecord};
+use crate::types::{Asset, Order, OrderSide};
+
+pub const SNAPSHOT_MAGIC: [u8; 8] = *b"MACHSNAP";
+pub const SNAPSHOT_VERSION: u16 = 1;
+
+// 文件头: 魔数 8 + 版本 u16 + 最后序号 u64 + 正文长度 u64 + 正文校验和 u32，均为小端
+const HEADER_LEN: usize = 30;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum SnapshotError {
+    #[error("快照读写失败: {message}")]
+    Io { kind: io::ErrorKind, message: String },
+    #[error("不是快照文件")]
+    BadMagic,
+    #[error("不支持的快照版本 {version}")]
+    UnsupportedVersion { version: u16 },
+    #[error("快照正文不完整或校验和不匹配")]
+    Corrupted,
+    #[error("快照正文无法解析")]
+    Malformed,
+    #[error(transparent)]
+    Journal(#[from] JournalError),
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e: io::Error) -> Self {
+        SnapshotError::Io { kind: e.kind(), message: e.to_string() }
+    }
+}
+
+// 快照文件头信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotInfo {
+    pub version: u16,
+    pub last_seq: u64,
+}
+
+// 写出某一时刻的完整状态，文件头记录最后一条已处理指令的序号
+pub fn write_snapshot<W: Write>(exchange: &Exchange, mut writer: W) -> Result<(), SnapshotError> {
+    let mut body = Encoder::default();
+    exchange.encode(&mut body);
+    let body = body.buf;
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(&SNAPSHOT_MAGIC);
+    header.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+    header.extend_from_slice(&exchange.last_seq().to_le_bytes());
+    header.extend_from_slice(&(body.len() as u64).to_le_bytes());
+    header.extend_from_slice(&crc32fast::hash(&body).to_le_bytes());
+
+    writer.write_all(&header)?;
+    writer.write_all(&body)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn read_snapshot<R: Read>(mut reader: R) -> Result<Exchange, SnapshotError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    let (info, body) = check(&bytes)?;
+
+    let mut decoder = Decoder::new(body);
+    let exchange = Exchange::decode(&mut decoder, info.last_seq).ok_or(SnapshotError::Malformed)?;
+    if !decoder.is_empty() {
+        return Err(SnapshotError::Malformed);
+    }
+    Ok(exchange)
+}
+
+// 只校验文件头与校验和，不重建状态
+pub fn verify_snapshot<R: Read>(mut reader: R) -> Result<SnapshotInfo, SnapshotError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    Ok(check(&bytes)?.0)
+}
+
+// 先写临时文件再改名，崩溃时不会留下半个快照覆盖旧快照
+pub fn save_snapshot(exchange: &Exchange, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+    let path = path.as_ref();
+    let tmp = path.with_extension("tmp");
+    let mut file = File::create(&tmp)?;
+    write_snapshot(exchange, &mut file)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+pub fn load_snapshot(path: impl AsRef<Path>) -> Result<Exchange, SnapshotError> {
+    read_snapshot(File::open(path)?)
+}
+
+// 故障恢复: 加载快照，再重放日志中快照之后的部分
+pub fn recover(path: impl AsRef<Path>, records: &[JournalRecord]) -> Result<Exchange, SnapshotError> {
+    let mut exchange = load_snapshot(path)?;
+    journal::replay(records, &mut exchange)?;
+    Ok(exchange)
+}
+
+fn check(bytes: &[u8]) -> Result<(SnapshotInfo, &[u8]), SnapshotError> {
+    if bytes.len() < HEADER_LEN || bytes[0..8] != SNAPSHOT_MAGIC {
+        return Err(SnapshotError::BadMagic);
+    }
+    let version = u16::from_le_bytes(bytes[8..10].try_into().unwrap());
+    if version != SNAPSHOT_VERSION {
+        return Err(SnapshotError::UnsupportedVersion { version });
+    }
+    let last_seq = u64::from_le_bytes(bytes[10..18].try_into().unwrap());
+    let len = u64::from_le_bytes(bytes[18..26].try_into().unwrap());
+    let crc = u32::from_le_bytes(bytes[26..30].try_into().unwrap());
+
+    let body = &bytes[HEADER_LEN..];
+    if body.len() as u64 != len || crc32fast::hash(body) != crc {
+        return Err(SnapshotError::Corrupted);
+    }
+    Ok((SnapshotInfo { version, last_seq }, body))
+}
+
+// 正文编码: 定长小端整数，Decimal 16 字节，集合先写长度
+#[derive(Default)]
+pub(crate) struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    pub(crate) fn u8(&mut self, v: u8) {
+        self.buf.push(v);
+    }
+
+    pub(crate) fn u32(&mut self, v: u32) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub(crate) fn u64(&mut self, v: u64) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub(crate) fn len(&mut self, v: usize) {
+        self.u64(v as u64);
+    }
+
+    pub(crate) fn decimal(&mut self, v: Decimal) {
+        self.buf.extend_from_slice(&v.serialize());
+    }
+
+    pub(crate) fn asset(&mut self, v: Asset) {
+        self.buf.extend_from_slice(v.as_bytes());
+    }
+
+    pub(crate) fn str(&mut self, v: &str) {
+        self.len(v.len());
+        self.buf.extend_from_slice(v.as_bytes());
+    }
+
+    pub(crate) fn side(&mut self, v: &OrderSide) {
+        self.u8(match v {
+            OrderSide::Bid => 0,
+            OrderSide::Ask => 1,
+        });
+    }
+
+    pub(crate) fn order(&mut self, v: &Order) {
+        self.u64(v.id);
+        self.u64(v.user_id);
+        self.side(&v.side);
+        self.decimal(v.price);
+        self.decimal(v.quantity);
+    }
+}
+
+pub(crate) struct Decoder<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    pub(crate) fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (head, tail) = self.buf.split_first_chunk::<N>()?;
+        self.buf = tail;
+        Some(*head)
+    }
+
+    pub(crate) fn u8(&mut self) -> Option<u8> {
+        self.take::<1>().map(|b| b[0])
+    }
+
+    pub(crate) fn u32(&mut self) -> Option<u32> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    pub(crate) fn u64(&mut self) -> Option<u64> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    // 长度不可能超过剩余字节数，防止损坏数据触发超大分配
+    pub(crate) fn len(&mut self) -> Option<usize> {
+        let len = usize::try_from(self.u64()?).ok()?;
+        (len <= self.buf.len()).then_some(len)
+    }
+
+    pub(crate) fn decimal(&mut self) -> Option<Decimal> {
+        self.take().map(Decimal::deserialize)
+    }
+
+    pub(crate) fn asset(&mut self) -> Option<Asset> {
+        self.take().map(Asset::from_bytes)
+    }
+
+    pub(crate) fn string(&mut self) -> Option<String> {
+        let len = self.len()?;
+        let (head, tail) = self.buf.split_at(len);
+        self.buf = tail;
+        String::from_utf8(head.to_vec()).ok()
+    }
+
+    pub(crate) fn side(&mut self) -> Option<OrderSide> {
+        match self.u8()? {
+            0 => Some(OrderSide::Bid),
+            1 => Some(OrderSide::Ask),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn order(&mut self) -> Option<Order> {
+        Some(Order {
+            id: self.u64()?,
+            user_id: self.u64()?,
+            side: self.side()?,
+            price: self.decimal()?,
+            quantity: self.decimal()?,
+        })
+    }
+}
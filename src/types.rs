@@ -1,18 +1,25 @@
 use std::fmt;
 use rust_decimal::Decimal;
+use crate::registry::{RegistryError, MAX_SYMBOL_LEN};
 
 pub type Price = Decimal;
 pub type Quantity = Decimal;
 pub type OrderID = u64;
+pub type TradeID = u64;
+pub type WithdrawalID = u64;
+pub type DepositID = u64;
+pub type TransferID = u64;
 
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OrderSide {
     Bid, //买单
     Ask, //卖单
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Order {
     pub id: OrderID,
     pub price: Price,
@@ -21,8 +28,44 @@ pub struct Order {
     pub user_id: UserID,
 }
 
+impl Order {
+    // 挂单需要冻结的数量: 买单冻结 价格*数量 的计价资产, 卖单冻结 数量 的基础资产
+    pub fn frozen_amount(&self) -> Decimal {
+        match self.side {
+            OrderSide::Bid => self.price * self.quantity,
+            OrderSide::Ask => self.quantity,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OrderStatus {
+    Open,            // 挂单中，尚未成交
+    PartiallyFilled, // 部分成交，剩余部分仍在订单簿上
+    Filled,          // 完全成交
+    Cancelled,       // 已撤销 (可能有部分成交)
+}
+
+// 订单状态查询结果
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrderReport {
+    pub order_id: OrderID,
+    pub user_id: UserID,
+    pub side: OrderSide,
+    pub price: Price,
+    pub status: OrderStatus,
+    pub original_quantity: Quantity,
+    pub filled_quantity: Quantity,
+    pub remaining_quantity: Quantity,
+    pub average_price: Option<Price>, // 没有成交时为 None
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TradeEvent{
+    pub trade_id: TradeID,
     pub maker_order_id: OrderID,
     pub maker_user_id: UserID,
     pub taker_order_id: OrderID,
@@ -31,22 +74,54 @@ pub struct TradeEvent{
     pub quantity: Quantity,
 }
 
-#[derive(Default,Clone,Copy,PartialEq, Eq, Hash)]
+#[derive(Default,Clone,Copy,PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[repr(C)]
 pub struct Asset([u8; 8]);
 
 impl Asset {
+    // 超过 8 字节会被截断 (不会切断多字节字符)，只用于可信的常量符号；
+    // 外部输入使用 try_new 或 registry::parse_symbol
     pub fn new(s: &str) -> Self {
         let bytes = s.as_bytes();
         let mut arr = [0u8; 8];
 
-        let len = bytes.len().min(8);
+        let mut len = bytes.len().min(8);
+        while !s.is_char_boundary(len) {
+            len -= 1;
+        }
 
         arr[..len].copy_from_slice(&bytes[..len]);
 
         Asset(arr)
     }
 
+    // 超过 8 字节时返回错误，避免不同符号截断后撞成同一个资产；
+    // 空符号和含空白、控制字符 (包括 NUL) 或非 ASCII 字符的符号同样拒绝
+    pub fn try_new(s: &str) -> Result<Self, RegistryError> {
+        if s.len() > MAX_SYMBOL_LEN {
+            return Err(RegistryError::SymbolTooLong { symbol: s.to_string() });
+        }
+        if s.is_empty() || !s.bytes().all(|b| b.is_ascii_graphic()) {
+            return Err(RegistryError::InvalidSymbol { symbol: s.to_string() });
+        }
+        Ok(Asset::new(s))
+    }
+
+    // 定长 8 字节的原始布局，用于二进制编码
+    pub fn as_bytes(&self) -> &[u8; 8] {
+        &self.0
+    }
+
+    pub fn from_bytes(bytes: [u8; 8]) -> Self {
+        Asset(bytes)
+    }
+
+    // 零拷贝: 直接把缓冲区中的 8 字节视为 Asset
+    pub fn from_bytes_ref(bytes: &[u8; 8]) -> &Asset {
+        // SAFETY: Asset 是只有一个 [u8; 8] 字段的 #[repr(C)] 结构体，大小与对齐 (1) 都和 [u8; 8] 相同
+        unsafe { &*(bytes as *const [u8; 8] as *const Asset) }
+    }
+
     pub fn as_str(&self) -> &str {
         let len = self.0.iter().position(|&x| x == 0).unwrap_or(8);
         std::str::from_utf8(&self.0[..len]).unwrap_or("???")
@@ -64,6 +139,22 @@ impl fmt::Display for Asset {
     }
 }
 
+// 序列化为符号字符串；反序列化时按 try_new 校验，拒绝超长或非法的符号，而不是静默截断
+#[cfg(feature = "serde")]
+impl serde::Serialize for Asset {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Asset {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Asset::try_new(&s).map_err(serde::de::Error::custom)
+    }
+}
+
 impl From<&str> for Asset {
     fn from(s: &str) -> Self {
         Asset::new(s)
@@ -71,3 +162,31 @@ impl From<&str> for Asset {
 }
 
 pub type UserID = u64;
+
+// 交易对, 例如 BTC/USDT: base 为基础资产, quote 为计价资产
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Instrument {
+    pub base: Asset,
+    pub quote: Asset,
+}
+
+impl Instrument {
+    pub fn new(base: Asset, quote: Asset) -> Self {
+        Instrument { base, quote }
+    }
+
+    // 下单时需要冻结的资产
+    pub fn frozen_asset(&self, side: &OrderSide) -> Asset {
+        match side {
+            OrderSide::Bid => self.quote,
+            OrderSide::Ask => self.base,
+        }
+    }
+}
+
+impl fmt::Display for Instrument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.base, self.quote)
+    }
+}
@@ -0,0 +1,252 @@
+use std::io::{self, Read, Write};
+
+// RFC 6455 服务端的最小实现: 握手与帧编解码，不支持扩展 (压缩等)
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+const MAX_REQUEST: usize = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Opcode {
+    Continuation,
+    Text,
+    Binary,
+    Close,
+    Ping,
+    Pong,
+}
+
+impl Opcode {
+    fn from_bits(bits: u8) -> Option<Self> {
+        Some(match bits {
+            0x0 => Opcode::Continuation,
+            0x1 => Opcode::Text,
+            0x2 => Opcode::Binary,
+            0x8 => Opcode::Close,
+            0x9 => Opcode::Ping,
+            0xA => Opcode::Pong,
+            _ => return None,
+        })
+    }
+
+    fn bits(self) -> u8 {
+        match self {
+            Opcode::Continuation => 0x0,
+            Opcode::Text => 0x1,
+            Opcode::Binary => 0x2,
+            Opcode::Close => 0x8,
+            Opcode::Ping => 0x9,
+            Opcode::Pong => 0xA,
+        }
+    }
+
+    pub fn is_control(self) -> bool {
+        matches!(self, Opcode::Close | Opcode::Ping | Opcode::Pong)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub fin: bool,
+    pub opcode: Opcode,
+    pub payload: Vec<u8>,
+}
+
+impl Frame {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self { fin: true, opcode: Opcode::Text, payload: text.into().into_bytes() }
+    }
+
+    // code 为 None 时不带状态码
+    pub fn close(code: Option<u16>) -> Self {
+        let payload = code.map(|c| c.to_be_bytes().to_vec()).unwrap_or_default();
+        Self { fin: true, opcode: Opcode::Close, payload }
+    }
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason)
+}
+
+// Sec-WebSocket-Accept = base64(sha1(key + GUID))
+pub fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{key}{GUID}").as_bytes()))
+}
+
+// 读取 HTTP Upgrade 请求并应答 101，返回请求路径。请求不合法时应答 400 并返回错误
+pub fn accept<S: Read + Write>(stream: &mut S) -> io::Result<String> {
+    // 逐字节读取，不会多读走客户端随后发来的帧
+    let mut request = Vec::new();
+    let mut byte = [0u8; 1];
+    while !request.ends_with(b"\r\n\r\n") {
+        if request.len() >= MAX_REQUEST {
+            return Err(invalid("握手请求过长"));
+        }
+        stream.read_exact(&mut byte)?;
+        request.push(byte[0]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let mut lines = request.split("\r\n");
+    let path = match lines.next().map(|l| l.split(' ').collect::<Vec<_>>()).as_deref() {
+        Some(["GET", path, _]) => path.to_string(),
+        _ => String::new(),
+    };
+
+    let mut key = None;
+    let mut upgrade = false;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else { continue };
+        let value = value.trim();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "sec-websocket-key" => key = Some(value.to_string()),
+            "upgrade" => upgrade = value.eq_ignore_ascii_case("websocket"),
+            _ => {}
+        }
+    }
+    match key {
+        Some(key) if upgrade && !path.is_empty() => {
+            write!(
+                stream,
+                "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                accept_key(&key)
+            )?;
+            Ok(path)
+        }
+        _ => {
+            stream.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n")?;
+            Err(invalid("不是 WebSocket 握手请求"))
+        }
+    }
+}
+
+// 读取一帧并去掉掩码。require_mask 为 true 时拒绝未加掩码的帧 (客户端发往服务端的帧必须加掩码)
+pub fn read_frame<R: Read>(reader: &mut R, max_payload: usize, require_mask: bool) -> io::Result<Frame> {
+    let mut head = [0u8; 2];
+    reader.read_exact(&mut head)?;
+    if head[0] & 0x70 != 0 {
+        return Err(invalid("未协商扩展却设置了 RSV 位"));
+    }
+    let fin = head[0] & 0x80 != 0;
+    let opcode = Opcode::from_bits(head[0] & 0x0F).ok_or_else(|| invalid("未知的 opcode"))?;
+    let masked = head[1] & 0x80 != 0;
+    if require_mask && !masked {
+        return Err(invalid("客户端帧未加掩码"));
+    }
+
+    let len = match head[1] & 0x7F {
+        126 => {
+            let mut ext = [0u8; 2];
+            reader.read_exact(&mut ext)?;
+            u64::from(u16::from_be_bytes(ext))
+        }
+        127 => {
+            let mut ext = [0u8; 8];
+            reader.read_exact(&mut ext)?;
+            u64::from_be_bytes(ext)
+        }
+        n => u64::from(n),
+    };
+    if opcode.is_control() && (!fin || len > 125) {
+        return Err(invalid("控制帧不能分片且不超过 125 字节"));
+    }
+    if len > max_payload as u64 {
+        return Err(invalid("帧超过长度上限"));
+    }
+
+    let mut mask = [0u8; 4];
+    if masked {
+        reader.read_exact(&mut mask)?;
+    }
+    let mut payload = vec![0u8; len as usize];
+    reader.read_exact(&mut payload)?;
+    if masked {
+        for (i, b) in payload.iter_mut().enumerate() {
+            *b ^= mask[i % 4];
+        }
+    }
+    Ok(Frame { fin, opcode, payload })
+}
+
+// 服务端发出的帧不加掩码 (mask 为 None)，客户端必须提供掩码
+pub fn write_frame<W: Write>(writer: &mut W, frame: &Frame, mask: Option<[u8; 4]>) -> io::Result<()> {
+    let mut buf = Vec::with_capacity(frame.payload.len() + 14);
+    buf.push(u8::from(frame.fin) << 7 | frame.opcode.bits());
+    let mask_bit = if mask.is_some() { 0x80 } else { 0 };
+    match frame.payload.len() {
+        n if n < 126 => buf.push(mask_bit | n as u8),
+        n if n <= u16::MAX as usize => {
+            buf.push(mask_bit | 126);
+            buf.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        n => {
+            buf.push(mask_bit | 127);
+            buf.extend_from_slice(&(n as u64).to_be_bytes());
+        }
+    }
+    match mask {
+        Some(mask) => {
+            buf.extend_from_slice(&mask);
+            buf.extend(frame.payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        }
+        None => buf.extend_from_slice(&frame.payload),
+    }
+    writer.write_all(&buf)
+}
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, &wi) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..20 => ((b & c) | (!b & d), 0x5A827999),
+                20..40 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..60 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(wi);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (x, y) in h.iter_mut().zip([a, b, c, d, e]) {
+            *x = x.wrapping_add(y);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (chunk, word) in digest.chunks_mut(4).zip(h) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
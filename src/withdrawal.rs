@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use rust_decimal::Decimal;
+use thiserror::Error;
+use crate::account::{AccountManager, AccountError};
+use crate::types::{Asset, UserID, WithdrawalID};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WithdrawalStatus {
+    Pending,   // 已冻结，等待审核
+    Approved,  // 审核通过，等待链上 / 银行出款
+    Completed, // 出款完成，资金已扣除
+    Rejected,  // 已拒绝，资金已退回可用
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Withdrawal {
+    pub id: WithdrawalID,
+    pub user_id: UserID,
+    pub asset: Asset,
+    pub amount: Decimal, // 用户申请金额，包含手续费
+    pub fee: Decimal,
+    pub status: WithdrawalStatus,
+    pub idempotency_key: String,
+}
+
+// 每个资产的提现规则
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WithdrawalPolicy {
+    pub min_amount: Decimal,
+    pub fee: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum WithdrawalError {
+    #[error("提现单 {id} 不存在")]
+    NotFound { id: WithdrawalID },
+    #[error("提现单 {id} 当前状态 {status:?} 不允许该操作")]
+    InvalidState { id: WithdrawalID, status: WithdrawalStatus },
+    #[error("{asset} 提现金额 {amount} 低于最小值 {minimum}")]
+    BelowMinimum { asset: Asset, amount: Decimal, minimum: Decimal },
+    #[error("幂等键 {key} 已用于另一笔提现")]
+    IdempotencyConflict { key: String },
+    #[error(transparent)]
+    Account(#[from] AccountError),
+}
+
+// 提现流程: 申请(冻结) -> 审核通过 -> 完成(扣除)，或在完成前拒绝(退回)
+#[derive(Default)]
+pub struct WithdrawalManager {
+    policies: HashMap<Asset, WithdrawalPolicy>,
+    withdrawals: HashMap<WithdrawalID, Withdrawal>,
+    // 幂等键按用户隔离，不同用户可以使用相同的键
+    by_key: HashMap<(UserID, String), WithdrawalID>,
+    next_id: WithdrawalID,
+}
+
+impl WithdrawalManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_policy(&mut self, asset: Asset, policy: WithdrawalPolicy) {
+        self.policies.insert(asset, policy);
+    }
+
+    pub fn get(&self, id: WithdrawalID) -> Option<&Withdrawal> {
+        self.withdrawals.get(&id)
+    }
+
+    // 申请提现: 同一用户以同一幂等键重复提交返回原提现单，不会重复冻结
+    pub fn request(
+        &mut self,
+        account: &mut AccountManager,
+        user_id: UserID,
+        asset: Asset,
+        amount: Decimal,
+        idempotency_key: &str,
+    ) -> Result<WithdrawalID, WithdrawalError> {
+        let key = (user_id, idempotency_key.to_string());
+        if let Some(id) = self.by_key.get(&key) {
+            let w = &self.withdrawals[id];
+            if w.asset == asset && w.amount == amount {
+                return Ok(*id);
+            }
+            return Err(WithdrawalError::IdempotencyConflict { key: idempotency_key.to_string() });
+        }
+
+        let policy = self.policies.get(&asset).copied().unwrap_or_default();
+        // 到账金额必须为正
+        let minimum = policy.min_amount.max(policy.fee);
+        if amount < minimum || amount <= policy.fee {
+            return Err(WithdrawalError::BelowMinimum { asset, amount, minimum });
+        }
+
+        let id = self.next_id + 1;
+        account.hold_withdrawal(user_id, asset, amount, id)?;
+        self.next_id = id;
+
+        self.by_key.insert(key, id);
+        self.withdrawals.insert(id, Withdrawal {
+            id,
+            user_id,
+            asset,
+            amount,
+            fee: policy.fee,
+            status: WithdrawalStatus::Pending,
+            idempotency_key: idempotency_key.to_string(),
+        });
+        Ok(id)
+    }
+
+    pub fn approve(&mut self, id: WithdrawalID) -> Result<(), WithdrawalError> {
+        let w = self.withdrawal_in(id, &[WithdrawalStatus::Pending])?;
+        w.status = WithdrawalStatus::Approved;
+        Ok(())
+    }
+
+    // 拒绝: 资金退回可用余额
+    pub fn reject(&mut self, account: &mut AccountManager, id: WithdrawalID) -> Result<(), WithdrawalError> {
+        let w = self.withdrawal_in(id, &[WithdrawalStatus::Pending, WithdrawalStatus::Approved])?;
+        account.release_withdrawal(w.user_id, w.asset, w.amount, id)?;
+        w.status = WithdrawalStatus::Rejected;
+        Ok(())
+    }
+
+    // 完成: 资金流出系统，返回实际到账金额 (扣除手续费)
+    pub fn complete(&mut self, account: &mut AccountManager, id: WithdrawalID) -> Result<Decimal, WithdrawalError> {
+        let w = self.withdrawal_in(id, &[WithdrawalStatus::Approved])?;
+        account.complete_withdrawal(w.user_id, w.asset, w.amount, w.fee, id)?;
+        w.status = WithdrawalStatus::Completed;
+        Ok(w.amount - w.fee)
+    }
+
+    fn withdrawal_in(&mut self, id: WithdrawalID, allowed: &[WithdrawalStatus]) -> Result<&mut Withdrawal, WithdrawalError> {
+        let w = self.withdrawals.get_mut(&id).ok_or(WithdrawalError::NotFound { id })?;
+        if !allowed.contains(&w.status) {
+            return Err(WithdrawalError::InvalidState { id, status: w.status });
+        }
+        Ok(w)
+    }
+}
@@ -0,0 +1,132 @@
+// tests/actor_test.rs
+
+use std::thread;
+use rust_decimal_macros::dec;
+use mach_rs::{Asset, Command, Engine, EngineOutput, EngineStopped, Exchange, Instrument, Journal, Order, OrderSide};
+use mach_rs::journal::{read_journal, replay};
+use mach_rs::observer::OrderEvent;
+
+fn pair() -> Instrument {
+    Instrument::new(Asset::from("BTC"), Asset::from("USDT"))
+}
+
+fn place(id: u64, user_id: u64, price: rust_decimal::Decimal, side: OrderSide) -> Command {
+    Command::Place(Order { id, user_id, price, quantity: dec!(1), side })
+}
+
+#[test]
+fn test_concurrent_gateways_are_serialized() {
+    let (engine, outputs) = Engine::spawn(Exchange::new(pair(), 8, 2), 4);
+    let (btc, usdt) = (Asset::from("BTC"), Asset::from("USDT"));
+
+    let gateways: Vec<_> = (0..4u64)
+        .map(|g| {
+            let handle = engine.handle();
+            thread::spawn(move || {
+                for i in 0..25u64 {
+                    let (user_id, id) = (g + 1, g * 100 + i + 1);
+                    handle.call(Command::Deposit { user_id, asset: btc, amount: dec!(1) }).unwrap().unwrap();
+                    handle.call(Command::Deposit { user_id, asset: usdt, amount: dec!(100) }).unwrap().unwrap();
+                    let side = if g % 2 == 0 { OrderSide::Ask } else { OrderSide::Bid };
+                    handle.submit(place(id, user_id, dec!(100), side)).unwrap();
+                }
+            })
+        })
+        .collect();
+    for g in gateways {
+        g.join().unwrap();
+    }
+
+    let open = engine.handle().query(|ex| (1..=4).map(|u| ex.book().user_order_count(u)).sum::<usize>()).unwrap();
+    let exchange = engine.shutdown();
+    assert_eq!(exchange.last_seq(), 300);
+    assert_eq!(open, 0, "买卖各 50 张同价单应全部成交");
+
+    // 序号连续，且每条指令的事件都在它的 Processed 之前发布
+    let mut seqs = Vec::new();
+    let mut trades = 0;
+    let mut pending_trades = 0;
+    for output in outputs.try_iter() {
+        match output {
+            EngineOutput::Order(OrderEvent::Trade(_)) => pending_trades += 1,
+            EngineOutput::Processed { seq, outcome, .. } => {
+                assert_eq!(outcome.unwrap().len(), pending_trades);
+                trades += pending_trades;
+                pending_trades = 0;
+                seqs.push(seq);
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(seqs, (1..=300).collect::<Vec<_>>());
+    assert_eq!(trades, 50);
+}
+
+#[test]
+fn test_journaled_engine_can_be_replayed() {
+    let path = std::env::temp_dir().join(format!("mach_actor_{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let (journal, _) = Journal::open(&path).unwrap();
+    let (engine, _outputs) = Engine::spawn_journaled(Exchange::new(pair(), 8, 2), journal, 16);
+    let handle = engine.handle();
+    handle.submit(Command::Deposit { user_id: 1, asset: Asset::from("BTC"), amount: dec!(2) }).unwrap();
+    handle.submit(place(1, 1, dec!(100), OrderSide::Ask)).unwrap();
+    let rejected = handle.call(place(2, 2, dec!(100), OrderSide::Bid)).unwrap();
+    assert!(rejected.is_err(), "买方没有资金");
+    let live = engine.shutdown();
+
+    let records = read_journal(std::fs::File::open(&path).unwrap()).unwrap();
+    let mut rebuilt = Exchange::new(pair(), 8, 2);
+    assert_eq!(replay(&records, &mut rebuilt).unwrap(), 3);
+    assert_eq!(rebuilt.last_seq(), live.last_seq());
+    assert_eq!(rebuilt.account().balances_of(1), live.account().balances_of(1));
+    assert!(rebuilt.book().order_status(1).is_some());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_invalid_command_is_rejected_without_halting() {
+    let path = std::env::temp_dir().join(format!("mach_actor_reject_{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let (journal, _) = Journal::open(&path).unwrap();
+    let (engine, outputs) = Engine::spawn_journaled(Exchange::new(pair(), 8, 2), journal, 16);
+    let handle = engine.handle();
+    let rejected = handle.call(Command::Deposit { user_id: 1, asset: Asset::from("BTC"), amount: dec!(0) }).unwrap();
+    assert!(rejected.is_err());
+    assert!(handle.call(Command::Deposit { user_id: 1, asset: Asset::from("BTC"), amount: dec!(1) }).unwrap().is_ok());
+    let live = engine.shutdown();
+    assert_eq!(live.last_seq(), 1);
+
+    let outputs: Vec<_> = outputs.try_iter().collect();
+    assert!(matches!(outputs.first(), Some(EngineOutput::Rejected { .. })));
+    assert!(outputs.iter().any(|o| matches!(o, EngineOutput::Processed { seq: 1, .. })));
+    assert!(!outputs.iter().any(|o| matches!(o, EngineOutput::Halted { .. })));
+    assert_eq!(read_journal(std::fs::File::open(&path).unwrap()).unwrap().len(), 1);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_handles_fail_after_shutdown() {
+    let (engine, _outputs) = Engine::spawn(Exchange::new(pair(), 8, 2), 1);
+    let handle = engine.handle();
+    engine.shutdown();
+    assert_eq!(handle.submit(Command::Cancel { order_id: 1 }), Err(EngineStopped));
+    assert_eq!(handle.query(|ex| ex.last_seq()), Err(EngineStopped));
+}
+
+#[test]
+fn test_output_overflow_disconnects_instead_of_blocking() {
+    let (engine, outputs) = Engine::spawn_with_output_capacity(Exchange::new(pair(), 8, 2), 4, 8);
+    let handle = engine.handle();
+
+    // 没人读输出，引擎也不会阻塞
+    for user_id in 1..=20 {
+        handle.call(Command::Deposit { user_id, asset: Asset::from("BTC"), amount: dec!(1) }).unwrap().unwrap();
+    }
+    assert_eq!(handle.query(|ex| ex.last_seq()).unwrap(), 20);
+
+    // 只保留写满前的输出，随后通道断开
+    assert_eq!(outputs.try_iter().count(), 8);
+    assert!(outputs.recv().is_err());
+    engine.shutdown();
+}
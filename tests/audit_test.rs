@@ -0,0 +1,93 @@
+// tests/audit_test.rs
+
+use rust_decimal_macros::dec;
+use mach_rs::{AccountManager, OrderBook, Order, OrderSide, Asset, Instrument, Auditor, AuditError, Settlement, Exchange, Command};
+
+fn setup() -> (AccountManager, OrderBook, Auditor, Settlement, Instrument) {
+    let btc = Asset::from("BTC");
+    let usdt = Asset::from("USDT");
+    let pair = Instrument::new(btc, usdt);
+    let mut account = AccountManager::new();
+    let mut auditor = Auditor::new();
+
+    account.deposit(1, btc, dec!(5)).unwrap();
+    auditor.on_deposit(btc, dec!(5));
+    account.deposit(2, usdt, dec!(1000)).unwrap();
+    auditor.on_deposit(usdt, dec!(1000));
+
+    (account, OrderBook::new(), auditor, Settlement::new(pair, 8, 2), pair)
+}
+
+#[test]
+fn test_correct_settlement_passes_audit() {
+    let (mut account, mut book, auditor, mut settlement, pair) = setup();
+
+    let ask = Order { id: 1, user_id: 1, price: dec!(100), quantity: dec!(2), side: OrderSide::Ask };
+    settlement.reserve(&mut account, &ask).unwrap();
+    book.match_order(ask);
+    assert!(auditor.audit(&account, &[&settlement]).is_ok());
+
+    let bid = Order { id: 2, user_id: 2, price: dec!(100), quantity: dec!(1), side: OrderSide::Bid };
+    settlement.reserve(&mut account, &bid).unwrap();
+    let trades = book.match_order(bid);
+    settlement.settle(&mut account, &trades).unwrap();
+    for t in trades {
+        account.charge_fee(t.taker_user_id, pair.base, dec!(0.001), t.trade_id).unwrap();
+    }
+
+    // 手续费科目计入总量，剩余 1 BTC 仍挂在订单簿上
+    assert!(auditor.audit(&account, &[&settlement]).is_ok());
+}
+
+#[test]
+fn test_minting_is_detected() {
+    let (mut account, _book, auditor, _, pair) = setup();
+
+    // 结算 bug: 没有对应扣款就给用户入账
+    account.deposit(3, pair.quote, dec!(1)).unwrap();
+
+    assert_eq!(
+        auditor.check_conservation(&account),
+        Err(AuditError::SupplyMismatch { asset: pair.quote, supply: dec!(1000), held: dec!(1001) })
+    );
+}
+
+#[test]
+fn test_frozen_without_order_is_detected() {
+    let (mut account, mut book, auditor, mut settlement, pair) = setup();
+
+    // 绕过结算模块多冻结了 100
+    account.try_freeze(2, pair.quote, dec!(100)).unwrap();
+    let bid = Order { id: 1, user_id: 2, price: dec!(100), quantity: dec!(2), side: OrderSide::Bid };
+    settlement.reserve(&mut account, &bid).unwrap();
+    book.match_order(bid);
+
+    assert_eq!(
+        auditor.check_reservations(&account, &[&settlement]),
+        Err(AuditError::ReservationMismatch { user_id: 2, asset: pair.quote, frozen: dec!(300), reserved: dec!(200) })
+    );
+    // 总量仍然守恒
+    assert!(auditor.check_conservation(&account).is_ok());
+}
+
+#[test]
+fn test_partial_fill_at_better_price_passes_audit() {
+    let pair = Instrument::new(Asset::from("BTC"), Asset::from("USDT"));
+    let mut exchange = Exchange::new(pair, 8, 2);
+    let mut auditor = Auditor::new();
+    let commands = [
+        Command::Deposit { user_id: 1, asset: pair.base, amount: dec!(1) },
+        Command::Deposit { user_id: 2, asset: pair.quote, amount: dec!(1000) },
+        Command::Place(Order { id: 1, user_id: 1, price: dec!(99.5), quantity: dec!(0.3), side: OrderSide::Ask }),
+        // 限价 101 买 1 个，以 99.5 成交 0.3 个，剩余部分继续挂单
+        Command::Place(Order { id: 2, user_id: 2, price: dec!(101), quantity: dec!(1), side: OrderSide::Bid }),
+    ];
+    for (seq, command) in commands.iter().enumerate() {
+        exchange.apply(seq as u64 + 1, command).unwrap();
+    }
+    auditor.on_deposit(pair.base, dec!(1));
+    auditor.on_deposit(pair.quote, dec!(1000));
+
+    assert_eq!(exchange.book().order_status(2).unwrap().remaining_quantity, dec!(0.7));
+    auditor.audit(exchange.account(), &[exchange.settlement()]).unwrap();
+}
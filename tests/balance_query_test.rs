@@ -0,0 +1,60 @@
+// tests/balance_query_test.rs
+
+use rust_decimal_macros::dec;
+use mach_rs::{AccountManager, AccountError, Asset};
+
+#[test]
+fn test_balance_distinguishes_missing_from_empty() {
+    let mut account = AccountManager::new();
+    let usdt = Asset::from("USDT");
+    let btc = Asset::from("BTC");
+
+    account.deposit(1, usdt, dec!(10)).unwrap();
+    account.try_freeze(1, usdt, dec!(10)).unwrap();
+    account.confirm_trade(1, usdt, dec!(10)).unwrap();
+
+    // 余额为 0 但记录存在
+    let balance = account.balance(1, usdt).unwrap();
+    assert_eq!((balance.available, balance.frozen), (dec!(0), dec!(0)));
+
+    assert_eq!(account.balance(2, usdt).unwrap_err(), AccountError::UserNotFound { user_id: 2 });
+    assert_eq!(account.balance(1, btc).unwrap_err(), AccountError::AssetNotFound { user_id: 1, asset: btc });
+}
+
+#[test]
+fn test_portfolio_listing_through_shared_reference() {
+    let mut account = AccountManager::new();
+    account.deposit(1, Asset::from("USDT"), dec!(100)).unwrap();
+    account.deposit(1, Asset::from("BTC"), dec!(1)).unwrap();
+    account.deposit(1, Asset::from("ETH"), dec!(3)).unwrap();
+
+    // 只需要共享引用 (例如读锁)
+    let shared: &AccountManager = &account;
+    let portfolio = shared.balances_of(1).unwrap();
+    let assets: Vec<_> = portfolio.iter().map(|(a, _)| a.as_str()).collect();
+    assert_eq!(assets, vec!["BTC", "ETH", "USDT"]);
+    assert_eq!(portfolio[1].1.available, dec!(3));
+    assert!(shared.balances_of(9).is_err());
+}
+
+#[test]
+fn test_holders_pagination() {
+    let mut account = AccountManager::new();
+    let btc = Asset::from("BTC");
+    for user in [5, 1, 4, 2, 3] {
+        account.deposit(user, btc, dec!(1)).unwrap();
+    }
+    // 通过划转产生的持有人也要被索引
+    account.transfer(5, 6, btc, dec!(0.5), 1).unwrap();
+    account.deposit(7, Asset::from("USDT"), dec!(1)).unwrap();
+
+    let page = account.holders(btc, None, 4);
+    assert_eq!(page.items.iter().map(|(u, _)| *u).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    assert_eq!(page.next_cursor, Some(4));
+
+    let page = account.holders(btc, page.next_cursor, 4);
+    assert_eq!(page.items.iter().map(|(u, _)| *u).collect::<Vec<_>>(), vec![5, 6]);
+    assert_eq!(page.next_cursor, None);
+
+    assert!(account.holders(Asset::from("ETH"), None, 10).items.is_empty());
+}
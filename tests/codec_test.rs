@@ -0,0 +1,113 @@
+// tests/codec_test.rs
+use mach_rs::codec::{self, CodecError, Message, HEADER_LEN, SCHEMA_VERSION};
+use mach_rs::{Asset, Instrument, Order, OrderSide, TradeEvent};
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+
+fn instrument() -> Instrument {
+    Instrument::new(Asset::new("BTC"), Asset::new("USDT"))
+}
+
+fn decimal() -> impl Strategy<Value = Decimal> {
+    (any::<i64>(), 0u32..=18).prop_map(|(m, s)| Decimal::new(m, s))
+}
+
+fn order() -> impl Strategy<Value = Order> {
+    (any::<u64>(), any::<u64>(), decimal(), decimal(), any::<bool>()).prop_map(|(id, user_id, price, quantity, bid)| Order {
+        id,
+        price,
+        quantity,
+        side: if bid { OrderSide::Bid } else { OrderSide::Ask },
+        user_id,
+    })
+}
+
+fn trade() -> impl Strategy<Value = TradeEvent> {
+    (any::<[u64; 5]>(), decimal(), decimal()).prop_map(|(ids, price, quantity)| TradeEvent {
+        trade_id: ids[0],
+        maker_order_id: ids[1],
+        maker_user_id: ids[2],
+        taker_order_id: ids[3],
+        taker_user_id: ids[4],
+        price,
+        quantity,
+    })
+}
+
+proptest! {
+    #[test]
+    fn order_and_trade_round_trip(order in order(), trade in trade()) {
+        let mut buf = Vec::new();
+        codec::encode_order(instrument(), &order, &mut buf);
+        codec::encode_trade(instrument(), &trade, &mut buf);
+
+        let (first, used) = codec::decode(&buf).unwrap();
+        let Message::NewOrder(view) = first else { panic!("应为新订单") };
+        prop_assert_eq!(view.to_order(), order);
+        prop_assert_eq!(first.instrument(), instrument());
+
+        let (second, rest) = codec::decode(&buf[used..]).unwrap();
+        prop_assert_eq!(used + rest, buf.len());
+        let Message::Trade(view) = second else { panic!("应为成交") };
+        prop_assert_eq!(view.to_trade(), trade);
+    }
+
+    #[test]
+    fn cancel_and_amend_round_trip(order_id in any::<u64>(), user_id in any::<u64>(), price in decimal(), quantity in decimal()) {
+        let mut buf = Vec::new();
+        codec::encode_cancel(instrument(), order_id, user_id, &mut buf);
+        codec::encode_amend(instrument(), order_id, user_id, price, quantity, &mut buf);
+
+        let (Message::Cancel(cancel), used) = codec::decode(&buf).unwrap() else { panic!("应为撤单") };
+        prop_assert_eq!((cancel.order_id(), cancel.user_id()), (order_id, user_id));
+        prop_assert_eq!(cancel.base(), &Asset::new("BTC"));
+
+        let (Message::Amend(amend), _) = codec::decode(&buf[used..]).unwrap() else { panic!("应为改单") };
+        prop_assert_eq!((amend.order_id(), amend.user_id()), (order_id, user_id));
+        prop_assert_eq!((amend.price(), amend.quantity()), (price, quantity));
+    }
+}
+
+#[test]
+fn longer_block_is_skipped_and_unknown_versions_rejected() {
+    let mut buf = Vec::new();
+    codec::encode_cancel(instrument(), 7, 1, &mut buf);
+    // 消息体末尾多出 8 字节，按 block_length 跳过
+    buf[0..2].copy_from_slice(&40u16.to_le_bytes());
+    buf.extend_from_slice(&[0xAB; 8]);
+    codec::encode_cancel(instrument(), 8, 1, &mut buf);
+
+    let (Message::Cancel(first), used) = codec::decode(&buf).unwrap() else { panic!("应为撤单") };
+    assert_eq!((first.order_id(), used), (7, HEADER_LEN + 40));
+    let (Message::Cancel(second), _) = codec::decode(&buf[used..]).unwrap() else { panic!("应为撤单") };
+    assert_eq!(second.order_id(), 8);
+
+    // 比解码器新的版本和版本 0 都不认识
+    for version in [0u16, SCHEMA_VERSION + 1] {
+        let mut unknown = buf.clone();
+        unknown[6..8].copy_from_slice(&version.to_le_bytes());
+        assert_eq!(codec::decode(&unknown).unwrap_err(), CodecError::UnsupportedVersion { version });
+    }
+}
+
+#[test]
+fn malformed_messages_are_rejected() {
+    let order = Order { id: 1, price: Decimal::new(100, 0), quantity: Decimal::new(1, 0), side: OrderSide::Bid, user_id: 1 };
+    let mut buf = Vec::new();
+    codec::encode_order(instrument(), &order, &mut buf);
+
+    assert_eq!(codec::decode(&buf[..5]).unwrap_err(), CodecError::Truncated { needed: HEADER_LEN, available: 5 });
+    assert_eq!(codec::decode(&buf[..40]).unwrap_err(), CodecError::Truncated { needed: buf.len(), available: 40 });
+
+    let mut bad_side = buf.clone();
+    bad_side[HEADER_LEN + 64] = 9;
+    assert_eq!(codec::decode(&bad_side).unwrap_err(), CodecError::InvalidField { offset: HEADER_LEN + 64 });
+
+    let mut bad_template = buf.clone();
+    bad_template[2..4].copy_from_slice(&99u16.to_le_bytes());
+    assert_eq!(codec::decode(&bad_template).unwrap_err(), CodecError::UnknownTemplate { template_id: 99 });
+
+    let mut bad_schema = buf;
+    bad_schema[4..6].copy_from_slice(&2u16.to_le_bytes());
+    assert_eq!(codec::decode(&bad_schema).unwrap_err(), CodecError::SchemaMismatch { schema_id: 2 });
+}
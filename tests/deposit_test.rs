@@ -0,0 +1,86 @@
+// tests/deposit_test.rs
+
+use rust_decimal_macros::dec;
+use mach_rs::{AccountManager, Asset, DepositManager, DepositStatus, DepositError, AccountError};
+
+#[test]
+fn test_duplicate_notification_does_not_double_credit() {
+    let mut account = AccountManager::new();
+    let mut deposits = DepositManager::new();
+    let usdt = Asset::from("USDT");
+
+    assert_eq!(deposits.credit(&mut account, "0xabc", 1, usdt, dec!(100), 0).unwrap(), DepositStatus::Credited);
+    assert_eq!(deposits.credit(&mut account, "0xabc", 1, usdt, dec!(100), 0).unwrap(), DepositStatus::Credited);
+    assert_eq!(account.get_balance(1, usdt), (dec!(100), dec!(0)));
+
+    // 同一交易号内容不一致
+    assert!(matches!(
+        deposits.credit(&mut account, "0xabc", 1, usdt, dec!(200), 0),
+        Err(DepositError::DuplicateMismatch { .. })
+    ));
+    assert!(account.verify_ledger().is_ok());
+}
+
+#[test]
+fn test_pending_until_required_confirmations() {
+    let mut account = AccountManager::new();
+    let mut deposits = DepositManager::new();
+    let btc = Asset::from("BTC");
+    deposits.set_required_confirmations(btc, 3);
+
+    assert_eq!(deposits.credit(&mut account, "tx1", 1, btc, dec!(2), 1).unwrap(), DepositStatus::Confirming);
+    // 确认中的资金不可用
+    assert_eq!(account.get_balance(1, btc), (dec!(0), dec!(0)));
+    assert!(account.try_freeze(1, btc, dec!(1)).is_err());
+
+    // 重复推送带来新的确认数
+    assert_eq!(deposits.credit(&mut account, "tx1", 1, btc, dec!(2), 2).unwrap(), DepositStatus::Confirming);
+    assert_eq!(deposits.update_confirmations(&mut account, "tx1", 3).unwrap(), DepositStatus::Credited);
+    assert_eq!(account.get_balance(1, btc), (dec!(2), dec!(0)));
+    assert_eq!(deposits.get("tx1").unwrap().confirmations, 3);
+    assert!(account.verify_ledger().is_ok());
+}
+
+#[test]
+fn test_reverse_orphaned_deposits() {
+    let mut account = AccountManager::new();
+    let mut deposits = DepositManager::new();
+    let btc = Asset::from("BTC");
+    deposits.set_required_confirmations(btc, 6);
+
+    deposits.credit(&mut account, "pending", 1, btc, dec!(1), 0).unwrap();
+    deposits.reverse(&mut account, "pending").unwrap();
+    assert_eq!(deposits.get("pending").unwrap().status, DepositStatus::Reversed);
+    assert!(matches!(deposits.reverse(&mut account, "pending"), Err(DepositError::InvalidState { .. })));
+
+    // 已入账但资金已冻结，无法回滚
+    deposits.credit(&mut account, "credited", 1, btc, dec!(5), 6).unwrap();
+    account.try_freeze(1, btc, dec!(4)).unwrap();
+    assert!(matches!(
+        deposits.reverse(&mut account, "credited"),
+        Err(DepositError::Account(AccountError::InsufficientAvailable { .. }))
+    ));
+
+    account.unlock(1, btc, dec!(4)).unwrap();
+    deposits.reverse(&mut account, "credited").unwrap();
+    assert_eq!(account.get_balance(1, btc), (dec!(0), dec!(0)));
+    assert!(account.verify_ledger().is_ok());
+}
+
+#[test]
+fn test_non_positive_amount_is_rejected() {
+    let mut account = AccountManager::new();
+    let mut deposits = DepositManager::new();
+    let usdt = Asset::from("USDT");
+    deposits.set_required_confirmations(Asset::from("BTC"), 3);
+
+    for (tx_id, asset, amount) in [("zero", usdt, dec!(0)), ("neg", usdt, dec!(-5)), ("pending", Asset::from("BTC"), dec!(-1))] {
+        assert_eq!(
+            deposits.credit(&mut account, tx_id, 1, asset, amount, 0),
+            Err(DepositError::Account(AccountError::InvalidAmount { user_id: 1, asset, amount }))
+        );
+        assert!(deposits.get(tx_id).is_none());
+    }
+    assert_eq!(account.get_balance(1, usdt), (dec!(0), dec!(0)));
+    assert!(account.verify_ledger().is_ok());
+}
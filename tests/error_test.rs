@@ -0,0 +1,44 @@
+// tests/error_test.rs
+
+use rust_decimal_macros::dec;
+use mach_rs::{AccountManager, AccountError, OrderBook, Order, OrderSide, Asset, EngineError, Error};
+
+#[test]
+fn test_account_error_carries_context() {
+    let mut account = AccountManager::new();
+    let usdt = Asset::from("USDT");
+    account.deposit(7, usdt, dec!(10)).unwrap();
+
+    let err: Error = account.try_freeze(7, usdt, dec!(25)).unwrap_err().into();
+    assert_eq!(err.code(), "INSUFFICIENT_BALANCE");
+    assert_eq!(err.user_id(), Some(7));
+    assert_eq!(err.to_string(), "用户 7 可用 USDT 不足: 需要 25, 可用 10");
+
+    let err = account.unlock(8, usdt, dec!(1)).unwrap_err();
+    assert_eq!(err, AccountError::UserNotFound { user_id: 8 });
+    let err = account.unlock(7, Asset::from("BTC"), dec!(1)).unwrap_err();
+    assert_eq!(err, AccountError::AssetNotFound { user_id: 7, asset: Asset::from("BTC") });
+
+    // 实现 std::error::Error，可以放进 Box<dyn Error>
+    let boxed: Box<dyn std::error::Error> = Box::new(Error::from(err));
+    assert!(boxed.to_string().contains("BTC"));
+}
+
+#[test]
+fn test_engine_rejections() {
+    let mut book = OrderBook::new();
+
+    assert_eq!(book.cancel_order(42).unwrap_err(), EngineError::OrderNotFound { order_id: 42 });
+
+    let zero = Order { id: 1, user_id: 1, price: dec!(100), quantity: dec!(0), side: OrderSide::Bid };
+    assert_eq!(book.place_order(zero).unwrap_err(), EngineError::InvalidQuantity { order_id: 1, quantity: dec!(0) });
+
+    let negative = Order { id: 1, user_id: 1, price: dec!(-1), quantity: dec!(1), side: OrderSide::Bid };
+    assert!(matches!(book.place_order(negative), Err(EngineError::InvalidPrice { .. })));
+
+    let ok = Order { id: 1, user_id: 1, price: dec!(100), quantity: dec!(1), side: OrderSide::Bid };
+    assert!(book.place_order(ok.clone()).unwrap().is_empty());
+    let err = Error::from(book.place_order(ok).unwrap_err());
+    assert_eq!(err.code(), "DUPLICATE_ORDER_ID");
+    assert_eq!(err.order_id(), Some(1));
+}
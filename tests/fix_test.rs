@@ -0,0 +1,435 @@
+// tests/fix_test.rs
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use rust_decimal_macros::dec;
+use mach_rs::fix::{msg_type, tag, MAX_BODY_LEN};
+use mach_rs::{Asset, Command, Engine, Exchange, FixAcceptor, FixConfig, FixError, FixMessage, Instrument, Order, OrderSide};
+
+fn pair() -> Instrument {
+    Instrument::new(Asset::from("BTC"), Asset::from("USDT"))
+}
+
+fn store(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("mach_fix_{name}_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn start(store: &Path) -> String {
+    let (engine, outputs) = Engine::spawn(Exchange::new(pair(), 8, 2), 64);
+    let handle = engine.handle();
+    handle.call(Command::Deposit { user_id: 1, asset: Asset::from("BTC"), amount: dec!(5) }).unwrap().unwrap();
+    handle.call(Command::Deposit { user_id: 2, asset: Asset::from("USDT"), amount: dec!(10000) }).unwrap().unwrap();
+
+    let config = FixConfig::new("MACH", store).counterparty("SELLER", 1, Some("pw")).counterparty("BUYER", 2, None);
+    let acceptor = FixAcceptor::new(handle, outputs, pair(), config).unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || acceptor.serve(listener));
+    addr
+}
+
+// 测试用的 FIX 发起方
+struct Initiator {
+    comp_id: &'static str,
+    stream: TcpStream,
+    buf: Vec<u8>,
+    next_seq: u64,
+}
+
+impl Initiator {
+    fn connect(addr: &str, comp_id: &'static str) -> Self {
+        let stream = TcpStream::connect(addr).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        Self { comp_id, stream, buf: Vec::new(), next_seq: 1 }
+    }
+
+    fn send(&mut self, msg: FixMessage) {
+        let msg = msg
+            .with(tag::SENDER_COMP_ID, self.comp_id)
+            .with(tag::TARGET_COMP_ID, "MACH")
+            .with(tag::MSG_SEQ_NUM, self.next_seq)
+            .with(tag::SENDING_TIME, "20260101-00:00:00.000");
+        self.next_seq += 1;
+        self.stream.write_all(&msg.encode()).unwrap();
+    }
+
+    fn recv(&mut self) -> FixMessage {
+        loop {
+            if let Some((msg, len)) = FixMessage::decode(&self.buf).unwrap() {
+                self.buf.drain(..len);
+                return msg;
+            }
+            let mut chunk = [0u8; 1024];
+            let n = self.stream.read(&mut chunk).unwrap();
+            assert!(n > 0, "连接被关闭");
+            self.buf.extend_from_slice(&chunk[..n]);
+        }
+    }
+
+    // 跳过心跳，返回下一条非心跳消息
+    fn expect(&mut self, msg_type: &str) -> FixMessage {
+        loop {
+            let msg = self.recv();
+            if msg.msg_type() != msg_type::HEARTBEAT {
+                assert_eq!(msg.msg_type(), msg_type, "{msg:?}");
+                return msg;
+            }
+        }
+    }
+
+    fn logon(&mut self, password: Option<&str>) -> FixMessage {
+        let mut logon = FixMessage::new(msg_type::LOGON).with(tag::ENCRYPT_METHOD, 0).with(tag::HEART_BT_INT, 30);
+        if let Some(pw) = password {
+            logon.set(tag::PASSWORD, pw);
+        }
+        self.send(logon);
+        self.expect(msg_type::LOGON)
+    }
+}
+
+fn new_order(cl_ord_id: &str, side: &str, price: &str, qty: &str) -> FixMessage {
+    FixMessage::new(msg_type::NEW_ORDER_SINGLE)
+        .with(tag::CL_ORD_ID, cl_ord_id)
+        .with(tag::SYMBOL, "BTC/USDT")
+        .with(tag::SIDE, side)
+        .with(tag::ORD_TYPE, "2")
+        .with(tag::PRICE, price)
+        .with(tag::ORDER_QTY, qty)
+}
+
+#[test]
+fn test_message_round_trip_and_checksum() {
+    let msg = new_order("A1", "1", "100.5", "2").with(tag::MSG_SEQ_NUM, 7).with(tag::SENDER_COMP_ID, "X");
+    let bytes = msg.encode();
+    assert!(bytes.starts_with(b"8=FIX.4.4\x019="));
+    let text = String::from_utf8_lossy(&bytes);
+    assert!(text.contains("\x0135=D\x0149=X\x0134=7\x01"), "消息头字段排在消息体之前: {text}");
+
+    assert_eq!(FixMessage::decode(&bytes[..bytes.len() - 1]).unwrap(), None);
+    let (decoded, used) = FixMessage::decode(&bytes).unwrap().unwrap();
+    assert_eq!((decoded.get(tag::PRICE), used), (Some("100.5"), bytes.len()));
+
+    let mut corrupted = bytes.clone();
+    let pos = corrupted.len() - 10;
+    corrupted[pos] ^= 1;
+    assert!(FixMessage::decode(&corrupted).is_err());
+}
+
+#[test]
+fn test_oversized_body_length_is_rejected() {
+    let too_long = format!("8=FIX.4.4\x019={}\x01", MAX_BODY_LEN + 1);
+    assert!(matches!(FixMessage::decode(too_long.as_bytes()), Err(FixError::Malformed { .. })));
+    let overflow = format!("8=FIX.4.4\x019={}\x01", usize::MAX);
+    assert!(matches!(FixMessage::decode(overflow.as_bytes()), Err(FixError::Malformed { .. })));
+    // 迟迟不出现 SOH 的 BodyLength 不会一直缓冲
+    assert!(FixMessage::decode(b"8=FIX.4.4\x019=99999").is_err());
+    assert_eq!(FixMessage::decode(b"8=FIX.4.4\x019=12").unwrap(), None);
+}
+
+#[test]
+fn test_session_is_released_after_a_malformed_message() {
+    let dir = store("malformed");
+    let addr = start(&dir);
+    let mut buyer = Initiator::connect(&addr, "BUYER");
+    buyer.logon(None);
+    buyer.stream.write_all(format!("8=FIX.4.4\x019={}\x01", MAX_BODY_LEN + 1).as_bytes()).unwrap();
+    let mut rest = Vec::new();
+    let _ = buyer.stream.read_to_end(&mut rest);
+
+    let mut buyer = Initiator::connect(&addr, "BUYER");
+    buyer.next_seq = 2;
+    buyer.logon(None);
+    buyer.send(FixMessage::new(msg_type::LOGOUT));
+    buyer.expect(msg_type::LOGOUT);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_order_entry_and_execution_reports() {
+    let dir = store("orders");
+    let addr = start(&dir);
+    let mut seller = Initiator::connect(&addr, "SELLER");
+    let mut buyer = Initiator::connect(&addr, "BUYER");
+    assert_eq!(seller.logon(Some("pw")).get(tag::HEART_BT_INT), Some("30"));
+    buyer.logon(None);
+
+    seller.send(new_order("S1", "2", "100", "2"));
+    let ack = seller.expect(msg_type::EXECUTION_REPORT);
+    assert_eq!((ack.get(tag::EXEC_TYPE), ack.get(tag::ORD_STATUS), ack.get(tag::LEAVES_QTY)), (Some("0"), Some("0"), Some("2")));
+
+    buyer.send(new_order("B1", "1", "100", "0.5"));
+    assert_eq!(buyer.expect(msg_type::EXECUTION_REPORT).get(tag::EXEC_TYPE), Some("0"));
+    let fill = buyer.expect(msg_type::EXECUTION_REPORT);
+    assert_eq!((fill.get(tag::EXEC_TYPE), fill.get(tag::ORD_STATUS), fill.get(tag::LAST_QTY)), (Some("F"), Some("2"), Some("0.5")));
+    let passive = seller.expect(msg_type::EXECUTION_REPORT);
+    assert_eq!((passive.get(tag::CL_ORD_ID), passive.get(tag::ORD_STATUS), passive.get(tag::CUM_QTY)), (Some("S1"), Some("1"), Some("0.5")));
+
+    // 改单: OrderQty 为新的订单总量
+    seller.send(FixMessage::new(msg_type::ORDER_CANCEL_REPLACE_REQUEST)
+        .with(tag::ORIG_CL_ORD_ID, "S1").with(tag::CL_ORD_ID, "S2").with(tag::SYMBOL, "BTC/USDT")
+        .with(tag::SIDE, "2").with(tag::ORD_TYPE, "2").with(tag::PRICE, "101").with(tag::ORDER_QTY, "1.5"));
+    let replaced = seller.expect(msg_type::EXECUTION_REPORT);
+    assert_eq!((replaced.get(tag::EXEC_TYPE), replaced.get(tag::ORIG_CL_ORD_ID), replaced.get(tag::LEAVES_QTY)), (Some("5"), Some("S1"), Some("1")));
+
+    seller.send(FixMessage::new(msg_type::ORDER_CANCEL_REQUEST).with(tag::ORIG_CL_ORD_ID, "S1").with(tag::CL_ORD_ID, "S3"));
+    assert_eq!(seller.expect(msg_type::ORDER_CANCEL_REJECT).get(tag::CXL_REJ_REASON), Some("1"));
+    seller.send(FixMessage::new(msg_type::ORDER_CANCEL_REQUEST).with(tag::ORIG_CL_ORD_ID, "S2").with(tag::CL_ORD_ID, "S4"));
+    let cancelled = seller.expect(msg_type::EXECUTION_REPORT);
+    assert_eq!((cancelled.get(tag::EXEC_TYPE), cancelled.get(tag::CL_ORD_ID), cancelled.get(tag::ORIG_CL_ORD_ID)), (Some("4"), Some("S4"), Some("S2")));
+
+    buyer.send(new_order("B2", "1", "100", "1000"));
+    let rejected = buyer.expect(msg_type::EXECUTION_REPORT);
+    assert_eq!(rejected.get(tag::EXEC_TYPE), Some("8"));
+    assert!(rejected.get(tag::TEXT).unwrap().starts_with("INSUFFICIENT_BALANCE"));
+
+    seller.send(FixMessage::new(msg_type::LOGOUT));
+    seller.expect(msg_type::LOGOUT);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_missed_reports_are_resent_and_sequence_numbers_persist() {
+    let dir = store("resend");
+    let addr = start(&dir);
+    let mut seller = Initiator::connect(&addr, "SELLER");
+    seller.logon(Some("pw"));
+    seller.send(new_order("S1", "2", "100", "1"));
+    seller.expect(msg_type::EXECUTION_REPORT);
+    seller.send(FixMessage::new(msg_type::LOGOUT));
+    seller.expect(msg_type::LOGOUT);
+    let next_seq = seller.next_seq;
+
+    // 卖方断线期间被动成交
+    let mut buyer = Initiator::connect(&addr, "BUYER");
+    buyer.logon(None);
+    buyer.send(new_order("B1", "1", "100", "1"));
+    buyer.expect(msg_type::EXECUTION_REPORT);
+    buyer.expect(msg_type::EXECUTION_REPORT);
+    thread::sleep(Duration::from_millis(100));
+
+    let content = std::fs::read_to_string(dir.join("MACH-SELLER.seqnums")).unwrap();
+    assert_eq!(content.trim(), format!("5 {next_seq}"), "Logon, 回报, Logout, 离线成交回报各占一个序号");
+
+    // 重连: 低于期望的序号被拒绝
+    let mut stale = Initiator::connect(&addr, "SELLER");
+    stale.send(FixMessage::new(msg_type::LOGON).with(tag::HEART_BT_INT, 30).with(tag::PASSWORD, "pw"));
+    assert_eq!(stale.expect(msg_type::LOGOUT).get(tag::MSG_SEQ_NUM), Some("5"));
+    thread::sleep(Duration::from_millis(100));
+
+    let mut seller = Initiator::connect(&addr, "SELLER");
+    seller.next_seq = next_seq;
+    let logon = seller.logon(Some("pw"));
+    assert_eq!(logon.get(tag::MSG_SEQ_NUM), Some("6"), "离线回报占用了 4, 拒绝登录的 Logout 占用了 5");
+    seller.send(FixMessage::new(msg_type::RESEND_REQUEST).with(tag::BEGIN_SEQ_NO, 1).with(tag::END_SEQ_NO, 0));
+
+    let gap = seller.expect(msg_type::SEQUENCE_RESET);
+    assert_eq!((gap.get(tag::MSG_SEQ_NUM), gap.get(tag::NEW_SEQ_NO)), (Some("1"), Some("2")));
+    let ack = seller.expect(msg_type::EXECUTION_REPORT);
+    assert_eq!((ack.get(tag::MSG_SEQ_NUM), ack.get(tag::POSS_DUP_FLAG)), (Some("2"), Some("Y")));
+    seller.expect(msg_type::SEQUENCE_RESET);
+    let missed = seller.expect(msg_type::EXECUTION_REPORT);
+    assert_eq!((missed.get(tag::MSG_SEQ_NUM), missed.get(tag::EXEC_TYPE), missed.get(tag::POSS_DUP_FLAG)), (Some("4"), Some("F"), Some("Y")));
+    let tail = seller.expect(msg_type::SEQUENCE_RESET);
+    assert_eq!(tail.get(tag::NEW_SEQ_NO), Some("7"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_gap_fill_ahead_of_expected_sequence_requests_resend() {
+    let dir = store("gapfill");
+    let addr = start(&dir);
+    let mut buyer = Initiator::connect(&addr, "BUYER");
+    buyer.logon(None);
+
+    // 期望 2，收到序号 5 的 GapFill: 2..4 缺失，不能直接跳到 NewSeqNo
+    buyer.next_seq = 5;
+    buyer.send(FixMessage::new(msg_type::SEQUENCE_RESET).with(tag::GAP_FILL_FLAG, "Y").with(tag::NEW_SEQ_NO, 6));
+    let resend = buyer.expect(msg_type::RESEND_REQUEST);
+    assert_eq!((resend.get(tag::BEGIN_SEQ_NO), resend.get(tag::END_SEQ_NO)), (Some("2"), Some("0")));
+
+    // 对方从 2 开始补发后序号正常推进
+    buyer.next_seq = 2;
+    buyer.send(FixMessage::new(msg_type::SEQUENCE_RESET).with(tag::GAP_FILL_FLAG, "Y").with(tag::NEW_SEQ_NO, 6));
+    buyer.next_seq = 6;
+    buyer.send(FixMessage::new(msg_type::TEST_REQUEST).with(tag::TEST_REQ_ID, "T1"));
+    let heartbeat = buyer.recv();
+    assert_eq!((heartbeat.msg_type(), heartbeat.get(tag::TEST_REQ_ID)), (msg_type::HEARTBEAT, Some("T1")));
+    buyer.send(FixMessage::new(msg_type::LOGOUT));
+    buyer.expect(msg_type::LOGOUT);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_order_ids_continue_after_existing_orders() {
+    let dir = store("order_ids");
+    let (engine, outputs) = Engine::spawn(Exchange::new(pair(), 8, 2), 64);
+    let handle = engine.handle();
+    handle.call(Command::Deposit { user_id: 2, asset: Asset::from("USDT"), amount: dec!(10000) }).unwrap().unwrap();
+    // 模拟从日志恢复出的挂单
+    handle.call(Command::Place(Order { id: 7, user_id: 2, price: dec!(90), quantity: dec!(1), side: OrderSide::Bid })).unwrap().unwrap();
+
+    // 订单 7 是重启前通过 FIX 下的单，订单表里记着它的 ClOrdID
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("MACH.orders"), "7\x01BUYER\x01OLD\x01\n").unwrap();
+
+    let config = FixConfig::new("MACH", &dir).counterparty("BUYER", 2, None);
+    let acceptor = FixAcceptor::new(handle, outputs, pair(), config).unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || acceptor.serve(listener));
+
+    let mut buyer = Initiator::connect(&addr, "BUYER");
+    buyer.logon(None);
+    buyer.send(new_order("B1", "1", "100", "1"));
+    assert_eq!(buyer.expect(msg_type::EXECUTION_REPORT).get(tag::ORDER_ID), Some("8"));
+
+    buyer.send(new_order("OLD", "1", "100", "1"));
+    assert_eq!(buyer.expect(msg_type::EXECUTION_REPORT).get(tag::ORD_REJ_REASON), Some("6"));
+    buyer.send(FixMessage::new(msg_type::ORDER_CANCEL_REQUEST).with(tag::ORIG_CL_ORD_ID, "OLD").with(tag::CL_ORD_ID, "C1"));
+    let cancelled = buyer.expect(msg_type::EXECUTION_REPORT);
+    assert_eq!((cancelled.get(tag::EXEC_TYPE), cancelled.get(tag::ORDER_ID)), (Some("4"), Some("7")));
+    buyer.send(FixMessage::new(msg_type::LOGOUT));
+    buyer.expect(msg_type::LOGOUT);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_replace_uses_fills_that_land_before_it_is_processed() {
+    let dir = store("replace_race");
+    let (engine, outputs) = Engine::spawn(Exchange::new(pair(), 8, 2), 64);
+    let handle = engine.handle();
+    handle.call(Command::Deposit { user_id: 1, asset: Asset::from("BTC"), amount: dec!(5) }).unwrap().unwrap();
+    handle.call(Command::Deposit { user_id: 2, asset: Asset::from("USDT"), amount: dec!(10000) }).unwrap().unwrap();
+
+    let config = FixConfig::new("MACH", &dir).counterparty("SELLER", 1, None);
+    let acceptor = FixAcceptor::new(handle.clone(), outputs, pair(), config).unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || acceptor.serve(listener));
+
+    let mut seller = Initiator::connect(&addr, "SELLER");
+    seller.logon(None);
+    seller.send(new_order("S1", "2", "100", "2"));
+    seller.expect(msg_type::EXECUTION_REPORT);
+
+    // 让引擎线程停住: 先排入一笔成交 1.5 的买单，再发两条改单，网关看到的已成交量还是 0
+    let (release, gate) = std::sync::mpsc::channel::<()>();
+    let blocker = { let handle = handle.clone(); thread::spawn(move || handle.query(move |_| { let _ = gate.recv(); })) };
+    thread::sleep(Duration::from_millis(50));
+    handle.submit(Command::Place(Order { id: 1000, user_id: 2, price: dec!(100), quantity: dec!(1.5), side: OrderSide::Bid })).unwrap();
+    let replace = |cl_ord_id: &str, qty: &str| FixMessage::new(msg_type::ORDER_CANCEL_REPLACE_REQUEST)
+        .with(tag::ORIG_CL_ORD_ID, "S1").with(tag::CL_ORD_ID, cl_ord_id).with(tag::SYMBOL, "BTC/USDT")
+        .with(tag::SIDE, "2").with(tag::ORD_TYPE, "2").with(tag::PRICE, "100").with(tag::ORDER_QTY, qty);
+    seller.send(replace("S2", "1.2"));
+    seller.send(replace("S3", "1.6"));
+    seller.send(FixMessage::new(msg_type::TEST_REQUEST).with(tag::TEST_REQ_ID, "T1"));
+    assert_eq!(seller.recv().get(tag::TEST_REQ_ID), Some("T1"));
+    release.send(()).unwrap();
+    blocker.join().unwrap().unwrap();
+
+    let fill = seller.expect(msg_type::EXECUTION_REPORT);
+    assert_eq!((fill.get(tag::EXEC_TYPE), fill.get(tag::CUM_QTY)), (Some("F"), Some("1.5")));
+    // 总量 1.2 小于实际成交，由引擎拒绝
+    let rejected = seller.expect(msg_type::ORDER_CANCEL_REJECT);
+    assert_eq!(rejected.get(tag::CL_ORD_ID), Some("S2"));
+    assert!(rejected.get(tag::TEXT).unwrap().starts_with("QUANTITY_NOT_ABOVE_FILLED"));
+    // 总量 1.6 扣除实际成交 1.5，剩余 0.1
+    let replaced = seller.expect(msg_type::EXECUTION_REPORT);
+    assert_eq!((replaced.get(tag::EXEC_TYPE), replaced.get(tag::CL_ORD_ID), replaced.get(tag::LEAVES_QTY)), (Some("5"), Some("S3"), Some("0.1")));
+    let status = handle.query(|ex| ex.book().order_status(1).unwrap()).unwrap();
+    assert_eq!((status.original_quantity, status.remaining_quantity), (dec!(1.6), dec!(0.1)));
+
+    seller.send(FixMessage::new(msg_type::LOGOUT));
+    seller.expect(msg_type::LOGOUT);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_fills_are_reported_after_a_restart() {
+    let dir = store("restart");
+    let config = || FixConfig::new("MACH", &dir).counterparty("SELLER", 1, None).counterparty("BUYER", 2, None);
+    let serve = |handle, outputs| {
+        let acceptor = FixAcceptor::new(handle, outputs, pair(), config()).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || acceptor.serve(listener));
+        addr
+    };
+
+    let (engine, outputs) = Engine::spawn(Exchange::new(pair(), 8, 2), 64);
+    let handle = engine.handle();
+    handle.call(Command::Deposit { user_id: 1, asset: Asset::from("BTC"), amount: dec!(5) }).unwrap().unwrap();
+    handle.call(Command::Deposit { user_id: 2, asset: Asset::from("USDT"), amount: dec!(10000) }).unwrap().unwrap();
+    let addr = serve(handle.clone(), outputs);
+
+    let mut seller = Initiator::connect(&addr, "SELLER");
+    seller.logon(None);
+    seller.send(new_order("S1", "2", "100", "3"));
+    seller.expect(msg_type::EXECUTION_REPORT);
+    let mut buyer = Initiator::connect(&addr, "BUYER");
+    buyer.logon(None);
+    buyer.send(new_order("B1", "1", "100", "1"));
+    buyer.expect(msg_type::EXECUTION_REPORT);
+    buyer.expect(msg_type::EXECUTION_REPORT);
+    seller.expect(msg_type::EXECUTION_REPORT);
+    for initiator in [&mut seller, &mut buyer] {
+        initiator.send(FixMessage::new(msg_type::LOGOUT));
+        initiator.expect(msg_type::LOGOUT);
+    }
+
+    // 用同一份状态重启引擎与接入端
+    let (engine, outputs) = Engine::spawn(engine.shutdown(), 64);
+    let addr = serve(engine.handle(), outputs);
+    let (seller_seq, buyer_seq) = (seller.next_seq, buyer.next_seq);
+    let mut seller = Initiator::connect(&addr, "SELLER");
+    seller.next_seq = seller_seq;
+    seller.logon(None);
+    let mut buyer = Initiator::connect(&addr, "BUYER");
+    buyer.next_seq = buyer_seq;
+    buyer.logon(None);
+
+    // 重启前的回报从消息文件重发，会话消息以 GapFill 跳过
+    seller.send(FixMessage::new(msg_type::RESEND_REQUEST).with(tag::BEGIN_SEQ_NO, 1).with(tag::END_SEQ_NO, 0));
+    seller.expect(msg_type::SEQUENCE_RESET);
+    let ack = seller.expect(msg_type::EXECUTION_REPORT);
+    assert_eq!((ack.get(tag::MSG_SEQ_NUM), ack.get(tag::EXEC_TYPE), ack.get(tag::POSS_DUP_FLAG)), (Some("2"), Some("0"), Some("Y")));
+    let fill = seller.expect(msg_type::EXECUTION_REPORT);
+    assert_eq!((fill.get(tag::MSG_SEQ_NUM), fill.get(tag::EXEC_TYPE), fill.get(tag::POSS_DUP_FLAG)), (Some("3"), Some("F"), Some("Y")));
+    assert_eq!(seller.expect(msg_type::SEQUENCE_RESET).get(tag::NEW_SEQ_NO), Some("6"));
+    let mut stored = std::fs::read(dir.join("MACH-SELLER.messages")).unwrap();
+    while let Some((msg, len)) = FixMessage::decode(&stored).unwrap() {
+        assert_eq!(msg.msg_type(), msg_type::EXECUTION_REPORT, "消息文件只保存业务消息");
+        stored.drain(..len);
+    }
+    assert!(stored.is_empty());
+
+    buyer.send(new_order("B2", "1", "100", "0.5"));
+    buyer.expect(msg_type::EXECUTION_REPORT);
+    buyer.expect(msg_type::EXECUTION_REPORT);
+    let fill = seller.expect(msg_type::EXECUTION_REPORT);
+    assert_eq!(
+        (fill.get(tag::EXEC_TYPE), fill.get(tag::CL_ORD_ID), fill.get(tag::CUM_QTY), fill.get(tag::LEAVES_QTY), fill.get(tag::AVG_PX)),
+        (Some("F"), Some("S1"), Some("1.5"), Some("1.5"), Some("100"))
+    );
+
+    // 重启前的 ClOrdID 仍可用于改单
+    seller.send(FixMessage::new(msg_type::ORDER_CANCEL_REPLACE_REQUEST)
+        .with(tag::ORIG_CL_ORD_ID, "S1").with(tag::CL_ORD_ID, "S2").with(tag::SYMBOL, "BTC/USDT")
+        .with(tag::SIDE, "2").with(tag::ORD_TYPE, "2").with(tag::PRICE, "101").with(tag::ORDER_QTY, "2"));
+    let replaced = seller.expect(msg_type::EXECUTION_REPORT);
+    assert_eq!((replaced.get(tag::EXEC_TYPE), replaced.get(tag::LEAVES_QTY)), (Some("5"), Some("0.5")));
+
+    for initiator in [&mut seller, &mut buyer] {
+        initiator.send(FixMessage::new(msg_type::LOGOUT));
+        initiator.expect(msg_type::LOGOUT);
+    }
+    // 订单表只留下仍活动的订单，ClOrdID 为改单后的
+    let orders = std::fs::read_to_string(dir.join("MACH.orders")).unwrap();
+    assert!(orders.ends_with("1\x01SELLER\x01S2\x01\n"), "{orders:?}");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
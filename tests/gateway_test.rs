@@ -0,0 +1,119 @@
+// tests/gateway_test.rs
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Lines, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use rust_decimal_macros::dec;
+use mach_rs::{Asset, Command, Engine, Exchange, Gateway, Instrument};
+use mach_rs::gateway::MAX_LINE_LEN;
+
+struct Client {
+    stream: TcpStream,
+    lines: Lines<BufReader<TcpStream>>,
+}
+
+impl Client {
+    fn connect(addr: &str) -> Self {
+        let stream = TcpStream::connect(addr).unwrap();
+        let lines = BufReader::new(stream.try_clone().unwrap()).lines();
+        Self { stream, lines }
+    }
+
+    fn send(&mut self, line: &str) {
+        writeln!(self.stream, "{line}").unwrap();
+    }
+
+    fn recv(&mut self) -> String {
+        self.lines.next().unwrap().unwrap()
+    }
+
+    fn request(&mut self, line: &str) -> String {
+        self.send(line);
+        self.recv()
+    }
+}
+
+fn start() -> String {
+    let (engine, outputs) = Engine::spawn(Exchange::new(Instrument::new(Asset::from("BTC"), Asset::from("USDT")), 8, 2), 64);
+    let handle = engine.handle();
+    handle.call(Command::Deposit { user_id: 1, asset: Asset::from("BTC"), amount: dec!(2) }).unwrap().unwrap();
+    handle.call(Command::Deposit { user_id: 2, asset: Asset::from("USDT"), amount: dec!(1000) }).unwrap().unwrap();
+
+    let credentials = HashMap::from([(1, String::from("alice")), (2, String::from("bob"))]);
+    let gateway = Gateway::new(handle, outputs, credentials);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || gateway.serve(listener));
+    addr
+}
+
+#[test]
+fn test_login_is_required_and_checked() {
+    let mut client = Client::connect(&start());
+    assert!(client.request("PLACE 1 BUY 100 1").starts_with("REJECT - NOT_LOGGED_IN"));
+    assert!(client.request("LOGIN 1 wrong").starts_with("REJECT - AUTH_FAILED"));
+    assert_eq!(client.request("LOGIN 1 alice"), "LOGGED_IN 1");
+    assert!(client.request("PLACE x BUY 100 1").starts_with("REJECT - BAD_REQUEST"));
+    assert!(client.request("PLACE 1 BUY 100 0").starts_with("REJECT 1 INVALID_QUANTITY"));
+}
+
+#[test]
+fn test_fills_are_pushed_to_both_sides() {
+    let addr = start();
+    let mut maker = Client::connect(&addr);
+    let mut taker = Client::connect(&addr);
+    maker.request("LOGIN 1 alice");
+    taker.request("LOGIN 2 bob");
+
+    assert_eq!(maker.request("PLACE 10 SELL 300 1.5"), "ACK 10");
+    assert!(taker.request("PLACE 20 BUY 300 4").starts_with("REJECT 20 INSUFFICIENT_BALANCE"));
+    assert_eq!(taker.request("PLACE 21 BUY 300 1"), "ACK 21");
+    assert_eq!(taker.recv(), "FILL 21 1 300 1");
+    // 挂单方没有发请求，成交异步推送
+    assert_eq!(maker.recv(), "FILL 10 1 300 1");
+
+    assert_eq!(maker.request("ORDER 10"), "ORDER 10 SELL 300 PARTIALLY_FILLED 1 0.5");
+    assert!(taker.request("CANCEL 10").starts_with("REJECT 10 ORDER_NOT_FOUND"), "不能撤别人的单");
+    assert_eq!(maker.request("AMEND 10 310 0.5"), "ACK 10");
+    maker.send("ORDERS");
+    assert_eq!(maker.recv(), "ORDER 10 SELL 310 PARTIALLY_FILLED 1 0.5");
+    assert_eq!(maker.recv(), "END");
+    assert_eq!(maker.request("CANCEL 10"), "CANCELLED 10");
+    assert_eq!(maker.request("BALANCE BTC"), "BALANCE BTC 1 0");
+    assert_eq!(taker.request("BALANCE USDT"), "BALANCE USDT 700 0");
+    assert!(taker.request("BALANCE USDT_ERC20").starts_with("REJECT - SYMBOL_TOO_LONG"));
+}
+
+#[test]
+fn test_order_ids_are_scoped_per_user() {
+    let addr = start();
+    let mut alice = Client::connect(&addr);
+    let mut bob = Client::connect(&addr);
+    alice.request("LOGIN 1 alice");
+    bob.request("LOGIN 2 bob");
+
+    // 不同用户可以使用相同的订单号，互相看不到
+    assert_eq!(alice.request("PLACE 1 SELL 300 1"), "ACK 1");
+    assert_eq!(bob.request("PLACE 1 BUY 100 1"), "ACK 1");
+    assert!(bob.request("CANCEL 2").starts_with("REJECT 2 ORDER_NOT_FOUND"));
+    assert_eq!(bob.request("ORDER 1"), "ORDER 1 BUY 100 OPEN 0 1");
+
+    // 活动订单号不能重复，订单结束后可以重用
+    assert!(alice.request("PLACE 1 SELL 300 0.5").starts_with("REJECT 1 DUPLICATE_ORDER_ID"));
+    assert_eq!(alice.request("CANCEL 1"), "CANCELLED 1");
+    assert!(alice.request("ORDER 1").starts_with("REJECT 1 ORDER_NOT_FOUND"));
+    assert_eq!(alice.request("PLACE 1 SELL 100 0.5"), "ACK 1");
+    assert_eq!(alice.recv(), "FILL 1 1 100 0.5");
+    assert_eq!(bob.recv(), "FILL 1 1 100 0.5");
+    assert_eq!(bob.request("ORDER 1"), "ORDER 1 BUY 100 PARTIALLY_FILLED 0.5 0.5");
+}
+
+#[test]
+fn test_over_long_line_disconnects() {
+    let mut client = Client::connect(&start());
+    assert_eq!(client.request("LOGIN 1 alice"), "LOGGED_IN 1");
+    client.send(&"A".repeat(MAX_LINE_LEN + 1));
+    assert!(client.recv().starts_with("REJECT - LINE_TOO_LONG"));
+    assert!(!matches!(client.lines.next(), Some(Ok(_))), "连接已断开");
+}
@@ -150,7 +150,7 @@ fn test_insufficient_funds_rejection() {
     let result = account.try_freeze(1, usdt, dec!(100));
 
     // 必须报错，而不是让余额变成负数
-    assert!(matches!(result, Err(AccountError::InsufficientAvailable)));
+    assert_eq!(result, Err(AccountError::InsufficientAvailable { user_id: 1, asset: usdt, requested: dec!(100), available: dec!(10) }));
 }
 
 #[test]
@@ -173,7 +173,7 @@ fn test_cancel_order_releases_funds() {
     assert_eq!(account.get_balance(1, usdt), (dec!(500), dec!(500))); // 500可用, 500冻结
 
     // 3. 撤单
-    if let Some(cancelled) = book.cancel_order(order_id) {
+    if let Ok(cancelled) = book.cancel_order(order_id) {
         // 拿到撤回的订单，计算该退多少钱
         let refund_amount = cancelled.price * cancelled.quantity;
 
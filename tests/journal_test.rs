@@ -0,0 +1,155 @@
+// tests/journal_test.rs
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use rust_decimal_macros::dec;
+use mach_rs::{AccountError, Asset, Command, Error, Exchange, Instrument, Journal, JournalError, Order, OrderSide};
+use mach_rs::journal::{read_journal, replay};
+
+fn pair() -> Instrument {
+    Instrument::new(Asset::from("BTC"), Asset::from("USDT"))
+}
+
+fn place(id: u64, user_id: u64, price: rust_decimal::Decimal, quantity: rust_decimal::Decimal, side: OrderSide) -> Command {
+    Command::Place(Order { id, user_id, price, quantity, side })
+}
+
+fn assert_same_state(a: &Exchange, b: &Exchange) {
+    assert_eq!(a.last_seq(), b.last_seq());
+    assert_eq!(a.account().ledger().entries(), b.account().ledger().entries());
+    for user in 1..=3 {
+        assert_eq!(a.account().balances_of(user), b.account().balances_of(user));
+        let (x, y) = (a.book().open_orders(user), b.book().open_orders(user));
+        assert_eq!(x.iter().map(|r| (r.order_id, r.price, r.remaining_quantity)).collect::<Vec<_>>(),
+                   y.iter().map(|r| (r.order_id, r.price, r.remaining_quantity)).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn test_replay_rebuilds_identical_state() {
+    let mut live = Exchange::new(pair(), 8, 2);
+    let mut journal = Journal::new(Vec::new());
+
+    let commands = [
+        Command::Deposit { user_id: 1, asset: Asset::from("BTC"), amount: dec!(5) },
+        Command::Deposit { user_id: 2, asset: Asset::from("USDT"), amount: dec!(1000) },
+        place(1, 1, dec!(100), dec!(2), OrderSide::Ask),
+        place(2, 1, dec!(101), dec!(1), OrderSide::Ask),
+        place(3, 2, dec!(100.5), dec!(1.5), OrderSide::Bid),
+        // 余额不足被拒绝，但仍然占用序号
+        place(4, 3, dec!(99), dec!(1), OrderSide::Bid),
+        Command::Amend { order_id: 2, price: dec!(102), quantity: dec!(0.5) },
+        // 订单 1 已成交 1.5: 总量 1 被拒绝，总量 2.5 剩余 1
+        Command::Replace { order_id: 1, price: dec!(100), order_qty: dec!(1) },
+        Command::Replace { order_id: 1, price: dec!(100), order_qty: dec!(2.5) },
+        Command::Cancel { order_id: 1 },
+        Command::Cancel { order_id: 42 },
+    ];
+    for command in &commands {
+        let _ = live.execute(&mut journal, command);
+    }
+    assert_eq!(journal.next_seq(), 12);
+
+    let bytes = journal.into_inner();
+    let records = read_journal(&bytes[..]).unwrap();
+    assert_eq!(records.iter().map(|r| &r.command).collect::<Vec<_>>(), commands.iter().collect::<Vec<_>>());
+
+    let mut recovered = Exchange::new(pair(), 8, 2);
+    assert_eq!(replay(&records, &mut recovered).unwrap(), 11);
+    assert_same_state(&live, &recovered);
+    // 已处理过的记录不会重复重放
+    assert_eq!(replay(&records, &mut recovered).unwrap(), 0);
+
+    // 恢复后继续撮合，成交号与原状态一致
+    let next = place(5, 2, dec!(102), dec!(0.5), OrderSide::Bid);
+    assert_eq!(live.apply(12, &next).unwrap(), recovered.apply(12, &next).unwrap());
+    assert_same_state(&live, &recovered);
+}
+
+#[test]
+fn test_non_positive_deposit_is_not_journaled() {
+    let mut live = Exchange::new(pair(), 8, 2);
+    let mut journal = Journal::new(Vec::new());
+
+    for amount in [dec!(0), dec!(-1)] {
+        let command = Command::Deposit { user_id: 1, asset: Asset::from("BTC"), amount };
+        assert!(matches!(
+            live.execute(&mut journal, &command),
+            Err(Error::Account(AccountError::InvalidAmount { user_id: 1, .. }))
+        ));
+    }
+    assert_eq!(journal.next_seq(), 1);
+    assert_eq!(live.last_seq(), 0);
+    assert!(read_journal(&journal.into_inner()[..]).unwrap().is_empty());
+
+    // 直接处理 (如重放旧日志) 同样拒绝，余额不变
+    let command = Command::Deposit { user_id: 1, asset: Asset::from("BTC"), amount: dec!(-1) };
+    assert!(live.apply(1, &command).is_err());
+    assert_eq!(live.last_seq(), 1);
+    assert_eq!(live.account().get_balance(1, Asset::from("BTC")), (dec!(0), dec!(0)));
+}
+
+#[test]
+fn test_torn_tail_is_truncated_and_corruption_detected() {
+    let path = std::env::temp_dir().join(format!("mach_journal_{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let (mut journal, records) = Journal::open(&path).unwrap();
+    assert!(records.is_empty());
+    for user_id in 1..=3 {
+        journal.append(&Command::Deposit { user_id, asset: Asset::from("BTC"), amount: dec!(1) }).unwrap();
+    }
+    journal.sync().unwrap();
+    drop(journal);
+    let complete_len = std::fs::metadata(&path).unwrap().len();
+
+    // 模拟崩溃: 最后一条记录只写了一半
+    OpenOptions::new().append(true).open(&path).unwrap().write_all(&[40, 0, 0, 0, 1, 2, 3]).unwrap();
+    let (mut journal, records) = Journal::open(&path).unwrap();
+    assert_eq!(records.len(), 3);
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), complete_len);
+    assert_eq!(journal.append(&Command::Cancel { order_id: 1 }).unwrap(), 4);
+    drop(journal);
+
+    // 中间记录被篡改
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes[20] ^= 0xff;
+    assert_eq!(read_journal(&bytes[..]).unwrap_err(), JournalError::ChecksumMismatch { offset: 0 });
+
+    // 中间记录的长度字段被改大: 不能当作残缺尾部截掉后面已提交的记录
+    let mut bytes = std::fs::read(&path).unwrap();
+    let record_len = 16 + u32::from_le_bytes(bytes[0..4].try_into().unwrap()) as usize;
+    bytes[record_len..record_len + 4].copy_from_slice(&200u32.to_le_bytes());
+    std::fs::write(&path, &bytes).unwrap();
+    assert_eq!(Journal::open(&path).err().unwrap(), JournalError::ChecksumMismatch { offset: record_len as u64 });
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), bytes.len() as u64);
+    bytes[record_len..record_len + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(read_journal(&bytes[..]).unwrap_err(), JournalError::InvalidLength { offset: record_len as u64, len: u32::MAX as usize });
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_amend_priority_and_funds() {
+    let mut ex = Exchange::new(pair(), 8, 2);
+    let usdt = Asset::from("USDT");
+    ex.apply(1, &Command::Deposit { user_id: 1, asset: usdt, amount: dec!(1000) }).unwrap();
+    ex.apply(2, &Command::Deposit { user_id: 2, asset: usdt, amount: dec!(1000) }).unwrap();
+    ex.apply(3, &Command::Deposit { user_id: 3, asset: Asset::from("BTC"), amount: dec!(1) }).unwrap();
+    ex.apply(4, &place(1, 1, dec!(100), dec!(2), OrderSide::Bid)).unwrap();
+    ex.apply(5, &place(2, 2, dec!(100), dec!(2), OrderSide::Bid)).unwrap();
+
+    // 只减数量: 保留优先级，多余冻结退回
+    ex.apply(6, &Command::Amend { order_id: 1, price: dec!(100), quantity: dec!(1) }).unwrap();
+    assert_eq!(ex.account().get_balance(1, usdt), (dec!(900), dec!(100)));
+    let trades = ex.apply(7, &place(3, 3, dec!(100), dec!(0.5), OrderSide::Ask)).unwrap();
+    assert_eq!(trades[0].maker_order_id, 1);
+
+    // 加数量: 排到队尾，补冻差额；资金不足时拒绝且不改变任何状态
+    ex.apply(8, &Command::Amend { order_id: 1, price: dec!(100), quantity: dec!(3) }).unwrap();
+    assert_eq!(ex.account().get_balance(1, usdt), (dec!(650), dec!(300)));
+    assert!(ex.apply(9, &Command::Amend { order_id: 1, price: dec!(1000), quantity: dec!(3) }).is_err());
+    assert_eq!(ex.book().order_status(1).unwrap().remaining_quantity, dec!(3));
+    let trades = ex.apply(10, &place(4, 3, dec!(100), dec!(0.5), OrderSide::Ask)).unwrap();
+    assert_eq!(trades[0].maker_order_id, 2);
+}
@@ -0,0 +1,85 @@
+// tests/ledger_test.rs
+
+use rust_decimal_macros::dec;
+use mach_rs::{AccountManager, OrderBook, Order, OrderSide, Asset, LedgerAccount, EntryReason, EntryRef};
+
+#[test]
+fn test_trade_settlement_is_journaled_and_balanced() {
+    let mut account = AccountManager::new();
+    let mut book = OrderBook::new();
+    let btc = Asset::from("BTC");
+    let usdt = Asset::from("USDT");
+
+    account.deposit(1, btc, dec!(5)).unwrap();
+    account.deposit(2, usdt, dec!(1000)).unwrap();
+
+    account.freeze_for_order(1, btc, dec!(2), 11).unwrap();
+    book.match_order(Order { id: 11, user_id: 1, price: dec!(100), quantity: dec!(2), side: OrderSide::Ask });
+    account.freeze_for_order(2, usdt, dec!(200), 12).unwrap();
+    let trades = book.match_order(Order { id: 12, user_id: 2, price: dec!(100), quantity: dec!(2), side: OrderSide::Bid });
+
+    for t in &trades {
+        let notional = t.price * t.quantity;
+        account.settle_debit(t.maker_user_id, btc, t.quantity, t.trade_id).unwrap();
+        account.settle_credit(t.maker_user_id, usdt, notional, t.trade_id).unwrap();
+        account.settle_debit(t.taker_user_id, usdt, notional, t.trade_id).unwrap();
+        account.settle_credit(t.taker_user_id, btc, t.quantity, t.trade_id).unwrap();
+        account.charge_fee(t.taker_user_id, btc, dec!(0.01), t.trade_id).unwrap();
+    }
+
+    assert!(account.verify_ledger().is_ok());
+    assert!(account.ledger().entries().iter().all(|e| e.is_balanced()));
+
+    // 清算科目在买卖双方都结算后归零，手续费进入手续费科目
+    let ledger = account.ledger();
+    assert_eq!(ledger.balance_of(LedgerAccount::Clearing, btc), dec!(0));
+    assert_eq!(ledger.balance_of(LedgerAccount::Clearing, usdt), dec!(0));
+    assert_eq!(ledger.balance_of(LedgerAccount::Fees, btc), dec!(0.01));
+    assert_eq!(ledger.balance_of(LedgerAccount::External, usdt), dec!(-1000));
+
+    // 按用户和资产查询分录
+    let reasons: Vec<_> = ledger.entries_for(2, btc).iter().map(|e| (e.reason, e.reference)).collect();
+    let trade_id = trades[0].trade_id;
+    assert_eq!(reasons, vec![
+        (EntryReason::TradeCredit, EntryRef::Trade(trade_id)),
+        (EntryReason::Fee, EntryRef::Trade(trade_id)),
+    ]);
+    let usdt_entries = ledger.entries_for(2, usdt);
+    assert_eq!(usdt_entries[1].reference, EntryRef::Order(12));
+}
+
+#[test]
+fn test_failed_operations_are_not_journaled() {
+    let mut account = AccountManager::new();
+    let usdt = Asset::from("USDT");
+
+    account.deposit(1, usdt, dec!(10)).unwrap();
+    assert!(account.try_freeze(1, usdt, dec!(11)).is_err());
+    assert!(account.unlock(1, usdt, dec!(1)).is_err());
+
+    assert_eq!(account.ledger().entries().len(), 1);
+    assert!(account.verify_ledger().is_ok());
+}
+
+#[test]
+fn test_pruned_ledger_keeps_running_totals() {
+    let mut account = AccountManager::new();
+    let usdt = Asset::from("USDT");
+
+    account.deposit(1, usdt, dec!(100)).unwrap();
+    account.freeze_for_order(1, usdt, dec!(30), 7).unwrap();
+    account.unlock_for_order(1, usdt, dec!(10), 7).unwrap();
+
+    account.prune_ledger(1);
+    let ledger = account.ledger();
+    assert_eq!(ledger.entries().len(), 1);
+    assert_eq!(ledger.entries_for(1, usdt).iter().map(|e| e.reason).collect::<Vec<_>>(), vec![EntryReason::Unlock]);
+    assert_eq!(ledger.balance_of(LedgerAccount::Available(1), usdt), dec!(80));
+    assert_eq!(ledger.balance_of(LedgerAccount::Frozen(1), usdt), dec!(20));
+    assert!(account.verify_ledger().is_ok());
+
+    // 裁剪后继续记账，按用户查询仍然正确
+    account.deposit(1, usdt, dec!(5)).unwrap();
+    assert_eq!(account.ledger().entries_for(1, usdt).len(), 2);
+    assert!(account.verify_ledger().is_ok());
+}
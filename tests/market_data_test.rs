@@ -0,0 +1,237 @@
+// tests/market_data_test.rs
+
+use std::io::{Cursor, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+use rust_decimal_macros::dec;
+use serde_json::{json, Value};
+use mach_rs::websocket::{self, Frame, Opcode};
+use mach_rs::{Asset, Command, Engine, EngineHandle, Exchange, Instrument, MarketDataServer, Order, OrderSide};
+
+fn pair() -> Instrument {
+    Instrument::new(Asset::from("BTC"), Asset::from("USDT"))
+}
+
+fn order(id: u64, user_id: u64, side: OrderSide, price: u64, quantity: u64) -> Order {
+    Order { id, user_id, price: price.into(), quantity: quantity.into(), side }
+}
+
+// 启动前已有一笔卖单 (101: 100 x 2)，行情服务以此为初始深度
+fn start(queue_capacity: usize) -> (String, EngineHandle) {
+    let mut exchange = Exchange::new(pair(), 8, 2);
+    exchange.apply(1, &Command::Deposit { user_id: 1, asset: Asset::from("BTC"), amount: dec!(10) }).unwrap();
+    exchange.apply(2, &Command::Deposit { user_id: 2, asset: Asset::from("USDT"), amount: dec!(10000000000) }).unwrap();
+    exchange.apply(3, &Command::Place(order(101, 1, OrderSide::Ask, 100, 2))).unwrap();
+
+    let server = MarketDataServer::new(queue_capacity);
+    let feed = server.feed(exchange.instrument(), exchange.book());
+    exchange.add_listener(feed);
+    let (engine, _outputs) = Engine::spawn(exchange, 1024);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || server.serve(listener));
+    (addr, engine.handle())
+}
+
+struct Client {
+    stream: TcpStream,
+}
+
+impl Client {
+    fn connect(addr: &str) -> Self {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+        write!(
+            stream,
+            "GET /ws HTTP/1.1\r\nHost: {addr}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n"
+        )
+        .unwrap();
+        let mut response = Vec::new();
+        let mut byte = [0u8; 1];
+        while !response.ends_with(b"\r\n\r\n") {
+            stream.read_exact(&mut byte).unwrap();
+            response.push(byte[0]);
+        }
+        let response = String::from_utf8(response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 101"), "{response}");
+        assert!(response.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+        Self { stream }
+    }
+
+    fn send(&mut self, request: Value) {
+        websocket::write_frame(&mut self.stream, &Frame::text(request.to_string()), Some([1, 2, 3, 4])).unwrap();
+    }
+
+    fn subscribe(&mut self, channel: &str) -> Value {
+        self.send(json!({ "op": "subscribe", "channel": channel, "instrument": "BTC/USDT" }));
+        self.recv()
+    }
+
+    fn try_recv(&mut self) -> std::io::Result<Value> {
+        let frame = websocket::read_frame(&mut self.stream, 1 << 20, false)?;
+        assert_eq!(frame.opcode, Opcode::Text);
+        Ok(serde_json::from_slice(&frame.payload).unwrap())
+    }
+
+    fn recv(&mut self) -> Value {
+        self.try_recv().unwrap()
+    }
+
+    fn write(&mut self, fin: bool, opcode: Opcode, payload: &[u8]) {
+        websocket::write_frame(&mut self.stream, &Frame { fin, opcode, payload: payload.to_vec() }, Some([1, 2, 3, 4])).unwrap();
+    }
+
+    // 读到 Close 帧后服务端关闭连接，返回其状态码
+    fn closed(&mut self) -> u16 {
+        let frame = websocket::read_frame(&mut self.stream, 1 << 20, false).unwrap();
+        assert_eq!(frame.opcode, Opcode::Close);
+        assert!(websocket::read_frame(&mut self.stream, 1 << 20, false).is_err());
+        u16::from_be_bytes([frame.payload[0], frame.payload[1]])
+    }
+}
+
+#[test]
+fn test_handshake_key_and_frame_round_trip() {
+    assert_eq!(websocket::accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+
+    for len in [0, 125, 126, 65535, 65536] {
+        let frame = Frame { fin: true, opcode: Opcode::Binary, payload: (0..len).map(|i| i as u8).collect() };
+        let mut buf = Vec::new();
+        websocket::write_frame(&mut buf, &frame, Some([9, 8, 7, 6])).unwrap();
+        assert_eq!(websocket::read_frame(&mut Cursor::new(&buf), 1 << 20, true).unwrap(), frame);
+    }
+
+    // 服务端要求客户端帧加掩码，且不接受超长帧
+    let mut unmasked = Vec::new();
+    websocket::write_frame(&mut unmasked, &Frame::text("hi"), None).unwrap();
+    assert!(websocket::read_frame(&mut Cursor::new(&unmasked), 1024, true).is_err());
+    assert!(websocket::read_frame(&mut Cursor::new(&unmasked), 1, false).is_err());
+
+    let mut request = Cursor::new(b"GET / HTTP/1.1\r\nHost: x\r\n\r\n".to_vec());
+    assert!(websocket::accept(&mut request).is_err());
+}
+
+#[test]
+fn test_snapshot_then_sequenced_updates() {
+    let (addr, engine) = start(64);
+    let mut client = Client::connect(&addr);
+
+    let depth = client.subscribe("depth");
+    assert_eq!(depth, json!({
+        "type": "snapshot", "channel": "depth", "instrument": "BTC/USDT", "seq": 0,
+        "bids": [], "asks": [["100", "2"]],
+    }));
+    let ticker = client.subscribe("ticker");
+    assert_eq!((&ticker["bid"], &ticker["ask"]), (&Value::Null, &json!(["100", "2"])));
+    assert_eq!(client.subscribe("trades")["type"], "subscribed");
+
+    // 吃掉 1.5 (部分成交)，剩余的买单挂在 99 之上不成交
+    engine.call(Command::Place(Order { id: 201, user_id: 2, price: dec!(100), quantity: dec!(1.5), side: OrderSide::Bid })).unwrap().unwrap();
+    let trade = client.recv();
+    assert_eq!((&trade["type"], &trade["seq"], &trade["price"], &trade["quantity"], &trade["side"]),
+               (&json!("trade"), &json!(1), &json!("100"), &json!("1.5"), &json!("buy")));
+    let update = client.recv();
+    assert_eq!((&update["seq"], &update["side"], &update["price"], &update["quantity"]), (&json!(1), &json!("ask"), &json!("100"), &json!("0.5")));
+    assert_eq!(client.recv()["ask"], json!(["100", "0.5"]));
+
+    engine.call(Command::Place(order(202, 2, OrderSide::Bid, 99, 3))).unwrap().unwrap();
+    let update = client.recv();
+    assert_eq!((&update["seq"], &update["side"], &update["quantity"]), (&json!(2), &json!("bid"), &json!("3")));
+    assert_eq!(client.recv()["bid"], json!(["99", "3"]));
+
+    // 原地减量与改价都体现为档位变化
+    client.send(json!({ "op": "unsubscribe", "channel": "ticker", "instrument": "BTC/USDT" }));
+    assert_eq!(client.recv()["type"], "unsubscribed");
+    engine.call(Command::Amend { order_id: 202, price: dec!(99), quantity: dec!(1) }).unwrap().unwrap();
+    assert_eq!((&client.recv()["quantity"], ), (&json!("1"), ));
+    engine.call(Command::Amend { order_id: 202, price: dec!(98), quantity: dec!(1) }).unwrap().unwrap();
+    let removed = client.recv();
+    let added = client.recv();
+    assert_eq!((&removed["seq"], &removed["price"], &removed["quantity"]), (&json!(4), &json!("99"), &json!("0")));
+    assert_eq!((&added["seq"], &added["price"], &added["quantity"]), (&json!(5), &json!("98"), &json!("1")));
+
+    engine.call(Command::Cancel { order_id: 101 }).unwrap().unwrap();
+    assert_eq!((&client.recv()["seq"], ), (&json!(6), ));
+
+    // 新订阅者的快照包含到当前序号为止的全部变化
+    let mut late = Client::connect(&addr);
+    assert_eq!(late.subscribe("depth"), json!({
+        "type": "snapshot", "channel": "depth", "instrument": "BTC/USDT", "seq": 6,
+        "bids": [["98", "1"]], "asks": [],
+    }));
+    assert_eq!(late.subscribe("trades")["seq"], 6);
+    assert_eq!(late.subscribe("candles")["type"], "error");
+    late.send(json!({ "op": "subscribe", "channel": "depth", "instrument": "ETH/USDT" }));
+    assert_eq!(late.recv()["type"], "error");
+}
+
+#[test]
+fn test_slow_consumer_is_disconnected() {
+    const ORDERS: u64 = 50_000;
+    const BATCH: u64 = 8;
+    let (addr, engine) = start(16);
+    let mut fast = Client::connect(&addr);
+    let mut slow = Client::connect(&addr);
+    fast.subscribe("depth");
+    slow.subscribe("depth");
+    slow.subscribe("ticker");
+
+    // 买价逐笔抬高，每笔都产生 depth 与 ticker 更新；正常客户端每批读完再继续，积压不超过队列上限
+    let mut seqs = Vec::new();
+    for batch in 0..ORDERS / BATCH {
+        for i in batch * BATCH..(batch + 1) * BATCH {
+            engine.submit(Command::Place(order(1000 + i, 2, OrderSide::Bid, 1 + i, 1))).unwrap();
+        }
+        for _ in 0..BATCH {
+            seqs.push(fast.recv()["seq"].as_u64().unwrap());
+        }
+    }
+    assert_eq!(seqs, (1..=ORDERS).collect::<Vec<_>>(), "正常读取的客户端不受影响");
+
+    // 慢客户端只能读到断开前已写出的部分
+    let mut received = 0;
+    let error = loop {
+        match slow.try_recv() {
+            Ok(_) => received += 1,
+            Err(e) => break e,
+        }
+    };
+    assert!(!matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut), "应被服务端断开: {error}");
+    assert!(received < ORDERS * 2);
+}
+
+#[test]
+fn test_fragmentation_and_utf8_errors_close_the_connection() {
+    let (addr, _engine) = start(64);
+    let request = json!({ "op": "subscribe", "channel": "trades", "instrument": "BTC/USDT" }).to_string();
+    let (head, tail) = request.as_bytes().split_at(10);
+
+    // 分片消息正常拼接
+    let mut client = Client::connect(&addr);
+    client.write(false, Opcode::Text, head);
+    client.write(true, Opcode::Continuation, tail);
+    assert_eq!(client.recv()["type"], "subscribed");
+
+    // 没有进行中的消息时收到续帧
+    let mut client = Client::connect(&addr);
+    client.write(true, Opcode::Continuation, tail);
+    assert_eq!(client.closed(), 1002);
+
+    // 分片消息中途开始新消息
+    let mut client = Client::connect(&addr);
+    client.write(false, Opcode::Text, head);
+    client.write(true, Opcode::Text, request.as_bytes());
+    assert_eq!(client.closed(), 1002);
+
+    // 文本消息不是合法的 UTF-8
+    let mut client = Client::connect(&addr);
+    client.write(true, Opcode::Text, b"{\"op\":\"\xff\"}");
+    assert_eq!(client.closed(), 1007);
+
+    // 客户端主动关闭
+    let mut client = Client::connect(&addr);
+    client.write(true, Opcode::Close, &1000u16.to_be_bytes());
+    assert_eq!(client.closed(), 1000);
+}
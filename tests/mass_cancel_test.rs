@@ -0,0 +1,94 @@
+// tests/mass_cancel_test.rs
+
+use rust_decimal_macros::dec;
+use mach_rs::{AccountManager, OrderBook, Order, OrderSide, Asset, Price};
+
+fn order(id: u64, user_id: u64, price: Price, side: OrderSide) -> Order {
+    Order { id, user_id, price, quantity: dec!(1), side }
+}
+
+#[test]
+fn test_cancel_user_orders_releases_funds() {
+    // 场景: 用户断线，撤掉其全部挂单并逐笔解冻
+    let mut account = AccountManager::new();
+    let mut book = OrderBook::new();
+    let btc = Asset::from("BTC");
+    let usdt = Asset::from("USDT");
+
+    account.deposit(1, btc, dec!(10)).unwrap();
+    account.deposit(1, usdt, dec!(1000)).unwrap();
+
+    account.try_freeze(1, usdt, dec!(90)).unwrap();
+    book.match_order(order(1, 1, dec!(90), OrderSide::Bid));
+    account.try_freeze(1, btc, dec!(1)).unwrap();
+    book.match_order(order(2, 1, dec!(110), OrderSide::Ask));
+    // 其他用户的挂单不受影响
+    book.match_order(order(3, 2, dec!(120), OrderSide::Ask));
+
+    let cancelled = book.cancel_user_orders(1);
+    assert_eq!(cancelled.iter().map(|o| o.id).collect::<Vec<_>>(), vec![1, 2]);
+
+    for o in cancelled {
+        match o.side {
+            OrderSide::Bid => account.unlock(o.user_id, usdt, o.price * o.quantity).unwrap(),
+            OrderSide::Ask => account.unlock(o.user_id, btc, o.quantity).unwrap(),
+        }
+    }
+
+    assert_eq!(account.get_balance(1, usdt), (dec!(1000), dec!(0)));
+    assert_eq!(account.get_balance(1, btc), (dec!(10), dec!(0)));
+    assert_eq!(book.user_order_count(1), 0);
+    assert_eq!(book.user_order_count(2), 1);
+    assert!(book.bids.is_empty());
+}
+
+#[test]
+fn test_cancel_user_side_orders_keeps_other_side() {
+    let mut book = OrderBook::new();
+
+    book.match_order(order(1, 1, dec!(90), OrderSide::Bid));
+    book.match_order(order(2, 1, dec!(91), OrderSide::Bid));
+    book.match_order(order(3, 1, dec!(110), OrderSide::Ask));
+
+    let cancelled = book.cancel_user_side_orders(1, OrderSide::Bid);
+    assert_eq!(cancelled.len(), 2);
+    assert!(book.bids.is_empty());
+    assert_eq!(book.user_order_count(1), 1);
+
+    // 已撤订单不能重复撤
+    assert!(book.cancel_order(1).is_err());
+    assert!(book.cancel_order(3).is_ok());
+}
+
+#[test]
+fn test_cancel_price_range() {
+    let mut book = OrderBook::new();
+
+    book.match_order(order(1, 1, dec!(95), OrderSide::Bid));
+    book.match_order(order(2, 2, dec!(99), OrderSide::Bid));
+    book.match_order(order(3, 3, dec!(101), OrderSide::Ask));
+    book.match_order(order(4, 4, dec!(105), OrderSide::Ask));
+
+    // 只撤卖盘
+    let cancelled = book.cancel_price_range(Some(OrderSide::Ask), dec!(100)..=dec!(110));
+    assert_eq!(cancelled.iter().map(|o| o.id).collect::<Vec<_>>(), vec![3, 4]);
+    assert_eq!(book.bids.len(), 2);
+
+    // 两边都撤
+    let cancelled = book.cancel_price_range(None, dec!(98)..);
+    assert_eq!(cancelled.iter().map(|o| o.id).collect::<Vec<_>>(), vec![2]);
+    assert_eq!(book.user_order_count(2), 0);
+    assert_eq!(book.user_order_count(1), 1);
+}
+
+#[test]
+fn test_filled_maker_leaves_user_index() {
+    let mut book = OrderBook::new();
+
+    book.match_order(order(1, 1, dec!(100), OrderSide::Ask));
+    let trades = book.match_order(order(2, 2, dec!(100), OrderSide::Bid));
+    assert_eq!(trades.len(), 1);
+
+    assert_eq!(book.user_order_count(1), 0);
+    assert!(book.cancel_user_orders(1).is_empty());
+}
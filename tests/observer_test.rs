@@ -0,0 +1,97 @@
+// tests/observer_test.rs
+
+use std::sync::{Arc, Mutex};
+use rust_decimal_macros::dec;
+use mach_rs::{AccountManager, OrderBook, Order, OrderSide, Asset};
+use mach_rs::{EventListener, BalanceEvent, BalanceEventKind, OrderEvent};
+
+#[derive(Default, Clone)]
+struct Recorder {
+    balances: Arc<Mutex<Vec<BalanceEvent>>>,
+    orders: Arc<Mutex<Vec<OrderEvent>>>,
+}
+
+impl EventListener for Recorder {
+    fn on_balance(&mut self, event: &BalanceEvent) {
+        self.balances.lock().unwrap().push(event.clone());
+    }
+    fn on_order(&mut self, event: &OrderEvent) {
+        self.orders.lock().unwrap().push(event.clone());
+    }
+}
+
+#[test]
+fn test_balance_events_report_post_change_balances() {
+    let recorder = Recorder::default();
+    let mut account = AccountManager::new();
+    account.add_listener(Box::new(recorder.clone()));
+    let usdt = Asset::from("USDT");
+
+    account.deposit(1, usdt, dec!(100)).unwrap();
+    account.try_freeze(1, usdt, dec!(40)).unwrap();
+    account.unlock(1, usdt, dec!(10)).unwrap();
+    account.confirm_trade(1, usdt, dec!(30)).unwrap();
+    // 失败的操作不产生事件
+    assert!(account.try_freeze(1, usdt, dec!(1000)).is_err());
+
+    let events = recorder.balances.lock().unwrap();
+    let kinds: Vec<_> = events.iter().map(|e| e.kind).collect();
+    assert_eq!(kinds, vec![
+        BalanceEventKind::Deposit,
+        BalanceEventKind::Freeze,
+        BalanceEventKind::Unlock,
+        BalanceEventKind::ConfirmTrade,
+    ]);
+    assert_eq!((events[1].available, events[1].frozen), (dec!(60), dec!(40)));
+    assert_eq!((events[3].available, events[3].frozen), (dec!(70), dec!(0)));
+}
+
+#[test]
+fn test_order_events() {
+    let recorder = Recorder::default();
+    let mut book = OrderBook::new();
+    book.add_listener(Box::new(recorder.clone()));
+
+    book.match_order(Order { id: 1, user_id: 1, price: dec!(100), quantity: dec!(2), side: OrderSide::Ask });
+    book.match_order(Order { id: 2, user_id: 2, price: dec!(100), quantity: dec!(1), side: OrderSide::Bid });
+    book.cancel_order(1).unwrap();
+
+    let events = recorder.orders.lock().unwrap();
+    assert_eq!(events.len(), 3);
+    assert!(matches!(&events[0], OrderEvent::Rested(o) if o.id == 1));
+    assert!(matches!(&events[1], OrderEvent::Trade(t) if t.maker_order_id == 1 && t.quantity == dec!(1)));
+    assert!(matches!(&events[2], OrderEvent::Cancelled(o) if o.id == 1 && o.quantity == dec!(1)));
+}
+
+#[test]
+fn test_amend_events() {
+    let recorder = Recorder::default();
+    let mut book = OrderBook::new();
+    book.add_listener(Box::new(recorder.clone()));
+
+    book.match_order(Order { id: 1, user_id: 1, price: dec!(100), quantity: dec!(3), side: OrderSide::Bid });
+    book.amend_order(1, dec!(100), dec!(2)).unwrap();
+    book.amend_order(1, dec!(101), dec!(2)).unwrap();
+
+    let events = recorder.orders.lock().unwrap();
+    assert_eq!(events.len(), 4);
+    assert!(matches!(&events[1], OrderEvent::Reduced(o) if o.quantity == dec!(2)));
+    assert!(matches!(&events[2], OrderEvent::Replaced(o) if o.price == dec!(100) && o.quantity == dec!(2)));
+    assert!(matches!(&events[3], OrderEvent::Rested(o) if o.price == dec!(101)));
+}
+
+#[test]
+fn test_trades_are_emitted_before_taker_rests() {
+    let recorder = Recorder::default();
+    let mut book = OrderBook::new();
+    book.add_listener(Box::new(recorder.clone()));
+
+    book.match_order(Order { id: 1, user_id: 1, price: dec!(100), quantity: dec!(1), side: OrderSide::Ask });
+    book.match_order(Order { id: 2, user_id: 2, price: dec!(101), quantity: dec!(3), side: OrderSide::Bid });
+
+    // 吃掉卖单后剩余部分才进入订单簿，监听方不会看到交叉的盘口
+    let events = recorder.orders.lock().unwrap();
+    assert_eq!(events.len(), 3);
+    assert!(matches!(&events[1], OrderEvent::Trade(t) if t.taker_order_id == 2));
+    assert!(matches!(&events[2], OrderEvent::Rested(o) if o.id == 2 && o.quantity == dec!(2)));
+}
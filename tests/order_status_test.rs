@@ -0,0 +1,108 @@
+// tests/order_status_test.rs
+
+use rust_decimal_macros::dec;
+use mach_rs::{OrderBook, Order, OrderSide, OrderStatus, EngineError};
+
+#[test]
+fn test_status_lifecycle_with_average_price() {
+    let mut book = OrderBook::new();
+
+    book.match_order(Order { id: 1, user_id: 1, price: dec!(100), quantity: dec!(1), side: OrderSide::Ask });
+    book.match_order(Order { id: 2, user_id: 1, price: dec!(102), quantity: dec!(1), side: OrderSide::Ask });
+
+    // 买 3 个 @ 105: 成交 100 和 102 两档，剩余 1 个挂单
+    book.match_order(Order { id: 3, user_id: 2, price: dec!(105), quantity: dec!(3), side: OrderSide::Bid });
+
+    let report = book.order_status(3).unwrap();
+    assert_eq!(report.status, OrderStatus::PartiallyFilled);
+    assert_eq!(report.filled_quantity, dec!(2));
+    assert_eq!(report.remaining_quantity, dec!(1));
+    assert_eq!(report.average_price, Some(dec!(101)));
+
+    let maker = book.order_status(1).unwrap();
+    assert_eq!(maker.status, OrderStatus::Filled);
+    assert_eq!(maker.remaining_quantity, dec!(0));
+    assert_eq!(maker.average_price, Some(dec!(100)));
+
+    // 撤掉剩余部分后，累计成交仍保留
+    book.cancel_order(3).unwrap();
+    let report = book.order_status(3).unwrap();
+    assert_eq!(report.status, OrderStatus::Cancelled);
+    assert_eq!(report.filled_quantity, dec!(2));
+    assert_eq!(report.remaining_quantity, dec!(0));
+
+    assert!(book.order_status(999).is_none());
+}
+
+#[test]
+fn test_open_orders_for_user() {
+    let mut book = OrderBook::new();
+
+    book.match_order(Order { id: 5, user_id: 1, price: dec!(90), quantity: dec!(1), side: OrderSide::Bid });
+    book.match_order(Order { id: 4, user_id: 1, price: dec!(110), quantity: dec!(2), side: OrderSide::Ask });
+    book.match_order(Order { id: 6, user_id: 2, price: dec!(89), quantity: dec!(1), side: OrderSide::Bid });
+
+    let open = book.open_orders(1);
+    assert_eq!(open.iter().map(|r| r.order_id).collect::<Vec<_>>(), vec![4, 5]);
+    assert!(open.iter().all(|r| r.status == OrderStatus::Open && r.average_price.is_none()));
+    assert!(book.open_orders(3).is_empty());
+}
+
+#[test]
+fn test_terminal_retention_is_bounded() {
+    let mut book = OrderBook::with_retention(2);
+
+    for id in 1..=3 {
+        book.match_order(Order { id, user_id: 1, price: dec!(100), quantity: dec!(1), side: OrderSide::Bid });
+        book.cancel_order(id).unwrap();
+    }
+
+    // 最早的终态订单被淘汰
+    assert!(book.order_status(1).is_none());
+    assert_eq!(book.order_status(2).unwrap().status, OrderStatus::Cancelled);
+    assert_eq!(book.order_status(3).unwrap().status, OrderStatus::Cancelled);
+}
+
+#[test]
+fn test_evicted_order_ids_cannot_be_reused() {
+    let mut book = OrderBook::with_retention(1);
+    for id in 1..=2 {
+        book.place_order(Order { id, user_id: 1, price: dec!(100), quantity: dec!(1), side: OrderSide::Bid }).unwrap();
+        book.cancel_order(id).unwrap();
+    }
+    assert!(book.order_status(1).is_none());
+    let reuse = book.place_order(Order { id: 1, user_id: 2, price: dec!(100), quantity: dec!(1), side: OrderSide::Bid });
+    assert_eq!(reuse, Err(EngineError::DuplicateOrderId { order_id: 1 }));
+
+    // 只拒绝确实用过的订单号，小于被淘汰订单号但未使用的仍可下单
+    let mut book = OrderBook::with_retention(1);
+    for id in [5, 9] {
+        book.place_order(Order { id, user_id: 1, price: dec!(100), quantity: dec!(1), side: OrderSide::Bid }).unwrap();
+        book.cancel_order(id).unwrap();
+    }
+    assert!(book.order_status(5).is_none());
+    assert!(book.place_order(Order { id: 3, user_id: 1, price: dec!(100), quantity: dec!(1), side: OrderSide::Bid }).is_ok());
+
+    // 不保留终态记录时同样拒绝已结束的订单号
+    let mut book = OrderBook::with_retention(0);
+    book.place_order(Order { id: 7, user_id: 1, price: dec!(100), quantity: dec!(1), side: OrderSide::Bid }).unwrap();
+    book.cancel_order(7).unwrap();
+    let reuse = book.place_order(Order { id: 7, user_id: 1, price: dec!(100), quantity: dec!(1), side: OrderSide::Bid });
+    assert_eq!(reuse, Err(EngineError::DuplicateOrderId { order_id: 7 }));
+    assert!(book.place_order(Order { id: 6, user_id: 1, price: dec!(100), quantity: dec!(1), side: OrderSide::Bid }).is_ok());
+}
+
+#[test]
+fn test_replace_quantity_counts_actual_fills() {
+    let mut book = OrderBook::new();
+    book.match_order(Order { id: 1, user_id: 1, price: dec!(100), quantity: dec!(3), side: OrderSide::Ask });
+    book.match_order(Order { id: 2, user_id: 2, price: dec!(100), quantity: dec!(2), side: OrderSide::Bid });
+
+    // 新的订单总量扣除已成交的 2 即为剩余数量
+    assert_eq!(book.replace_quantity(1, dec!(5)), Ok(dec!(3)));
+    assert_eq!(
+        book.replace_quantity(1, dec!(2)),
+        Err(EngineError::QuantityNotAboveFilled { order_id: 1, quantity: dec!(2), filled: dec!(2) })
+    );
+    assert_eq!(book.replace_quantity(2, dec!(5)), Err(EngineError::OrderNotFound { order_id: 2 }));
+}
@@ -0,0 +1,160 @@
+// tests/pipeline_test.rs
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use rust_decimal_macros::dec;
+use mach_rs::{Asset, Command, Exchange, Instrument, Journal, JournalError, Order, OrderSide, PipelineBuilder};
+use mach_rs::journal::{read_journal, replay};
+use mach_rs::ring::ring_buffer;
+
+fn pair() -> Instrument {
+    Instrument::new(Asset::from("BTC"), Asset::from("USDT"))
+}
+
+#[test]
+fn test_ring_buffer_consumers_see_every_item_in_order() {
+    let mut producer = ring_buffer::<u64>(8);
+    let first = producer.subscribe(&[]);
+    let seen_by_first = Arc::new(Mutex::new(0));
+    let second = producer.subscribe(&[&first]);
+
+    let counter = seen_by_first.clone();
+    let a = thread::spawn(move || {
+        let mut expected = 0;
+        first.run(|seq, &v| {
+            assert_eq!((seq, v), (expected, expected * 10));
+            expected += 1;
+            *counter.lock().unwrap() = expected;
+        });
+        expected
+    });
+    let counter = seen_by_first.clone();
+    let b = thread::spawn(move || {
+        let mut expected = 0;
+        second.run(|seq, &v| {
+            // 依赖 first: 只能看到 first 已处理过的序号
+            assert!(seq < *counter.lock().unwrap());
+            assert_eq!(v, seq * 10);
+            expected += 1;
+        });
+        expected
+    });
+
+    for i in 0..10_000u64 {
+        assert_eq!(producer.publish(i * 10), i);
+    }
+    drop(producer);
+    assert_eq!(a.join().unwrap(), 10_000);
+    assert_eq!(b.join().unwrap(), 10_000);
+}
+
+#[test]
+fn test_try_publish_respects_slowest_consumer() {
+    let mut producer = ring_buffer::<u32>(3);
+    assert_eq!(producer.capacity(), 4);
+    let mut consumer = producer.subscribe(&[]);
+    for i in 0..4 {
+        producer.try_publish(i).unwrap();
+    }
+    assert_eq!(producer.try_publish(9), Err(9));
+    assert_eq!(consumer.poll(|_, _| {}), 4);
+    assert_eq!(producer.try_publish(9), Ok(4));
+}
+
+#[test]
+fn test_pipeline_journals_matches_and_fans_out() {
+    let path = std::env::temp_dir().join(format!("mach_pipeline_{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let (journal, _) = Journal::open(&path).unwrap();
+
+    let trades = Arc::new(Mutex::new(Vec::new()));
+    let seqs = Arc::new(Mutex::new(Vec::new()));
+    let (t, s) = (trades.clone(), seqs.clone());
+    let mut pipeline = PipelineBuilder::new(Exchange::new(pair(), 8, 2), 4)
+        .journal(journal)
+        .unwrap()
+        .consumer(move |p| {
+            if let Ok(fills) = &p.outcome {
+                t.lock().unwrap().extend(fills.iter().cloned());
+            }
+        })
+        .consumer(move |p| s.lock().unwrap().push(p.seq))
+        .spawn();
+
+    pipeline.publish(Command::Deposit { user_id: 1, asset: Asset::from("BTC"), amount: dec!(10) }).unwrap();
+    pipeline.publish(Command::Deposit { user_id: 2, asset: Asset::from("USDT"), amount: dec!(1000) }).unwrap();
+    // 非正金额的充值在定序前拒绝，不占用序号
+    assert!(pipeline.publish(Command::Deposit { user_id: 2, asset: Asset::from("USDT"), amount: dec!(0) }).is_err());
+    for id in 0..10 {
+        let (user_id, side) = if id % 2 == 0 { (1, OrderSide::Ask) } else { (2, OrderSide::Bid) };
+        pipeline.publish(Command::Place(Order { id, user_id, price: dec!(100), quantity: dec!(1), side })).unwrap();
+    }
+    assert_eq!(pipeline.journal_error(), None);
+    let live = pipeline.shutdown();
+
+    assert_eq!(live.last_seq(), 12);
+    assert_eq!(*seqs.lock().unwrap(), (1..=12).collect::<Vec<_>>());
+    assert_eq!(trades.lock().unwrap().len(), 5);
+
+    let records = read_journal(std::fs::File::open(&path).unwrap()).unwrap();
+    let mut rebuilt = Exchange::new(pair(), 8, 2);
+    replay(&records, &mut rebuilt).unwrap();
+    assert_eq!(rebuilt.account().balances_of(2), live.account().balances_of(2));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_journal_must_continue_exchange_sequence() {
+    let journal = Journal::resume(Vec::new(), 5);
+    let err = PipelineBuilder::new(Exchange::new(pair(), 8, 2), 4).journal(journal).err().unwrap();
+    assert_eq!(err, JournalError::SequenceGap { expected: 1, found: 6 });
+}
+
+#[test]
+fn test_dropped_consumer_no_longer_gates_the_producer() {
+    let mut producer = ring_buffer::<u32>(4);
+    let mut live = producer.subscribe(&[]);
+    let dropped = producer.subscribe(&[]);
+    for i in 0..4 {
+        producer.publish(i);
+    }
+    assert_eq!(live.poll(|_, _| {}), 4);
+    assert_eq!(producer.try_publish(4), Err(4));
+
+    // 释放后只等仍在运行的消费者
+    drop(dropped);
+    assert_eq!(producer.try_publish(4), Ok(4));
+}
+
+#[test]
+fn test_panicked_consumer_does_not_hang_the_pipeline() {
+    let mut pipeline = PipelineBuilder::new(Exchange::new(pair(), 8, 2), 4)
+        .consumer(|_| panic!("下游消费者异常"))
+        .spawn();
+    for user_id in 0..64 {
+        pipeline.publish(Command::Deposit { user_id, asset: Asset::from("BTC"), amount: dec!(1) }).unwrap();
+    }
+    let shutdown = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| pipeline.shutdown()));
+    assert!(shutdown.is_err(), "异常退出的线程在关闭时报告");
+}
+
+#[test]
+fn test_consumer_stops_when_its_upstream_exits() {
+    let mut producer = ring_buffer::<u32>(4);
+    let mut upstream = producer.subscribe(&[]);
+    let downstream = producer.subscribe(&[&upstream]);
+    producer.publish(1);
+    producer.publish(2);
+    upstream.poll(|_, _| {});
+    producer.publish(3);
+    drop(upstream);
+
+    let seen = thread::spawn(move || {
+        let mut seen = Vec::new();
+        downstream.run(|_, &v| seen.push(v));
+        seen
+    });
+    // 生产者仍然打开，下游处理完上游已处理的部分后退出
+    assert_eq!(seen.join().unwrap(), vec![1, 2]);
+    drop(producer);
+}
@@ -0,0 +1,69 @@
+// tests/rate_limit_test.rs
+
+use mach_rs::{Asset, Instrument, RateLimiter, RateLimit, RateLimitConfig, RateLimitScope, RequestKind, ManualClock};
+
+fn config() -> RateLimitConfig {
+    RateLimitConfig {
+        // 用户: 突发 2 笔新单，每 100ms 补 1 个
+        user_orders: RateLimit { capacity: 2, refill_millis: 100 },
+        user_cancels: RateLimit { capacity: 1, refill_millis: 50 },
+        instrument_orders: RateLimit { capacity: 3, refill_millis: 100 },
+        instrument_cancels: RateLimit { capacity: 10, refill_millis: 10 },
+    }
+}
+
+fn btc_usdt() -> Instrument {
+    Instrument::new(Asset::from("BTC"), Asset::from("USDT"))
+}
+
+#[test]
+fn test_user_bucket_refills_on_clock() {
+    let mut limiter = RateLimiter::new(config(), ManualClock::new(0));
+    let pair = btc_usdt();
+
+    assert!(limiter.check_new_order(1, pair).is_ok());
+    assert!(limiter.check_new_order(1, pair).is_ok());
+
+    let rejected = limiter.check_new_order(1, pair).unwrap_err();
+    assert_eq!(rejected.scope, RateLimitScope::User);
+    assert_eq!(rejected.kind, RequestKind::NewOrder);
+    assert_eq!(rejected.retry_after_millis, 100);
+
+    limiter.clock().advance(40);
+    assert_eq!(limiter.check_new_order(1, pair).unwrap_err().retry_after_millis, 60);
+
+    limiter.clock().advance(60);
+    assert!(limiter.check_new_order(1, pair).is_ok());
+}
+
+#[test]
+fn test_cancels_have_separate_budget() {
+    let mut limiter = RateLimiter::new(config(), ManualClock::new(0));
+    let pair = btc_usdt();
+
+    assert!(limiter.check_new_order(1, pair).is_ok());
+    assert!(limiter.check_new_order(1, pair).is_ok());
+    // 新单额度用完不影响撤单
+    assert!(limiter.check_cancel(1, pair).is_ok());
+    let rejected = limiter.check_cancel(1, pair).unwrap_err();
+    assert_eq!(rejected.kind, RequestKind::Cancel);
+    assert_eq!(rejected.retry_after_millis, 50);
+}
+
+#[test]
+fn test_instrument_bucket_shared_across_users() {
+    let mut limiter = RateLimiter::new(config(), ManualClock::new(0));
+    let pair = btc_usdt();
+    let other = Instrument::new(Asset::from("ETH"), Asset::from("USDT"));
+
+    assert!(limiter.check_new_order(1, pair).is_ok());
+    assert!(limiter.check_new_order(2, pair).is_ok());
+    assert!(limiter.check_new_order(3, pair).is_ok());
+
+    let rejected = limiter.check_new_order(4, pair).unwrap_err();
+    assert_eq!(rejected.scope, RateLimitScope::Instrument);
+    // 其他交易对不受影响
+    assert!(limiter.check_new_order(4, other).is_ok());
+    // 被交易对维度拒绝的请求不消耗用户额度
+    assert!(limiter.check_new_order(4, other).is_ok());
+}
@@ -0,0 +1,94 @@
+// tests/registry_test.rs
+
+use rust_decimal_macros::dec;
+use mach_rs::{AccountError, AccountManager, Asset, AssetOperation, AssetRegistry, AssetStatus, Error, RegistryError, WithdrawalManager};
+use mach_rs::registry::parse_symbol;
+
+fn registry() -> AssetRegistry {
+    let mut registry = AssetRegistry::new();
+    registry.register("BTC", "Bitcoin", 8).unwrap();
+    registry.register("USDT", "Tether USD", 6).unwrap();
+    registry
+}
+
+#[test]
+fn test_symbol_validation_rejects_truncation() {
+    let mut registry = registry();
+
+    // 以前 "USDT_ERC20" 会被截断成 "USDT_ERC"，与真正的 USDT_ERC 冲突
+    assert_eq!(
+        registry.register("USDT_ERC20", "Tether (ERC20)", 6).unwrap_err(),
+        RegistryError::SymbolTooLong { symbol: "USDT_ERC20".to_string() }
+    );
+    assert!(matches!(parse_symbol("比特"), Err(RegistryError::InvalidSymbol { .. })));
+    assert!(matches!(parse_symbol("btc"), Err(RegistryError::InvalidSymbol { .. })));
+    assert!(matches!(parse_symbol(""), Err(RegistryError::InvalidSymbol { .. })));
+    assert_eq!(parse_symbol("USDT_ERC").unwrap(), Asset::from("USDT_ERC"));
+
+    assert_eq!(registry.register("BTC", "Bitcoin", 8).unwrap_err(), RegistryError::DuplicateAsset { asset: Asset::from("BTC") });
+    assert!(matches!(registry.register("ETH", "Ether", 29), Err(RegistryError::InvalidPrecision { .. })));
+
+    let info = registry.lookup("USDT").unwrap();
+    assert_eq!((info.name.as_str(), info.precision, info.status), ("Tether USD", 6, AssetStatus::Enabled));
+    assert!(registry.lookup("USDT_ERC20").is_none());
+    assert_eq!(registry.assets().map(|a| a.asset.as_str()).collect::<Vec<_>>(), vec!["BTC", "USDT"]);
+
+    // 直接构造 Asset 仍然截断，但不会切断多字节字符；外部输入用 try_new 拒绝截断
+    assert_eq!(Asset::new("比特币").as_str(), "比特");
+    assert_eq!(Asset::try_new("USDT_ERC20"), Err(RegistryError::SymbolTooLong { symbol: "USDT_ERC20".to_string() }));
+    assert_eq!(Asset::try_new("USDT_ERC"), Ok(Asset::from("USDT_ERC")));
+
+    // 空符号、NUL、空白与其他不可打印字符一律拒绝
+    for symbol in ["", "BT\0C", "\0", "BT C", "BTC\n", "\u{7f}", "比特"] {
+        assert_eq!(Asset::try_new(symbol), Err(RegistryError::InvalidSymbol { symbol: symbol.to_string() }));
+    }
+}
+
+#[test]
+fn test_account_rejects_unknown_asset_and_excess_precision() {
+    let mut account = AccountManager::with_registry(registry());
+    let usdt = Asset::from("USDT");
+    let doge = Asset::from("DOGE");
+
+    assert_eq!(account.deposit(1, doge, dec!(1)).unwrap_err(), AccountError::UnknownAsset { user_id: 1, asset: doge });
+    assert!(matches!(
+        account.deposit(1, usdt, dec!(1.0000001)),
+        Err(AccountError::ExcessPrecision { precision: 6, .. })
+    ));
+    // 末尾的 0 不算精度
+    account.deposit(1, usdt, dec!(1.500000000)).unwrap();
+    assert!(account.settle_credit(2, doge, dec!(1), 1).is_err());
+    assert_eq!(account.balances_of(1).unwrap().len(), 1);
+
+    let err = Error::from(account.deposit(1, doge, dec!(1)).unwrap_err());
+    assert_eq!(err.code(), "UNKNOWN_ASSET");
+}
+
+#[test]
+fn test_asset_status_gates_operations() {
+    let mut account = AccountManager::with_registry(registry());
+    let mut withdrawals = WithdrawalManager::new();
+    let btc = Asset::from("BTC");
+
+    account.deposit(1, btc, dec!(2)).unwrap();
+    account.try_freeze(1, btc, dec!(1)).unwrap();
+
+    account.registry_mut().unwrap().set_status(btc, AssetStatus::WithdrawDisabled).unwrap();
+    assert!(matches!(
+        withdrawals.request(&mut account, 1, btc, dec!(0.5), "w-1"),
+        Err(mach_rs::WithdrawalError::Account(AccountError::AssetNotAllowed { operation: AssetOperation::Withdraw, .. }))
+    ));
+    account.transfer(1, 2, btc, dec!(0.1), 1).unwrap();
+
+    account.registry_mut().unwrap().set_status(btc, AssetStatus::DepositOnly).unwrap();
+    account.deposit(1, btc, dec!(1)).unwrap();
+    assert!(account.try_freeze(1, btc, dec!(0.1)).is_err());
+    assert!(account.transfer(1, 2, btc, dec!(0.1), 2).is_err());
+
+    account.registry_mut().unwrap().set_status(btc, AssetStatus::Disabled).unwrap();
+    assert!(account.deposit(1, btc, dec!(1)).is_err());
+    // 下架后已有挂单仍可撤单解冻
+    account.unlock(1, btc, dec!(1)).unwrap();
+    assert_eq!(account.get_balance(1, btc), (dec!(2.9), dec!(0)));
+    account.verify_ledger().unwrap();
+}
@@ -0,0 +1,107 @@
+// tests/risk_test.rs
+
+use std::collections::HashMap;
+use rust_decimal_macros::dec;
+use mach_rs::{OrderBook, Order, OrderSide, Asset, Instrument, RiskEngine, RiskLimits, RiskRejection};
+
+fn pair() -> Instrument {
+    Instrument::new(Asset::from("BTC"), Asset::from("USDT"))
+}
+
+// 风控通过后撮合，并同步计数器
+fn submit(risk: &mut RiskEngine, book: &mut OrderBook, order: Order) -> Result<(), RiskRejection> {
+    risk.check(&order)?;
+    let trades = book.match_order(order.clone());
+    risk.on_order_matched(&order, &trades);
+    Ok(())
+}
+
+#[test]
+fn test_max_open_orders_released_by_fill_and_cancel() {
+    let mut book = OrderBook::new();
+    let limits = RiskLimits { max_open_orders: Some(2), ..Default::default() };
+    let mut risk = RiskEngine::new(pair(), limits);
+
+    submit(&mut risk, &mut book, Order { id: 1, user_id: 1, price: dec!(100), quantity: dec!(1), side: OrderSide::Ask }).unwrap();
+    submit(&mut risk, &mut book, Order { id: 2, user_id: 1, price: dec!(101), quantity: dec!(1), side: OrderSide::Ask }).unwrap();
+
+    let third = Order { id: 3, user_id: 1, price: dec!(102), quantity: dec!(1), side: OrderSide::Ask };
+    assert_eq!(risk.check(&third), Err(RiskRejection::MaxOpenOrders { limit: 2, current: 2 }));
+
+    // 订单 1 被吃掉后额度释放
+    submit(&mut risk, &mut book, Order { id: 10, user_id: 2, price: dec!(100), quantity: dec!(1), side: OrderSide::Bid }).unwrap();
+    assert_eq!(risk.open_order_count(1), 1);
+    assert_eq!(risk.open_order_count(2), 0);
+    submit(&mut risk, &mut book, third).unwrap();
+
+    // 撤单后额度释放
+    let cancelled = book.cancel_order(2).unwrap();
+    risk.on_order_cancelled(&cancelled);
+    assert_eq!(risk.open_order_count(1), 1);
+}
+
+#[test]
+fn test_notional_and_price_deviation() {
+    let limits = RiskLimits {
+        max_order_notional: Some(dec!(1000)),
+        max_price_deviation: Some(dec!(0.1)),
+        ..Default::default()
+    };
+    let mut risk = RiskEngine::new(pair(), limits);
+
+    let big = Order { id: 1, user_id: 1, price: dec!(100), quantity: dec!(11), side: OrderSide::Bid };
+    assert_eq!(risk.check(&big), Err(RiskRejection::MaxOrderNotional { limit: dec!(1000), notional: dec!(1100) }));
+
+    // 没有参考价时不检查偏离
+    let far = Order { id: 2, user_id: 1, price: dec!(120), quantity: dec!(1), side: OrderSide::Bid };
+    assert!(risk.check(&far).is_ok());
+
+    risk.set_reference_price(dec!(100));
+    assert!(matches!(risk.check(&far), Err(RiskRejection::PriceDeviation { .. })));
+    let near = Order { id: 3, user_id: 1, price: dec!(110), quantity: dec!(1), side: OrderSide::Bid };
+    assert!(risk.check(&near).is_ok());
+}
+
+#[test]
+fn test_max_position_counts_fills_and_resting_orders() {
+    let btc = Asset::from("BTC");
+    let mut book = OrderBook::new();
+    let limits = RiskLimits { max_position: HashMap::from([(btc, dec!(5))]), ..Default::default() };
+    let mut risk = RiskEngine::new(pair(), limits);
+
+    submit(&mut risk, &mut book, Order { id: 1, user_id: 2, price: dec!(100), quantity: dec!(3), side: OrderSide::Ask }).unwrap();
+    // 用户 1 买 3 个，全部成交，持仓 3
+    submit(&mut risk, &mut book, Order { id: 2, user_id: 1, price: dec!(100), quantity: dec!(3), side: OrderSide::Bid }).unwrap();
+    assert_eq!(risk.position(1, btc), dec!(3));
+    assert_eq!(risk.position(2, btc), dec!(-3));
+
+    // 再挂 2 个买单，预估持仓达到 5
+    submit(&mut risk, &mut book, Order { id: 3, user_id: 1, price: dec!(90), quantity: dec!(2), side: OrderSide::Bid }).unwrap();
+    let more = Order { id: 4, user_id: 1, price: dec!(90), quantity: dec!(1), side: OrderSide::Bid };
+    assert!(matches!(risk.check(&more), Err(RiskRejection::MaxPosition { projected, .. }) if projected == dec!(6)));
+
+    // 反方向的卖单降低持仓，不受影响
+    let sell = Order { id: 5, user_id: 1, price: dec!(200), quantity: dec!(3), side: OrderSide::Ask };
+    assert!(risk.check(&sell).is_ok());
+
+    let cancelled = book.cancel_order(3).unwrap();
+    risk.on_order_cancelled(&cancelled);
+    assert!(risk.check(&more).is_ok());
+}
+
+#[test]
+fn test_daily_volume_resets_on_new_day() {
+    let mut book = OrderBook::new();
+    let limits = RiskLimits { max_daily_volume: Some(dec!(250)), ..Default::default() };
+    let mut risk = RiskEngine::new(pair(), limits);
+
+    submit(&mut risk, &mut book, Order { id: 1, user_id: 2, price: dec!(100), quantity: dec!(2), side: OrderSide::Ask }).unwrap();
+    submit(&mut risk, &mut book, Order { id: 2, user_id: 1, price: dec!(100), quantity: dec!(2), side: OrderSide::Bid }).unwrap();
+    assert_eq!(risk.daily_volume(1), dec!(200));
+
+    let next = Order { id: 3, user_id: 1, price: dec!(100), quantity: dec!(1), side: OrderSide::Bid };
+    assert_eq!(risk.check(&next), Err(RiskRejection::MaxDailyVolume { limit: dec!(250), projected: dec!(300) }));
+
+    risk.start_new_day();
+    assert!(risk.check(&next).is_ok());
+}
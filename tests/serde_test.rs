@@ -0,0 +1,49 @@
+// tests/serde_test.rs
+#![cfg(feature = "serde")]
+
+use rust_decimal_macros::dec;
+use mach_rs::{Asset, Balance, Order, OrderBook, OrderSide, OrderStatus, TradeEvent};
+
+#[test]
+fn test_decimal_and_asset_encoded_as_strings() {
+    let order = Order { id: 7, user_id: 1, price: dec!(0.1000000000000000000000000001), quantity: dec!(2.50), side: OrderSide::Bid };
+    let json = serde_json::to_value(&order).unwrap();
+    assert_eq!(json["price"], "0.1000000000000000000000000001");
+    assert_eq!(json["quantity"], "2.50");
+    assert_eq!(json["side"], "Bid");
+
+    let back: Order = serde_json::from_value(json).unwrap();
+    assert_eq!((back.price, back.quantity, back.side), (order.price, order.quantity, order.side));
+
+    let balance = Balance { available: dec!(1.23456789), frozen: dec!(0), ..Default::default() };
+    let back: Balance = serde_json::from_str(&serde_json::to_string(&balance).unwrap()).unwrap();
+    assert_eq!(back, balance);
+
+    assert_eq!(serde_json::to_string(&Asset::from("USDT")).unwrap(), "\"USDT\"");
+    assert_eq!(serde_json::from_str::<Asset>("\"BTC\"").unwrap(), Asset::from("BTC"));
+    // 超长符号不会被截断
+    assert!(serde_json::from_str::<Asset>("\"USDT_ERC20\"").is_err());
+    assert!(serde_json::from_str::<Asset>("\"\"").is_err());
+    assert!(serde_json::from_str::<Asset>("\"BT\\u0000C\"").is_err());
+}
+
+#[test]
+fn test_order_book_state_round_trip() {
+    let mut book = OrderBook::new();
+    book.match_order(Order { id: 1, user_id: 1, price: dec!(100), quantity: dec!(2), side: OrderSide::Ask });
+    book.match_order(Order { id: 2, user_id: 2, price: dec!(100), quantity: dec!(1), side: OrderSide::Ask });
+    book.match_order(Order { id: 3, user_id: 3, price: dec!(90), quantity: dec!(1), side: OrderSide::Bid });
+    let trades = book.match_order(Order { id: 4, user_id: 4, price: dec!(100), quantity: dec!(1.5), side: OrderSide::Bid });
+
+    let trade: TradeEvent = serde_json::from_str(&serde_json::to_string(&trades[0]).unwrap()).unwrap();
+    assert_eq!((trade.trade_id, trade.quantity), (1, dec!(1.5)));
+
+    let mut restored: OrderBook = serde_json::from_str(&serde_json::to_string(&book).unwrap()).unwrap();
+    assert_eq!(restored.order_status(1).unwrap().status, OrderStatus::PartiallyFilled);
+    assert_eq!(restored.order_status(4).unwrap().status, OrderStatus::Filled);
+    assert_eq!(restored.user_order_count(2), 1);
+
+    // 恢复后的订单簿继续撮合，队列顺序与成交号连续
+    let trades = restored.match_order(Order { id: 5, user_id: 5, price: dec!(100), quantity: dec!(1), side: OrderSide::Bid });
+    assert_eq!(trades.iter().map(|t| (t.trade_id, t.maker_order_id)).collect::<Vec<_>>(), vec![(2, 1), (3, 2)]);
+}
@@ -0,0 +1,159 @@
+// tests/session_test.rs
+
+use rust_decimal_macros::dec;
+use mach_rs::{AccountManager, OrderBook, Order, OrderSide, Asset, Instrument, SessionManager, SessionError, Error, Settlement};
+
+fn setup() -> (AccountManager, OrderBook, Settlement, Instrument) {
+    let mut account = AccountManager::new();
+    let btc = Asset::from("BTC");
+    let usdt = Asset::from("USDT");
+    account.deposit(1, btc, dec!(10)).unwrap();
+    account.deposit(1, usdt, dec!(1000)).unwrap();
+    account.deposit(2, usdt, dec!(1000)).unwrap();
+    let pair = Instrument::new(btc, usdt);
+    (account, OrderBook::new(), Settlement::new(pair, 8, 2), pair)
+}
+
+#[test]
+fn test_logout_cancels_resting_orders() {
+    let (mut account, mut book, mut settlement, pair) = setup();
+    let mut sessions = SessionManager::new(10);
+
+    let sid = sessions.logon(1, true);
+    sessions.place_order(sid, Order { id: 1, user_id: 1, price: dec!(100), quantity: dec!(2), side: OrderSide::Bid }, &mut book, &mut account, &mut settlement).unwrap();
+    sessions.place_order(sid, Order { id: 2, user_id: 1, price: dec!(120), quantity: dec!(3), side: OrderSide::Ask }, &mut book, &mut account, &mut settlement).unwrap();
+    assert_eq!(account.get_balance(1, pair.quote), (dec!(800), dec!(200)));
+    assert_eq!(account.get_balance(1, pair.base), (dec!(7), dec!(3)));
+
+    let cancelled = sessions.logout(sid, &mut book, &mut account, &mut settlement).unwrap();
+    assert_eq!(cancelled.len(), 2);
+    assert_eq!(account.get_balance(1, pair.quote), (dec!(1000), dec!(0)));
+    assert_eq!(account.get_balance(1, pair.base), (dec!(10), dec!(0)));
+    assert!(sessions.session(sid).is_none());
+}
+
+#[test]
+fn test_heartbeat_timeout_only_cancels_cod_sessions() {
+    let (mut account, mut book, mut settlement, pair) = setup();
+    let mut sessions = SessionManager::new(10);
+
+    let cod = sessions.logon(1, true);
+    let keep = sessions.logon(2, false);
+    sessions.place_order(cod, Order { id: 1, user_id: 1, price: dec!(100), quantity: dec!(1), side: OrderSide::Bid }, &mut book, &mut account, &mut settlement).unwrap();
+    sessions.place_order(keep, Order { id: 2, user_id: 2, price: dec!(99), quantity: dec!(1), side: OrderSide::Bid }, &mut book, &mut account, &mut settlement).unwrap();
+
+    // 时钟推进到 5，心跳仍有效
+    assert!(sessions.advance_clock(5, &mut book, &mut account, &mut settlement).unwrap().is_empty());
+    sessions.heartbeat(cod).unwrap();
+
+    // 超过 5 + 10 后会话失效
+    let cancelled = sessions.advance_clock(16, &mut book, &mut account, &mut settlement).unwrap();
+    assert_eq!(cancelled.iter().map(|o| o.id).collect::<Vec<_>>(), vec![1]);
+    assert_eq!(account.get_balance(1, pair.quote), (dec!(1000), dec!(0)));
+
+    // 非 COD 会话的订单留在订单簿上
+    assert_eq!(book.user_order_count(2), 1);
+    assert!(sessions.session(keep).is_none());
+    assert_eq!(sessions.heartbeat(cod), Err(SessionError::SessionNotFound { session_id: cod }));
+}
+
+#[test]
+fn test_filled_orders_are_skipped_on_disconnect() {
+    let (mut account, mut book, mut settlement, pair) = setup();
+    let mut sessions = SessionManager::new(10);
+
+    let sid = sessions.logon(1, true);
+    sessions.place_order(sid, Order { id: 1, user_id: 1, price: dec!(100), quantity: dec!(1), side: OrderSide::Ask }, &mut book, &mut account, &mut settlement).unwrap();
+    let taker = sessions.logon(2, false);
+    let trades = sessions.place_order(taker, Order { id: 2, user_id: 2, price: dec!(100), quantity: dec!(1), side: OrderSide::Bid }, &mut book, &mut account, &mut settlement).unwrap();
+    assert_eq!(trades.len(), 1);
+    assert_eq!(account.get_balance(1, pair.base), (dec!(9), dec!(0)));
+
+    assert!(sessions.logout(sid, &mut book, &mut account, &mut settlement).unwrap().is_empty());
+}
+
+#[test]
+fn test_kill_switch_blocks_new_orders() {
+    let (mut account, mut book, mut settlement, pair) = setup();
+    let mut sessions = SessionManager::new(10);
+    let sid = sessions.logon(1, false);
+
+    sessions.activate_kill_switch(1);
+    let result = sessions.place_order(sid, Order { id: 1, user_id: 1, price: dec!(100), quantity: dec!(1), side: OrderSide::Bid }, &mut book, &mut account, &mut settlement);
+    assert_eq!(result.unwrap_err(), Error::Session(SessionError::KillSwitchActive { user_id: 1 }));
+    // 被拒绝的订单不冻结资金
+    assert_eq!(account.get_balance(1, pair.quote), (dec!(1000), dec!(0)));
+
+    sessions.reset_kill_switch(1);
+    assert!(sessions.place_order(sid, Order { id: 1, user_id: 1, price: dec!(100), quantity: dec!(1), side: OrderSide::Bid }, &mut book, &mut account, &mut settlement).is_ok());
+
+    let result = sessions.place_order(sid, Order { id: 3, user_id: 2, price: dec!(100), quantity: dec!(1), side: OrderSide::Bid }, &mut book, &mut account, &mut settlement);
+    assert_eq!(result.unwrap_err().code(), "USER_MISMATCH");
+}
+
+#[test]
+fn test_fills_prune_tracked_orders_and_logout_unlocks_remainder() {
+    let (mut account, mut book, mut settlement, pair) = setup();
+    let mut sessions = SessionManager::new(10);
+
+    let maker = sessions.logon(1, true);
+    let taker = sessions.logon(2, true);
+    sessions.place_order(maker, Order { id: 1, user_id: 1, price: dec!(100), quantity: dec!(1), side: OrderSide::Ask }, &mut book, &mut account, &mut settlement).unwrap();
+    sessions.place_order(maker, Order { id: 2, user_id: 1, price: dec!(90), quantity: dec!(3), side: OrderSide::Bid }, &mut book, &mut account, &mut settlement).unwrap();
+    assert_eq!(sessions.session(maker).unwrap().order_count(), 2);
+
+    // 吃掉卖单，并部分成交买单；成交经会话层转发
+    let trades = sessions.place_order(taker, Order { id: 3, user_id: 2, price: dec!(100), quantity: dec!(1), side: OrderSide::Bid }, &mut book, &mut account, &mut settlement).unwrap();
+    assert_eq!(trades.len(), 1);
+    assert_eq!(sessions.session(maker).unwrap().order_count(), 1);
+    assert_eq!(sessions.session(taker).unwrap().order_count(), 0);
+
+    // 不经过会话层的撮合: 调用方结算后转发成交
+    account.deposit(2, pair.base, dec!(1)).unwrap();
+    let ask = Order { id: 4, user_id: 2, price: dec!(90), quantity: dec!(1), side: OrderSide::Ask };
+    settlement.reserve(&mut account, &ask).unwrap();
+    let trades = book.match_order(ask);
+    settlement.settle(&mut account, &trades).unwrap();
+    sessions.on_trades(&trades, &settlement);
+    assert_eq!(sessions.session(maker).unwrap().order_count(), 1);
+
+    // 剩余 2 @ 90 解冻，已成交部分已结算
+    let cancelled = sessions.logout(maker, &mut book, &mut account, &mut settlement).unwrap();
+    assert_eq!(cancelled.iter().map(|o| o.quantity).collect::<Vec<_>>(), vec![dec!(2)]);
+    assert_eq!(account.get_balance(1, pair.quote), (dec!(1010), dec!(0)));
+    assert_eq!(account.get_balance(1, pair.base), (dec!(10), dec!(0)));
+}
+
+#[test]
+fn test_price_improvement_is_released_per_fill() {
+    let (mut account, mut book, mut settlement, pair) = setup();
+    let mut sessions = SessionManager::new(10);
+    let maker = sessions.logon(1, true);
+    let taker = sessions.logon(2, false);
+
+    sessions.place_order(maker, Order { id: 1, user_id: 1, price: dec!(100), quantity: dec!(1), side: OrderSide::Ask }, &mut book, &mut account, &mut settlement).unwrap();
+    // 限价 110 买 3 个，以 100 成交 1 个: 改善的 10 立即退回，剩余 2 个按 110 冻结
+    sessions.place_order(taker, Order { id: 2, user_id: 2, price: dec!(110), quantity: dec!(3), side: OrderSide::Bid }, &mut book, &mut account, &mut settlement).unwrap();
+    assert_eq!(account.get_balance(2, pair.quote), (dec!(680), dec!(220)));
+    assert_eq!(sessions.session(taker).unwrap().order_count(), 1);
+}
+
+#[test]
+fn test_amend_adjusts_frozen_funds() {
+    let (mut account, mut book, mut settlement, pair) = setup();
+    let mut sessions = SessionManager::new(10);
+    let sid = sessions.logon(2, true);
+    let other = sessions.logon(1, true);
+
+    sessions.place_order(sid, Order { id: 1, user_id: 2, price: dec!(100), quantity: dec!(2), side: OrderSide::Bid }, &mut book, &mut account, &mut settlement).unwrap();
+    sessions.amend_order(sid, 1, dec!(90), dec!(3), &mut book, &mut account, &mut settlement).unwrap();
+    assert_eq!(account.get_balance(2, pair.quote), (dec!(730), dec!(270)));
+
+    // 只能改本会话的挂单
+    let err = sessions.amend_order(other, 1, dec!(80), dec!(1), &mut book, &mut account, &mut settlement).unwrap_err();
+    assert_eq!(err.code(), "ORDER_NOT_FOUND");
+
+    let cancelled = sessions.logout(sid, &mut book, &mut account, &mut settlement).unwrap();
+    assert_eq!(cancelled.len(), 1);
+    assert_eq!(account.get_balance(2, pair.quote), (dec!(1000), dec!(0)));
+}
@@ -0,0 +1,183 @@
+// tests/settlement_test.rs
+
+use rust_decimal_macros::dec;
+use mach_rs::{AccountManager, OrderBook, Order, OrderSide, Asset, Instrument, Auditor, AssetRegistry, Settlement, SettlementError};
+
+const HOUSE: u64 = 99;
+
+fn order(id: u64, user_id: u64, price: rust_decimal::Decimal, quantity: rust_decimal::Decimal, side: OrderSide) -> Order {
+    Order { id, user_id, price, quantity, side }
+}
+
+fn setup() -> (AccountManager, OrderBook, Auditor, Settlement) {
+    let mut registry = AssetRegistry::new();
+    let btc = registry.register("BTC", "Bitcoin", 8).unwrap();
+    let usdt = registry.register("USDT", "Tether USD", 2).unwrap();
+    let pair = Instrument::new(btc, usdt);
+
+    let settlement = Settlement::from_registry(pair, &registry).unwrap();
+    let mut account = AccountManager::with_registry(registry);
+    let mut auditor = Auditor::new();
+    for user in 1..=3 {
+        account.deposit(user, btc, dec!(1)).unwrap();
+        auditor.on_deposit(btc, dec!(1));
+    }
+    account.deposit(4, usdt, dec!(1000)).unwrap();
+    auditor.on_deposit(usdt, dec!(1000));
+
+    (account, OrderBook::new(), auditor, settlement)
+}
+
+#[test]
+fn test_rounding_never_overdraws_and_dust_is_swept() {
+    let (mut account, mut book, auditor, mut settlement) = setup();
+    let btc = Asset::from("BTC");
+    let usdt = Asset::from("USDT");
+
+    for user in 1..=3 {
+        let ask = order(user, user, dec!(0.333), dec!(1), OrderSide::Ask);
+        settlement.reserve(&mut account, &ask).unwrap();
+        book.match_order(ask);
+    }
+    // 0.333 * 3 = 0.999，向上取整冻结 1.00
+    let bid = order(10, 4, dec!(0.333), dec!(3), OrderSide::Bid);
+    assert_eq!(settlement.reserve(&mut account, &bid).unwrap(), dec!(1.00));
+    let trades = book.match_order(bid);
+    assert_eq!(trades.len(), 3);
+    settlement.settle(&mut account, &trades).unwrap();
+
+    // 买方按累计金额取整，总共只扣 1.00，冻结清零
+    assert_eq!(account.get_balance(4, usdt), (dec!(999.00), dec!(0)));
+    assert_eq!(account.get_balance(4, btc), (dec!(3), dec!(0)));
+    // 卖方入账向下取整
+    for user in 1..=3 {
+        assert_eq!(account.get_balance(user, usdt), (dec!(0.33), dec!(0)));
+    }
+    assert_eq!(account.clearing_dust(usdt), dec!(0.01));
+    auditor.audit(&account, &[&settlement]).unwrap();
+
+    assert_eq!(account.sweep_dust(usdt, HOUSE).unwrap(), dec!(0.01));
+    assert_eq!(account.get_balance(HOUSE, usdt), (dec!(0.01), dec!(0)));
+    assert_eq!(account.clearing_dust(usdt), dec!(0));
+    assert_eq!(account.sweep_dust(usdt, HOUSE).unwrap(), dec!(0));
+    auditor.audit(&account, &[&settlement]).unwrap();
+    account.verify_ledger().unwrap();
+}
+
+#[test]
+fn test_price_improvement_is_released_on_fill() {
+    let (mut account, mut book, auditor, mut settlement) = setup();
+    let usdt = Asset::from("USDT");
+
+    let ask = order(1, 1, dec!(100), dec!(1), OrderSide::Ask);
+    settlement.reserve(&mut account, &ask).unwrap();
+    book.match_order(ask);
+
+    // 限价 101 吃到 100 的卖单，多冻结的 1 USDT 在成交后退回
+    let bid = order(2, 4, dec!(101), dec!(1), OrderSide::Bid);
+    settlement.reserve(&mut account, &bid).unwrap();
+    let trades = book.match_order(bid);
+    settlement.settle(&mut account, &trades).unwrap();
+
+    assert_eq!(account.get_balance(4, usdt), (dec!(900), dec!(0)));
+    assert_eq!(settlement.reserved(2), None);
+    auditor.audit(&account, &[&settlement]).unwrap();
+}
+
+#[test]
+fn test_release_on_cancel_and_unknown_orders() {
+    let (mut account, mut book, _, mut settlement) = setup();
+    let btc = Asset::from("BTC");
+
+    let ask = order(1, 1, dec!(10), dec!(1), OrderSide::Ask);
+    settlement.reserve(&mut account, &ask).unwrap();
+    book.match_order(ask);
+
+    let bid = order(2, 4, dec!(10), dec!(0.4), OrderSide::Bid);
+    settlement.reserve(&mut account, &bid).unwrap();
+    let trades = book.match_order(bid);
+    settlement.settle(&mut account, &trades).unwrap();
+    assert_eq!(settlement.reserved(1), Some(dec!(0.6)));
+
+    book.cancel_order(1).unwrap();
+    assert_eq!(settlement.release(&mut account, 1).unwrap(), dec!(0.6));
+    assert_eq!(account.get_balance(1, btc), (dec!(0.6), dec!(0)));
+    assert_eq!(settlement.release(&mut account, 1).unwrap_err(), SettlementError::UnknownOrder { order_id: 1 });
+
+    // 没有通过结算模块冻结的订单无法结算
+    book.match_order(order(3, 2, dec!(10), dec!(1), OrderSide::Ask));
+    let bid = order(4, 4, dec!(10), dec!(1), OrderSide::Bid);
+    settlement.reserve(&mut account, &bid).unwrap();
+    let trades = book.match_order(bid);
+    assert_eq!(settlement.settle(&mut account, &trades).unwrap_err(), SettlementError::UnknownOrder { order_id: 3 });
+}
+
+#[test]
+fn test_price_improvement_is_released_on_partial_fill() {
+    let (mut account, mut book, auditor, mut settlement) = setup();
+    let usdt = Asset::from("USDT");
+
+    let ask = order(1, 1, dec!(100), dec!(0.5), OrderSide::Ask);
+    settlement.reserve(&mut account, &ask).unwrap();
+    book.match_order(ask);
+
+    // 限价 101 买 2 个，以 100 成交 0.5 个: 改善的 0.5 USDT 立即退回，剩余 1.5 个按限价继续冻结
+    let bid = order(2, 4, dec!(101), dec!(2), OrderSide::Bid);
+    assert_eq!(settlement.reserve(&mut account, &bid).unwrap(), dec!(202));
+    let trades = book.match_order(bid);
+    settlement.settle(&mut account, &trades).unwrap();
+
+    assert_eq!(settlement.reserved(2), Some(dec!(151.5)));
+    assert_eq!(account.get_balance(4, usdt), (dec!(798.5), dec!(151.5)));
+    auditor.audit(&account, &[&settlement]).unwrap();
+}
+
+#[test]
+fn test_failed_settlement_changes_nothing() {
+    let (mut account, mut book, _, mut settlement) = setup();
+    let (btc, usdt) = (Asset::from("BTC"), Asset::from("USDT"));
+
+    let ask = order(1, 1, dec!(10), dec!(1), OrderSide::Ask);
+    settlement.reserve(&mut account, &ask).unwrap();
+    book.match_order(ask);
+    // 第二个卖单没有经过结算模块
+    book.match_order(order(2, 2, dec!(11), dec!(1), OrderSide::Ask));
+
+    let bid = order(3, 4, dec!(11), dec!(2), OrderSide::Bid);
+    settlement.reserve(&mut account, &bid).unwrap();
+    let trades = book.match_order(bid);
+    assert_eq!(trades.len(), 2);
+    assert_eq!(settlement.settle(&mut account, &trades).unwrap_err(), SettlementError::UnknownOrder { order_id: 2 });
+
+    // 第一笔成交也没有被结算
+    assert_eq!(account.get_balance(1, btc), (dec!(0), dec!(1)));
+    assert_eq!(account.get_balance(4, usdt), (dec!(978), dec!(22)));
+    assert_eq!(settlement.reserved(1), Some(dec!(1)));
+    assert_eq!(settlement.reserved(3), Some(dec!(22)));
+    account.verify_ledger().unwrap();
+}
+
+#[test]
+fn test_check_rejects_before_the_book_changes() {
+    let (mut account, mut book, _, mut settlement) = setup();
+
+    // 没有经过结算模块的卖单: 预检失败时订单簿和冻结都不变
+    book.match_order(order(1, 1, dec!(10), dec!(1), OrderSide::Ask));
+    let bid = order(2, 4, dec!(10), dec!(1), OrderSide::Bid);
+    let preview = book.preview_match(&bid);
+    assert_eq!(settlement.check(&account, &preview, Some(&bid)).unwrap_err(), SettlementError::UnknownOrder { order_id: 1 });
+    assert_eq!(book.user_order_count(1), 1);
+    assert_eq!(account.get_balance(4, Asset::from("USDT")), (dec!(1000), dec!(0)));
+
+    // 预演的成交与实际撮合一致
+    book.cancel_order(1).unwrap();
+    let ask = order(3, 2, dec!(10), dec!(0.4), OrderSide::Ask);
+    settlement.reserve(&mut account, &ask).unwrap();
+    book.match_order(ask);
+    let bid = order(4, 4, dec!(11), dec!(1), OrderSide::Bid);
+    let preview = book.preview_match(&bid);
+    settlement.check(&account, &preview, Some(&bid)).unwrap();
+    settlement.reserve(&mut account, &bid).unwrap();
+    assert_eq!(book.match_order(bid), preview);
+    settlement.settle(&mut account, &preview).unwrap();
+}
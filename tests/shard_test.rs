@@ -0,0 +1,90 @@
+// tests/shard_test.rs
+
+use std::thread;
+use rust_decimal_macros::dec;
+use mach_rs::{AccountError, AccountManager, Asset, Command, Error, Instrument, Order, OrderSide, ShardError, ShardedEngine};
+
+fn usdt() -> Asset {
+    Asset::from("USDT")
+}
+
+fn pairs() -> [Instrument; 2] {
+    [Instrument::new(Asset::from("BTC"), usdt()), Instrument::new(Asset::from("ETH"), usdt())]
+}
+
+fn bid(id: u64, user_id: u64, price: rust_decimal::Decimal) -> Command {
+    Command::Place(Order { id, user_id, price, quantity: dec!(1), side: OrderSide::Bid })
+}
+
+#[test]
+fn test_funds_cannot_be_overcommitted_across_shards() {
+    let [btc, eth] = pairs();
+    let engine = ShardedEngine::builder(AccountManager::new()).instrument(btc, 8, 2).instrument(eth, 8, 2).spawn(16);
+    let handle = engine.handle();
+    handle.call(btc, Command::Deposit { user_id: 1, asset: usdt(), amount: dec!(1000) }).unwrap().unwrap();
+
+    // 两个分片同时为同一用户冻结 100 次 10 USDT，总共只够 100 次
+    let workers: Vec<_> = [btc, eth].into_iter().enumerate()
+        .map(|(n, instrument)| {
+            let handle = handle.clone();
+            thread::spawn(move || {
+                (0..100u64).filter(|i| handle.call(instrument, bid(n as u64 * 1000 + i, 1, dec!(10))).unwrap().is_ok()).count()
+            })
+        })
+        .collect();
+    let accepted: usize = workers.into_iter().map(|w| w.join().unwrap()).sum();
+    assert_eq!(accepted, 100);
+
+    assert_eq!(handle.query_accounts(|a| a.get_balance(1, usdt())).unwrap(), (dec!(0), dec!(1000)));
+
+    let rejected = handle.call(eth, bid(9999, 1, dec!(10))).unwrap();
+    assert!(matches!(rejected, Err(Error::Account(AccountError::InsufficientAvailable { .. }))));
+
+    drop(handle);
+    let (_, books) = engine.shutdown();
+    let open: usize = books.values().map(|b| b.user_order_count(1)).sum();
+    assert_eq!(open, 100);
+}
+
+#[test]
+fn test_shards_match_and_settle_independently() {
+    let [btc, eth] = pairs();
+    let engine = ShardedEngine::builder(AccountManager::new()).instrument(btc, 8, 2).instrument(eth, 8, 2).spawn(4);
+    let handle = engine.handle();
+    handle.call(btc, Command::Deposit { user_id: 1, asset: Asset::from("BTC"), amount: dec!(1) }).unwrap().unwrap();
+    handle.call(eth, Command::Deposit { user_id: 1, asset: Asset::from("ETH"), amount: dec!(1) }).unwrap().unwrap();
+    handle.call(btc, Command::Deposit { user_id: 2, asset: usdt(), amount: dec!(500) }).unwrap().unwrap();
+
+    let ask = |id, price| Command::Place(Order { id, user_id: 1, price, quantity: dec!(1), side: OrderSide::Ask });
+    handle.call(btc, ask(1, dec!(300))).unwrap().unwrap();
+    handle.call(eth, ask(1, dec!(200))).unwrap().unwrap(); // 订单号只需在分片内唯一
+    assert_eq!(handle.call(btc, bid(2, 2, dec!(300))).unwrap().unwrap().len(), 1);
+    assert_eq!(handle.call(eth, bid(2, 2, dec!(200))).unwrap().unwrap().len(), 1);
+
+    let usdt_balance = handle.query_accounts(|a| (a.get_balance(1, usdt()).0, a.get_balance(2, usdt()).0)).unwrap();
+    assert_eq!(usdt_balance, (dec!(500), dec!(0)));
+
+    handle.call(btc, Command::Cancel { order_id: 1 }).unwrap().unwrap_err();
+    let unknown = Instrument::new(Asset::from("SOL"), usdt());
+    assert_eq!(handle.call(unknown, Command::Cancel { order_id: 1 }).err(), Some(ShardError::UnknownInstrument { instrument: unknown }));
+
+    drop(handle);
+    let (account, books) = engine.shutdown();
+    assert_eq!(account.get_balance(2, Asset::from("ETH")).0, dec!(1));
+    assert_eq!(books.len(), 2);
+}
+
+#[test]
+fn test_shutdown_does_not_wait_for_live_handles() {
+    let [btc, _] = pairs();
+    let engine = ShardedEngine::builder(AccountManager::new()).instrument(btc, 8, 2).spawn(4);
+    let handle = engine.handle();
+    handle.call(btc, Command::Deposit { user_id: 2, asset: usdt(), amount: dec!(500) }).unwrap().unwrap();
+    handle.call(btc, bid(1, 2, dec!(100))).unwrap().unwrap();
+
+    let (account, books) = engine.shutdown();
+    assert_eq!(account.get_balance(2, usdt()), (dec!(400), dec!(100)));
+    assert_eq!(books[&btc].user_order_count(2), 1);
+    assert_eq!(handle.call(btc, bid(2, 2, dec!(100))).err(), Some(ShardError::Stopped));
+    assert_eq!(handle.query_accounts(|a| a.get_balance(2, usdt())).err(), Some(ShardError::Stopped));
+}
@@ -0,0 +1,120 @@
+// tests/snapshot_test.rs
+
+use rust_decimal_macros::dec;
+use mach_rs::{Asset, Command, Exchange, Instrument, Journal, Order, OrderSide, OrderStatus, SnapshotError};
+use mach_rs::journal::read_journal;
+use mach_rs::snapshot::{self, read_snapshot, verify_snapshot, write_snapshot};
+
+fn pair() -> Instrument {
+    Instrument::new(Asset::from("BTC"), Asset::from("USDT"))
+}
+
+fn place(id: u64, user_id: u64, price: rust_decimal::Decimal, quantity: rust_decimal::Decimal, side: OrderSide) -> Command {
+    Command::Place(Order { id, user_id, price, quantity, side })
+}
+
+fn head_commands() -> Vec<Command> {
+    vec![
+        Command::Deposit { user_id: 1, asset: Asset::from("BTC"), amount: dec!(5) },
+        Command::Deposit { user_id: 2, asset: Asset::from("USDT"), amount: dec!(1000) },
+        place(1, 1, dec!(100), dec!(1), OrderSide::Ask),
+        place(2, 1, dec!(100), dec!(1), OrderSide::Ask),
+        place(3, 1, dec!(101), dec!(2), OrderSide::Ask),
+        place(4, 2, dec!(90), dec!(1), OrderSide::Bid),
+        place(5, 2, dec!(100.333), dec!(1.5), OrderSide::Bid),
+        Command::Cancel { order_id: 4 },
+    ]
+}
+
+#[test]
+fn test_restore_then_replay_tail_matches_live_state() {
+    let mut live = Exchange::new(pair(), 8, 2);
+    let mut journal = Journal::new(Vec::new());
+    for command in head_commands() {
+        let _ = live.execute(&mut journal, &command);
+    }
+
+    let mut snap = Vec::new();
+    write_snapshot(&live, &mut snap).unwrap();
+    assert_eq!(verify_snapshot(&snap[..]).unwrap().last_seq, 8);
+
+    // 快照之后的日志尾部
+    for command in [
+        place(6, 2, dec!(101), dec!(1), OrderSide::Bid),
+        Command::Amend { order_id: 3, price: dec!(99), quantity: dec!(0.5) },
+    ] {
+        let _ = live.execute(&mut journal, &command);
+    }
+
+    let records = read_journal(&journal.into_inner()[..]).unwrap();
+    let mut recovered = read_snapshot(&snap[..]).unwrap();
+    assert_eq!(recovered.last_seq(), 8);
+    assert_eq!(mach_rs::journal::replay(&records, &mut recovered).unwrap(), 2);
+
+    assert_eq!(recovered.last_seq(), live.last_seq());
+    for user in 1..=2 {
+        assert_eq!(recovered.account().balances_of(user), live.account().balances_of(user));
+        assert_eq!(
+            recovered.book().open_orders(user).iter().map(|r| (r.order_id, r.remaining_quantity)).collect::<Vec<_>>(),
+            live.book().open_orders(user).iter().map(|r| (r.order_id, r.remaining_quantity)).collect::<Vec<_>>()
+        );
+    }
+    // 终态记录与成交均价也随快照恢复
+    let report = recovered.book().order_status(4).unwrap();
+    assert_eq!(report.status, OrderStatus::Cancelled);
+    assert_eq!(recovered.book().order_status(5).unwrap().average_price, live.book().order_status(5).unwrap().average_price);
+    recovered.account().verify_ledger().unwrap();
+
+    // 队列顺序与成交号保持一致
+    let next = place(7, 2, dec!(101), dec!(3), OrderSide::Bid);
+    assert_eq!(live.apply(11, &next).unwrap(), recovered.apply(11, &next).unwrap());
+    assert_eq!(recovered.account().balances_of(1), live.account().balances_of(1));
+}
+
+#[test]
+fn test_snapshot_integrity_checks() {
+    let mut ex = Exchange::new(pair(), 8, 2);
+    for (seq, command) in head_commands().iter().enumerate() {
+        let _ = ex.apply(seq as u64 + 1, command);
+    }
+    let mut snap = Vec::new();
+    write_snapshot(&ex, &mut snap).unwrap();
+
+    let mut corrupted = snap.clone();
+    let last = corrupted.len() - 1;
+    corrupted[last] ^= 0x01;
+    assert_eq!(read_snapshot(&corrupted[..]).err(), Some(SnapshotError::Corrupted));
+    assert_eq!(read_snapshot(&snap[..snap.len() - 3]).err(), Some(SnapshotError::Corrupted));
+    assert_eq!(read_snapshot(&b"not a snapshot"[..]).err(), Some(SnapshotError::BadMagic));
+
+    // 首个发布的格式版本为 1
+    assert_eq!(verify_snapshot(&snap[..]).unwrap().version, 1);
+    let mut future = snap.clone();
+    future[8] = 9;
+    assert_eq!(verify_snapshot(&future[..]).unwrap_err(), SnapshotError::UnsupportedVersion { version: 9 });
+}
+
+#[test]
+fn test_save_and_recover_from_files() {
+    let dir = std::env::temp_dir().join(format!("mach_snapshot_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (snap_path, log_path) = (dir.join("state.snap"), dir.join("commands.log"));
+    let _ = std::fs::remove_file(&log_path);
+
+    let mut live = Exchange::new(pair(), 8, 2);
+    let (mut journal, _) = Journal::open(&log_path).unwrap();
+    for command in head_commands() {
+        let _ = live.execute(&mut journal, &command);
+    }
+    snapshot::save_snapshot(&live, &snap_path).unwrap();
+    let _ = live.execute(&mut journal, &place(6, 2, dec!(101), dec!(1), OrderSide::Bid));
+    drop(journal);
+
+    // 进程重启
+    let (_, records) = Journal::open(&log_path).unwrap();
+    let recovered = snapshot::recover(&snap_path, &records).unwrap();
+    assert_eq!(recovered.last_seq(), 9);
+    assert_eq!(recovered.account().balances_of(2), live.account().balances_of(2));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,55 @@
+// tests/transfer_test.rs
+
+use rust_decimal_macros::dec;
+use mach_rs::{AccountManager, AccountError, Asset, EntryReason, EntryRef};
+
+#[test]
+fn test_transfer_between_users_is_atomic_and_journaled() {
+    let mut account = AccountManager::new();
+    let usdt = Asset::from("USDT");
+    account.deposit(1, usdt, dec!(100)).unwrap();
+
+    account.transfer(1, 2, usdt, dec!(40), 9001).unwrap();
+    assert_eq!(account.get_balance(1, usdt), (dec!(60), dec!(0)));
+    assert_eq!(account.get_balance(2, usdt), (dec!(40), dec!(0)));
+
+    let entry = account.ledger().entries_for(2, usdt)[0].clone();
+    assert_eq!((entry.reason, entry.reference), (EntryReason::Transfer, EntryRef::Transfer(9001)));
+    assert!(account.verify_ledger().is_ok());
+
+    // 余额不足时双方都不变
+    let err = account.transfer(1, 2, usdt, dec!(61), 9002).unwrap_err();
+    assert!(matches!(err, AccountError::InsufficientAvailable { user_id: 1, .. }));
+    assert_eq!(account.get_balance(1, usdt), (dec!(60), dec!(0)));
+    assert_eq!(account.get_balance(2, usdt), (dec!(40), dec!(0)));
+
+    assert_eq!(account.transfer(1, 1, usdt, dec!(1), 9003), Err(AccountError::SelfTransfer { user_id: 1 }));
+    assert!(matches!(account.transfer(1, 2, usdt, dec!(0), 9004), Err(AccountError::InvalidAmount { .. })));
+}
+
+#[test]
+fn test_sub_account_hierarchy() {
+    let mut account = AccountManager::new();
+    let btc = Asset::from("BTC");
+    account.deposit(10, btc, dec!(5)).unwrap();
+
+    account.add_sub_account(10, 11).unwrap();
+    account.add_sub_account(10, 12).unwrap();
+    account.add_sub_account(11, 13).unwrap();
+    assert_eq!(account.sub_accounts(10), &[11, 12]);
+    assert_eq!(account.parent_of(13), Some(11));
+
+    // 一个子账户只能有一个母账户，不能形成循环
+    assert!(account.add_sub_account(12, 11).is_err());
+    assert!(account.add_sub_account(13, 10).is_err());
+
+    account.sub_account_transfer(10, 11, btc, dec!(2), 1).unwrap();
+    account.sub_account_transfer(11, 10, btc, dec!(1), 2).unwrap();
+    assert_eq!(account.get_balance(10, btc), (dec!(4), dec!(0)));
+    assert_eq!(account.get_balance(11, btc), (dec!(1), dec!(0)));
+
+    // 兄弟账户之间不是母子关系
+    assert!(matches!(account.sub_account_transfer(11, 12, btc, dec!(1), 3), Err(AccountError::NotSubAccount { .. })));
+    // 跨层级也不允许
+    assert!(matches!(account.sub_account_transfer(10, 13, btc, dec!(1), 4), Err(AccountError::NotSubAccount { .. })));
+}
@@ -0,0 +1,92 @@
+// tests/withdrawal_test.rs
+
+use rust_decimal_macros::dec;
+use mach_rs::{AccountManager, Asset, Auditor, LedgerAccount, WithdrawalManager, WithdrawalPolicy, WithdrawalStatus, WithdrawalError};
+
+fn setup() -> (AccountManager, WithdrawalManager, Auditor, Asset) {
+    let usdt = Asset::from("USDT");
+    let mut account = AccountManager::new();
+    let mut auditor = Auditor::new();
+    account.deposit(1, usdt, dec!(100)).unwrap();
+    auditor.on_deposit(usdt, dec!(100));
+
+    let mut withdrawals = WithdrawalManager::new();
+    withdrawals.set_policy(usdt, WithdrawalPolicy { min_amount: dec!(10), fee: dec!(1) });
+    (account, withdrawals, auditor, usdt)
+}
+
+#[test]
+fn test_withdrawal_completed_burns_funds() {
+    let (mut account, mut withdrawals, mut auditor, usdt) = setup();
+
+    let id = withdrawals.request(&mut account, 1, usdt, dec!(30), "w-1").unwrap();
+    assert_eq!(account.get_balance(1, usdt), (dec!(70), dec!(0)));
+    assert_eq!(withdrawals.get(id).unwrap().status, WithdrawalStatus::Pending);
+
+    // 未审核不能完成
+    assert!(matches!(withdrawals.complete(&mut account, id), Err(WithdrawalError::InvalidState { .. })));
+
+    withdrawals.approve(id).unwrap();
+    let paid = withdrawals.complete(&mut account, id).unwrap();
+    assert_eq!(paid, dec!(29));
+    auditor.on_withdrawal(usdt, paid);
+
+    assert_eq!(withdrawals.get(id).unwrap().status, WithdrawalStatus::Completed);
+    assert_eq!(account.ledger().balance_of(LedgerAccount::Fees, usdt), dec!(1));
+    assert!(account.verify_ledger().is_ok());
+    assert!(auditor.check_conservation(&account).is_ok());
+
+    // 已完成不能再拒绝
+    assert!(matches!(withdrawals.reject(&mut account, id), Err(WithdrawalError::InvalidState { .. })));
+}
+
+#[test]
+fn test_rejected_withdrawal_returns_funds() {
+    let (mut account, mut withdrawals, auditor, usdt) = setup();
+
+    let id = withdrawals.request(&mut account, 1, usdt, dec!(50), "w-1").unwrap();
+    // 审核中资金仍计入总量
+    assert!(auditor.check_conservation(&account).is_ok());
+
+    withdrawals.approve(id).unwrap();
+    withdrawals.reject(&mut account, id).unwrap();
+
+    assert_eq!(account.get_balance(1, usdt), (dec!(100), dec!(0)));
+    assert_eq!(withdrawals.get(id).unwrap().status, WithdrawalStatus::Rejected);
+    assert!(account.verify_ledger().is_ok());
+}
+
+#[test]
+fn test_idempotency_and_minimum() {
+    let (mut account, mut withdrawals, _auditor, usdt) = setup();
+
+    let id = withdrawals.request(&mut account, 1, usdt, dec!(20), "w-1").unwrap();
+    // 重试返回同一笔，不重复冻结
+    assert_eq!(withdrawals.request(&mut account, 1, usdt, dec!(20), "w-1").unwrap(), id);
+    assert_eq!(account.get_balance(1, usdt), (dec!(80), dec!(0)));
+
+    assert!(matches!(
+        withdrawals.request(&mut account, 1, usdt, dec!(25), "w-1"),
+        Err(WithdrawalError::IdempotencyConflict { .. })
+    ));
+    assert_eq!(
+        withdrawals.request(&mut account, 1, usdt, dec!(5), "w-2"),
+        Err(WithdrawalError::BelowMinimum { asset: usdt, amount: dec!(5), minimum: dec!(10) })
+    );
+    // 余额不足的申请不占用幂等键
+    assert!(matches!(withdrawals.request(&mut account, 1, usdt, dec!(500), "w-3"), Err(WithdrawalError::Account(_))));
+    assert!(withdrawals.request(&mut account, 1, usdt, dec!(50), "w-3").is_ok());
+}
+
+#[test]
+fn test_idempotency_keys_are_scoped_per_user() {
+    let (mut account, mut withdrawals, _auditor, usdt) = setup();
+    account.deposit(2, usdt, dec!(100)).unwrap();
+
+    let first = withdrawals.request(&mut account, 1, usdt, dec!(20), "w-1").unwrap();
+    // 其他用户使用相同的键得到自己的提现单
+    let second = withdrawals.request(&mut account, 2, usdt, dec!(20), "w-1").unwrap();
+    assert_ne!(first, second);
+    assert_eq!(withdrawals.get(second).unwrap().user_id, 2);
+    assert_eq!(account.get_balance(2, usdt), (dec!(80), dec!(0)));
+}